@@ -0,0 +1,81 @@
+use aoc_2020::cache::Cache;
+use aoc_2020::input::read_day;
+use aoc_2020::registry::solutions;
+use aoc_2020::{day23, day5};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::env;
+use std::hint::black_box;
+
+/// Parses and solves every day on its real input, and loads the parsed input from the cache, which is kept in
+/// `AOC_CACHE_DIR` or the temporary directory. Filter with e.g. `cargo bench -- day11/`.
+fn days(c: &mut Criterion) {
+    let cache = Cache::from_env().unwrap_or_else(|| Cache::new(env::temp_dir().join("aoc-cache")));
+    for solution in solutions() {
+        let day = solution.day();
+        let input = read_day(day);
+
+        let mut group = c.benchmark_group(format!("day{}", day));
+        // Some days take seconds per iteration, so keep the sample count at the minimum.
+        group.sample_size(10);
+        group.bench_function("parse", |b| {
+            b.iter(|| solution.parse(black_box(&input)).unwrap())
+        });
+
+        let (parsed, _) = cache.parse(*solution, &input).unwrap();
+        group.bench_function("load", |b| {
+            b.iter(|| cache.load(*solution, black_box(&input)).unwrap())
+        });
+
+        for part in 1..=2 {
+            group.bench_function(format!("part{}", part), |b| {
+                b.iter(|| solution.solve(part, black_box(parsed.as_ref())))
+            });
+        }
+        group.finish();
+    }
+}
+
+/// Decoding boarding passes by binary search versus reading them as binary numbers.
+fn day5_variants(c: &mut Criterion) {
+    let passes = day5::read_file();
+
+    let mut group = c.benchmark_group("day5/variants");
+    group.bench_function("decode_pass", |b| {
+        b.iter(|| {
+            passes
+                .iter()
+                .map(|pass| day5::decode_pass(black_box(pass)).seat())
+                .max()
+        })
+    });
+    group.bench_function("line_to_seat", |b| {
+        b.iter(|| {
+            passes
+                .iter()
+                .map(|pass| day5::line_to_seat(&mut black_box(pass).chars()))
+                .max()
+        })
+    });
+    group.finish();
+}
+
+/// Moving cups around a `Vec` versus following links in a successor table.
+fn day23_variants(c: &mut Criterion) {
+    let cups = day23::read_file().unwrap();
+
+    let mut group = c.benchmark_group("day23/variants");
+    for moves in [10, 100, 1000].iter() {
+        group.bench_with_input(BenchmarkId::new("play_cups", moves), moves, |b, moves| {
+            b.iter(|| day23::play_cups(black_box(&cups), *moves))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("play_cups_faster", moves),
+            moves,
+            |b, moves| b.iter(|| day23::play_cups_faster(black_box(&cups), *moves)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, days, day5_variants, day23_variants);
+criterion_main!(benches);
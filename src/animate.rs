@@ -0,0 +1,250 @@
+//! Plays the simulation days back in the terminal, one generation per frame: the seating of day 11, the cube slices
+//! of day 17, the card games of day 22 and the hex floor of day 24.
+use crate::day11::{self, Position};
+use crate::day17;
+use crate::day22;
+use crate::day24;
+use crate::solution::lines;
+use itertools::Itertools;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// One generation of a simulation, drawn as text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub generation: usize,
+    /// The number of live cells: occupied seats, active cubes, cards held by player 1 or black tiles.
+    pub population: usize,
+    pub text: String,
+}
+
+/// Draws every generation of the day's simulation for `part`.
+pub fn frames(day: u8, part: u8, input: &str) -> Result<Vec<Frame>, String> {
+    let frames = match day {
+        11 => {
+            let layout = day11::parse_layout(input).map_err(|e| e.to_string())?;
+            day11::seating_rounds(&layout, part)
+                .iter()
+                .enumerate()
+                .map(|(generation, round)| Frame {
+                    generation,
+                    population: round.count(|p| *p == Position::Occupied),
+                    text: round.render(|p| match p {
+                        Position::Floor => '.',
+                        Position::Empty => 'L',
+                        Position::Occupied => '#',
+                    }),
+                })
+                .collect()
+        }
+        17 => {
+            let active = day17::parse_slice(input).map_err(|e| e.to_string())?;
+            if part == 1 {
+                cube_frames(&day17::boot_cycles::<3>(&active, 6))
+            } else {
+                cube_frames(&day17::boot_cycles::<4>(&active, 6))
+            }
+        }
+        22 => {
+            let (mut deck1, mut deck2) =
+                day22::parse_decks(lines(input)).map_err(|e| e.to_string())?;
+            let mut frames = Vec::new();
+            let observe = |deck1: &VecDeque<u64>, deck2: &VecDeque<u64>| {
+                frames.push(Frame {
+                    generation: frames.len(),
+                    population: deck1.len(),
+                    text: format!(
+                        "Player 1: {}\nPlayer 2: {}\n",
+                        deck1.iter().join(", "),
+                        deck2.iter().join(", ")
+                    ),
+                })
+            };
+            if part == 1 {
+                day22::combat_with(&mut deck1, &mut deck2, observe);
+            } else {
+                day22::recursive_combat_with(&mut deck1, &mut deck2, observe);
+            }
+            frames
+        }
+        24 => {
+            let coords = day24::parse_coords(lines(input)).map_err(|e| e.to_string())?;
+            let turns = if part == 1 { 0 } else { 100 };
+            let days = day24::floor_days(&coords, turns);
+            day24::floor_frames(&days)
+                .iter()
+                .zip(days.iter())
+                .enumerate()
+                .map(|(generation, (frame, black))| Frame {
+                    generation,
+                    population: black.len(),
+                    text: frame.render(|black| if *black { '#' } else { '.' }),
+                })
+                .collect()
+        }
+        _ => return Err(format!("Day {} has no simulation", day)),
+    };
+    Ok(frames)
+}
+
+fn cube_frames<const N: usize>(states: &[Vec<[i64; N]>]) -> Vec<Frame> {
+    day17::slice_frames(states)
+        .iter()
+        .zip(states.iter())
+        .enumerate()
+        .map(|(generation, (frame, active))| Frame {
+            generation,
+            population: active.len(),
+            text: frame.render(|cube| match cube {
+                Some(true) => '#',
+                Some(false) => '.',
+                None => ' ',
+            }),
+        })
+        .collect()
+}
+
+/// What the viewer asked for while frames are playing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Pauses, or resumes if paused.
+    Toggle,
+    /// Shows the next frame while paused.
+    Step,
+    Quit,
+}
+
+impl Command {
+    /// Reads a line typed in the terminal: an empty line toggles pausing, `n` steps and `q` quits.
+    pub fn parse(line: &str) -> Option<Command> {
+        match line.trim() {
+            "" => Some(Command::Toggle),
+            "n" => Some(Command::Step),
+            "q" => Some(Command::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// Clears the screen and draws each frame under a header, waiting `delay` between frames. Commands are read from
+/// `commands` while waiting, and playing continues without pausing once the sender is dropped. Returns the number of
+/// frames shown.
+pub fn play(
+    title: &str,
+    frames: &[Frame],
+    delay: Duration,
+    paused: bool,
+    commands: &Receiver<Command>,
+    out: &mut impl Write,
+) -> io::Result<usize> {
+    let mut paused = paused;
+    let last = frames.last().map_or(0, |frame| frame.generation);
+    for (shown, frame) in frames.iter().enumerate() {
+        write!(
+            out,
+            "\x1b[2J\x1b[H{} | generation {}/{} | population {}{}\n\n{}",
+            title,
+            frame.generation,
+            last,
+            frame.population,
+            if paused { " | paused" } else { "" },
+            frame.text
+        )?;
+        out.flush()?;
+        if shown + 1 == frames.len() {
+            break;
+        }
+
+        let next_frame = Instant::now() + delay;
+        loop {
+            let command = if paused {
+                commands.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            } else {
+                commands.recv_timeout(next_frame.saturating_duration_since(Instant::now()))
+            };
+            match command {
+                Ok(Command::Toggle) if paused => {
+                    paused = false;
+                    break;
+                }
+                Ok(Command::Toggle) => paused = true,
+                Ok(Command::Step) if paused => break,
+                Ok(Command::Step) => {}
+                Ok(Command::Quit) => return Ok(shown + 1),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    paused = false;
+                    thread::sleep(next_frame.saturating_duration_since(Instant::now()));
+                    break;
+                }
+            }
+        }
+    }
+    Ok(frames.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::animate::{frames, play, Command};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    const DECKS: &str = "Player 1:\n9\n2\n6\n3\n1\n\nPlayer 2:\n5\n8\n4\n7\n10\n";
+
+    #[test]
+    fn test_frames() {
+        let cards = frames(22, 1, DECKS).unwrap();
+        assert_eq!(cards.len(), 30);
+        assert_eq!(
+            cards[0].text,
+            "Player 1: 9, 2, 6, 3, 1\nPlayer 2: 5, 8, 4, 7, 10\n"
+        );
+        assert_eq!(cards[0].population, 5);
+        assert_eq!(cards[29].population, 0);
+        assert_eq!(frames(22, 2, DECKS).unwrap().len(), 18);
+
+        let cubes = frames(17, 1, ".#.\n..#\n###\n").unwrap();
+        assert_eq!(cubes.len(), 7);
+        assert_eq!(cubes[6].population, 112);
+        assert_eq!(cubes[0].text.lines().count(), cubes[6].text.lines().count());
+
+        let floor = frames(24, 1, "esew\nnwwswee\n").unwrap();
+        assert_eq!(floor.len(), 1);
+        assert_eq!(floor[0].population, 2);
+        assert!(frames(20, 1, "").is_err());
+    }
+
+    #[test]
+    fn test_play() {
+        let frames = frames(22, 1, DECKS).unwrap();
+        let (sender, receiver) = mpsc::channel();
+        drop(sender);
+        let mut out = Vec::new();
+        let shown = play(
+            "Day 22",
+            &frames,
+            Duration::ZERO,
+            false,
+            &receiver,
+            &mut out,
+        )
+        .unwrap();
+        assert_eq!(shown, 30);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("Day 22 | generation 29/29 | population 0"));
+
+        // Step twice while paused, then quit.
+        let (sender, receiver) = mpsc::channel();
+        for command in [Command::Step, Command::Step, Command::Quit].iter() {
+            sender.send(*command).unwrap();
+        }
+        let mut out = Vec::new();
+        let shown = play("Day 22", &frames, Duration::ZERO, true, &receiver, &mut out).unwrap();
+        assert_eq!(shown, 3);
+        assert_eq!(Command::parse("\n"), Some(Command::Toggle));
+        assert_eq!(Command::parse("x"), None);
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// The outcome of giving every slot a different value from its candidates. Assignments hold one value per slot, in
+/// slot order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Assignment<V> {
+    /// No other choice of values satisfies the candidates.
+    Unique(Vec<V>),
+    /// An assignment, and alternatives that each differ from it in at least one slot.
+    Ambiguous(Vec<V>, Vec<Vec<V>>),
+    /// Some slots cannot all be given different values.
+    Unsatisfiable,
+}
+
+impl<V> Assignment<V> {
+    pub fn unique(&self) -> Option<&[V]> {
+        match self {
+            Assignment::Unique(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+/// Picks a different value for every slot from its candidates. Values that are the only candidate left for a slot are
+/// first removed from the other slots, which settles most puzzles. Whatever is left is solved as a bipartite matching,
+/// and every slot is then checked for another value it could take.
+pub fn assign<V: Copy + Eq + Hash>(candidates: &[HashSet<V>]) -> Assignment<V> {
+    assign_with(candidates, |_, _, _| {})
+}
+
+/// Same as `assign`, calling `observe` each time elimination settles a slot, with the slot, its value and the other
+/// slots the value was removed from.
+pub fn assign_with<V: Copy + Eq + Hash>(
+    candidates: &[HashSet<V>],
+    mut observe: impl FnMut(usize, V, &[usize]),
+) -> Assignment<V> {
+    let mut values: Vec<V> = Vec::new();
+    let mut index: HashMap<V, usize> = HashMap::new();
+    let mut edges: Vec<Vec<usize>> = candidates
+        .iter()
+        .map(|set| {
+            set.iter()
+                .map(|value| {
+                    *index.entry(*value).or_insert_with(|| {
+                        values.push(*value);
+                        values.len() - 1
+                    })
+                })
+                .collect()
+        })
+        .collect();
+
+    if !propagate(&mut edges, &mut |slot, value, removed_from| {
+        observe(slot, values[value], removed_from)
+    }) {
+        return Assignment::Unsatisfiable;
+    }
+
+    let mut slot_of = vec![None; values.len()];
+    for slot in 0..edges.len() {
+        if !augment(slot, &edges, &mut slot_of, &mut vec![false; values.len()]) {
+            return Assignment::Unsatisfiable;
+        }
+    }
+    let to_values = |slot_of: &[Option<usize>]| {
+        let mut res = vec![None; edges.len()];
+        for (value, slot) in slot_of.iter().enumerate() {
+            if let Some(slot) = slot {
+                res[*slot] = Some(values[value]);
+            }
+        }
+        res.into_iter().flatten().collect::<Vec<V>>()
+    };
+    let assignment = to_values(&slot_of);
+
+    let mut alternatives: Vec<Vec<V>> = Vec::new();
+    for slot in 0..edges.len() {
+        // Forbid the slot's current value, then look for another way to fill it.
+        let current = index[&assignment[slot]];
+        let mut edges = edges.clone();
+        edges[slot].retain(|value| *value != current);
+        let mut slot_of = slot_of.clone();
+        slot_of[current] = None;
+        if augment(slot, &edges, &mut slot_of, &mut vec![false; values.len()]) {
+            let alternative = to_values(&slot_of);
+            if !alternatives.contains(&alternative) {
+                alternatives.push(alternative);
+            }
+        }
+    }
+
+    if alternatives.is_empty() {
+        Assignment::Unique(assignment)
+    } else {
+        Assignment::Ambiguous(assignment, alternatives)
+    }
+}
+
+/// Removes the value of every slot with a single candidate from all other slots, until no new such slot turns up.
+/// Returns false if a slot runs out of candidates.
+fn propagate(edges: &mut [Vec<usize>], observe: &mut dyn FnMut(usize, usize, &[usize])) -> bool {
+    let mut settled = vec![false; edges.len()];
+    while let Some(slot) = (0..edges.len()).find(|slot| !settled[*slot] && edges[*slot].len() == 1)
+    {
+        settled[slot] = true;
+        let value = edges[slot][0];
+        let mut removed_from = Vec::new();
+        for (other, values) in edges.iter_mut().enumerate() {
+            if other != slot && values.contains(&value) {
+                values.retain(|v| *v != value);
+                removed_from.push(other);
+            }
+        }
+        observe(slot, value, &removed_from);
+    }
+    edges.iter().all(|values| !values.is_empty())
+}
+
+/// Finds an augmenting path from `slot`, taking values from other slots where they can move elsewhere.
+fn augment(
+    slot: usize,
+    edges: &[Vec<usize>],
+    slot_of: &mut [Option<usize>],
+    visited: &mut [bool],
+) -> bool {
+    for &value in edges[slot].iter() {
+        if !visited[value] {
+            visited[value] = true;
+            let free = match slot_of[value] {
+                None => true,
+                Some(other) => augment(other, edges, slot_of, visited),
+            };
+            if free {
+                slot_of[value] = Some(slot);
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assignment::{assign, assign_with, Assignment};
+    use std::collections::HashSet;
+
+    fn candidates(slots: &[&str]) -> Vec<HashSet<char>> {
+        slots.iter().map(|slot| slot.chars().collect()).collect()
+    }
+
+    #[test]
+    fn test_unique() {
+        let res = assign(&candidates(&["ab", "abc", "a"]));
+        assert_eq!(res, Assignment::Unique(vec!['b', 'c', 'a']));
+        assert_eq!(res.unique(), Some(&['b', 'c', 'a'][..]));
+    }
+
+    #[test]
+    fn test_observe() {
+        let mut settled = Vec::new();
+        let res = assign_with(
+            &candidates(&["ab", "abc", "a"]),
+            |slot, value, removed_from| settled.push((slot, value, removed_from.to_vec())),
+        );
+        assert_eq!(res, Assignment::Unique(vec!['b', 'c', 'a']));
+        assert_eq!(
+            settled,
+            vec![(2, 'a', vec![0, 1]), (0, 'b', vec![1]), (1, 'c', vec![])]
+        );
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        match assign(&candidates(&["ab", "ab", "abc"])) {
+            Assignment::Ambiguous(assignment, alternatives) => {
+                assert_eq!(assignment[2], 'c');
+                assert_eq!(alternatives.len(), 1);
+                assert_eq!(alternatives[0][0], assignment[1]);
+                assert_eq!(alternatives[0][1], assignment[0]);
+            }
+            res => panic!("Expected an ambiguous assignment, got {:?}", res),
+        }
+
+        // A spare value makes even a single slot ambiguous.
+        let res = assign(&candidates(&["ab"]));
+        assert!(matches!(res, Assignment::Ambiguous(_, alternatives) if alternatives.len() == 1));
+    }
+
+    #[test]
+    fn test_unsatisfiable() {
+        assert_eq!(assign(&candidates(&["a", "a"])), Assignment::Unsatisfiable);
+        // Propagation stalls here, and only the matching notices.
+        assert_eq!(
+            assign(&candidates(&["ab", "ab", "ab"])),
+            Assignment::Unsatisfiable
+        );
+        assert_eq!(assign(&candidates(&["a", ""])), Assignment::Unsatisfiable);
+    }
+}
@@ -0,0 +1,273 @@
+use crate::grid::{Grid, Pos, DIRECTIONS8};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// How the cells of an automaton are connected.
+pub trait Topology {
+    type Coord: Copy + Eq + Hash;
+
+    /// Appends the neighbours of `coord` to `res`.
+    fn neighbours(&self, coord: Self::Coord, res: &mut Vec<Self::Coord>);
+}
+
+/// An unbounded N-dimensional lattice where every cell has the 3^N - 1 neighbours that differ by at most one along
+/// each axis.
+pub struct Lattice<const N: usize> {
+    offsets: Vec<[i64; N]>,
+}
+
+impl<const N: usize> Lattice<N> {
+    pub fn new() -> Lattice<N> {
+        let mut offsets = vec![[0; N]];
+        for axis in 0..N {
+            offsets = offsets
+                .iter()
+                .flat_map(|offset| {
+                    (-1..=1).map(move |d| {
+                        let mut offset = *offset;
+                        offset[axis] = d;
+                        offset
+                    })
+                })
+                .collect();
+        }
+        offsets.retain(|offset| offset.iter().any(|d| *d != 0));
+        Lattice { offsets }
+    }
+}
+
+impl<const N: usize> Default for Lattice<N> {
+    fn default() -> Self {
+        Lattice::new()
+    }
+}
+
+impl<const N: usize> Topology for Lattice<N> {
+    type Coord = [i64; N];
+
+    fn neighbours(&self, coord: [i64; N], res: &mut Vec<[i64; N]>) {
+        res.extend(self.offsets.iter().map(|offset| {
+            let mut neighbour = coord;
+            for (n, d) in neighbour.iter_mut().zip(offset.iter()) {
+                *n += d;
+            }
+            neighbour
+        }));
+    }
+}
+
+/// An unbounded square grid with eight neighbours per cell.
+pub type Square = Lattice<2>;
+
+/// An unbounded grid of hexagons in doubled coordinates: east and west neighbours are two apart in x, and the other
+/// four neighbours are one apart in both x and y.
+pub struct Hex;
+
+impl Hex {
+    pub const DIRECTIONS: [(i64, i64); 6] = [(2, 0), (1, 1), (-1, 1), (-2, 0), (-1, -1), (1, -1)];
+}
+
+impl Topology for Hex {
+    type Coord = (i64, i64);
+
+    fn neighbours(&self, (x, y): (i64, i64), res: &mut Vec<(i64, i64)>) {
+        res.extend(Hex::DIRECTIONS.iter().map(|(dx, dy)| (x + dx, y + dy)));
+    }
+}
+
+/// The cells of a bounded grid, where each cell sees the first cell in each of the eight directions that is visible.
+/// The cells that are looked through never take part in the automaton, so their neighbours are worked out up front.
+pub struct LineOfSight {
+    neighbours: HashMap<Pos, Vec<Pos>>,
+}
+
+impl LineOfSight {
+    pub fn new<S>(grid: &Grid<S>, is_visible: impl Fn(&S) -> bool) -> LineOfSight {
+        let neighbours = grid
+            .positions()
+            .map(|pos| {
+                let seen = DIRECTIONS8
+                    .iter()
+                    .filter_map(|step| grid.ray(pos, *step).find(|p| is_visible(&grid[*p])))
+                    .collect();
+                (pos, seen)
+            })
+            .collect();
+        LineOfSight { neighbours }
+    }
+
+    /// Every cell is visible, so each cell sees only the cells next to it.
+    pub fn adjacent<S>(grid: &Grid<S>) -> LineOfSight {
+        LineOfSight::new(grid, |_| true)
+    }
+}
+
+impl Topology for LineOfSight {
+    type Coord = Pos;
+
+    fn neighbours(&self, coord: Pos, res: &mut Vec<Pos>) {
+        if let Some(neighbours) = self.neighbours.get(&coord) {
+            res.extend(neighbours.iter().copied());
+        }
+    }
+}
+
+/// A cellular automaton. Cells in the default state are not stored, so the set of live cells can grow without
+/// bounds. Every generation, a rule computes the next state of each cell from its state and its neighbours' states.
+pub struct Automaton<T: Topology, S> {
+    topology: T,
+    cells: HashMap<T::Coord, S>,
+    generation: usize,
+}
+
+impl<T: Topology, S: Copy + Eq + Default> Automaton<T, S> {
+    pub fn new(topology: T, cells: impl IntoIterator<Item = (T::Coord, S)>) -> Automaton<T, S> {
+        let cells = cells
+            .into_iter()
+            .filter(|(_, state)| *state != S::default())
+            .collect();
+        Automaton {
+            topology,
+            cells,
+            generation: 0,
+        }
+    }
+
+    pub fn get(&self, coord: T::Coord) -> S {
+        self.cells.get(&coord).copied().unwrap_or_default()
+    }
+
+    /// The cells that are not in the default state.
+    pub fn cells(&self) -> impl Iterator<Item = (T::Coord, S)> + '_ {
+        self.cells.iter().map(|(coord, state)| (*coord, *state))
+    }
+
+    pub fn count(&self, state: S) -> usize {
+        self.cells.values().filter(|s| **s == state).count()
+    }
+
+    /// The number of generations that have been run.
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    /// Computes the next generation. Only the live cells and their neighbours are considered, so a cell in the
+    /// default state without live neighbours must stay in the default state. Returns the number of cells that changed.
+    pub fn step(&mut self, rule: impl Fn(S, &[S]) -> S) -> usize {
+        let mut neighbours = Vec::new();
+        let mut candidates: HashSet<T::Coord> = HashSet::with_capacity(self.cells.len());
+        for coord in self.cells.keys() {
+            candidates.insert(*coord);
+            self.topology.neighbours(*coord, &mut neighbours);
+        }
+        candidates.extend(neighbours.drain(..));
+
+        let mut states = Vec::new();
+        let mut changes = Vec::new();
+        for coord in candidates {
+            self.topology.neighbours(coord, &mut neighbours);
+            states.extend(neighbours.drain(..).map(|c| self.get(c)));
+            let state = self.get(coord);
+            let next = rule(state, &states);
+            if next != state {
+                changes.push((coord, next));
+            }
+            states.clear();
+        }
+
+        for (coord, state) in changes.iter() {
+            if *state == S::default() {
+                self.cells.remove(coord);
+            } else {
+                self.cells.insert(*coord, *state);
+            }
+        }
+        self.generation += 1;
+        changes.len()
+    }
+
+    pub fn run_for(&mut self, generations: usize, rule: impl Fn(S, &[S]) -> S) {
+        for _ in 0..generations {
+            self.step(&rule);
+        }
+    }
+
+    /// Runs until a generation changes nothing. Returns the number of generations run, including the last one.
+    pub fn run_until_stable(&mut self, rule: impl Fn(S, &[S]) -> S) -> usize {
+        let start = self.generation;
+        while self.step(&rule) > 0 {}
+        self.generation - start
+    }
+}
+
+/// The rule of Conway's game of life, generalised to any topology: a live cell survives with a number of live
+/// neighbours in `survive` and a dead cell comes alive with a number in `birth`.
+pub fn life(birth: &'static [usize], survive: &'static [usize]) -> impl Fn(bool, &[bool]) -> bool {
+    move |alive, neighbours| {
+        let count = neighbours.iter().filter(|n| **n).count();
+        if alive {
+            survive.contains(&count)
+        } else {
+            birth.contains(&count)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::automaton::{life, Automaton, Hex, Lattice, LineOfSight, Square, Topology};
+    use crate::grid::Grid;
+    use itertools::Itertools;
+
+    #[test]
+    fn test_lattice() {
+        let mut res = Vec::new();
+        Lattice::<3>::new().neighbours([0, 0, 0], &mut res);
+        assert_eq!(res.len(), 26);
+        assert!(!res.contains(&[0, 0, 0]));
+        assert!(res.contains(&[-1, 1, 0]));
+
+        let mut res = Vec::new();
+        Hex.neighbours((0, 0), &mut res);
+        assert_eq!(res.len(), 6);
+    }
+
+    #[test]
+    fn test_blinker() {
+        let cells = (0..3).map(|x| ([x, 0], true));
+        let mut automaton = Automaton::new(Square::new(), cells);
+        automaton.step(life(&[3], &[2, 3]));
+        let vertical = automaton.cells().map(|(c, _)| c).sorted().collect_vec();
+        assert_eq!(vertical, vec![[1, -1], [1, 0], [1, 1]]);
+
+        automaton.run_for(3, life(&[3], &[2, 3]));
+        assert_eq!(automaton.generation(), 4);
+        assert!(automaton.get([0, 0]));
+        assert_eq!(automaton.count(true), 3);
+    }
+
+    #[test]
+    fn test_run_until_stable() {
+        // A block is stable and the single cell next to it dies.
+        let cells = vec![[0, 0], [0, 1], [1, 0], [1, 1], [4, 4]];
+        let mut automaton = Automaton::new(Square::new(), cells.into_iter().map(|c| (c, true)));
+        assert_eq!(automaton.run_until_stable(life(&[3], &[2, 3])), 2);
+        assert_eq!(automaton.count(true), 4);
+    }
+
+    #[test]
+    fn test_line_of_sight() {
+        let grid = Grid::parse(0, "#.#\n...\n#.#", Some).unwrap();
+        let topology = LineOfSight::new(&grid, |c| *c == '#');
+        let mut res = Vec::new();
+        topology.neighbours((0, 0), &mut res);
+        assert_eq!(
+            res.into_iter().sorted().collect_vec(),
+            vec![(0, 2), (2, 0), (2, 2)]
+        );
+
+        let mut res = Vec::new();
+        LineOfSight::adjacent(&grid).neighbours((0, 0), &mut res);
+        assert_eq!(res.len(), 3);
+    }
+}
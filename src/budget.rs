@@ -0,0 +1,174 @@
+//! Limits on how long the slow solvers may run, and reports on how far they have got.
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The number of steps between checks of the clock and the cancellation token, and between progress reports.
+pub const CHECK_INTERVAL: u64 = 1 << 16;
+
+/// How many steps a solver has taken, out of the total if it is known up front.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub done: u64,
+    pub total: Option<u64>,
+}
+
+/// Why a solver stopped before finishing, and how far it got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupted {
+    TimedOut(Progress),
+    Cancelled(Progress),
+}
+
+impl fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (reason, progress) = match self {
+            Interrupted::TimedOut(progress) => ("Timed out", progress),
+            Interrupted::Cancelled(progress) => ("Cancelled", progress),
+        };
+        match progress.total {
+            Some(total) => write!(f, "{} after {} of {} steps", reason, progress.done, total),
+            None => write!(f, "{} after {} steps", reason, progress.done),
+        }
+    }
+}
+
+impl Error for Interrupted {}
+
+/// Stops a solver running on another thread. Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// What a solver may spend: an optional deadline and cancellation token, and a callback that is told how far it has
+/// got. Solvers call `tick` once per step.
+pub struct Budget<'a> {
+    deadline: Option<Instant>,
+    token: Option<CancelToken>,
+    on_progress: Option<Box<dyn FnMut(Progress) + 'a>>,
+    done: u64,
+}
+
+impl<'a> Budget<'a> {
+    /// A budget that never runs out.
+    pub fn unlimited() -> Budget<'a> {
+        Budget {
+            deadline: None,
+            token: None,
+            on_progress: None,
+            done: 0,
+        }
+    }
+
+    /// A budget that runs out `limit` from now.
+    pub fn time_limit(limit: Duration) -> Budget<'a> {
+        Budget {
+            deadline: Some(Instant::now() + limit),
+            ..Budget::unlimited()
+        }
+    }
+
+    /// Also runs out when the token is cancelled.
+    pub fn with_token(self, token: CancelToken) -> Budget<'a> {
+        Budget {
+            token: Some(token),
+            ..self
+        }
+    }
+
+    /// Reports progress every `CHECK_INTERVAL` steps.
+    pub fn with_progress(self, on_progress: impl FnMut(Progress) + 'a) -> Budget<'a> {
+        Budget {
+            on_progress: Some(Box::new(on_progress)),
+            ..self
+        }
+    }
+
+    /// The number of steps taken so far.
+    pub fn done(&self) -> u64 {
+        self.done
+    }
+
+    /// Counts a step towards `total`, if it is known. Every `CHECK_INTERVAL` steps this reports progress, then fails
+    /// if the deadline has passed or the token was cancelled.
+    pub fn tick(&mut self, total: Option<u64>) -> Result<(), Interrupted> {
+        self.done += 1;
+        if !self.done.is_multiple_of(CHECK_INTERVAL) {
+            return Ok(());
+        }
+
+        let progress = Progress {
+            done: self.done,
+            total,
+        };
+        if let Some(on_progress) = self.on_progress.as_mut() {
+            on_progress(progress);
+        }
+        if self.token.as_ref().is_some_and(CancelToken::is_cancelled) {
+            return Err(Interrupted::Cancelled(progress));
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(Interrupted::TimedOut(progress));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::budget::{Budget, CancelToken, Interrupted, Progress, CHECK_INTERVAL};
+    use std::time::Duration;
+
+    #[test]
+    fn test_budget() {
+        let mut reports = Vec::new();
+        let mut budget = Budget::unlimited().with_progress(|progress| reports.push(progress));
+        for _ in 0..CHECK_INTERVAL * 3 {
+            budget.tick(Some(CHECK_INTERVAL * 3)).unwrap();
+        }
+        assert_eq!(budget.done(), CHECK_INTERVAL * 3);
+        drop(budget);
+        assert_eq!(reports.len(), 3);
+        assert_eq!(
+            reports[2],
+            Progress {
+                done: CHECK_INTERVAL * 3,
+                total: Some(CHECK_INTERVAL * 3)
+            }
+        );
+
+        let mut budget = Budget::time_limit(Duration::ZERO);
+        let res = (0..CHECK_INTERVAL).try_for_each(|_| budget.tick(None));
+        let timed_out = Interrupted::TimedOut(Progress {
+            done: CHECK_INTERVAL,
+            total: None,
+        });
+        assert_eq!(res, Err(timed_out));
+        assert_eq!(timed_out.to_string(), "Timed out after 65536 steps");
+
+        let token = CancelToken::new();
+        let mut budget = Budget::unlimited().with_token(token.clone());
+        token.cancel();
+        let res = (0..CHECK_INTERVAL * 2).try_for_each(|_| budget.tick(Some(10)));
+        assert!(matches!(res, Err(Interrupted::Cancelled(_))));
+        assert_eq!(budget.done(), CHECK_INTERVAL);
+    }
+}
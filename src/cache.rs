@@ -0,0 +1,356 @@
+//! An opt-in cache of parsed inputs, for the days whose parsing is a noticeable share of the run time on large inputs.
+//! Each entry is a compact binary file named after the day and a hash of the input text. Its header repeats the hash
+//! and length of the input, the version of the cache format and a fingerprint of the build and the parsed model, so an
+//! entry that no longer matches is detected and rebuilt instead of being trusted.
+use crate::error::ParseError;
+use crate::solution::DynSolution;
+use std::any::Any;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Environment variable naming the cache directory. Nothing is cached unless it is set.
+pub const CACHE_DIR_VAR: &str = "AOC_CACHE_DIR";
+
+/// The version of the entry format. Bump it whenever the header changes.
+pub const CACHE_VERSION: u16 = 2;
+
+const MAGIC: &[u8; 4] = b"AOCP";
+const HEADER_LEN: usize = 4 + 2 + 1 + 8 + 8 + 8;
+
+/// Numbers the temporary files of this process, since its threads may store the same entry at once.
+static TEMP_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// What a cache lookup found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lookup {
+    /// The entry matched the input and was loaded.
+    Hit,
+    /// There was no entry for the input.
+    Missing,
+    /// The entry was for another version of the cache, another build or model, another day or another input, or could
+    /// not be decoded.
+    Stale,
+}
+
+/// A directory of cached parsed inputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new(dir: impl Into<PathBuf>) -> Cache {
+        Cache { dir: dir.into() }
+    }
+
+    /// The cache in the directory named by `AOC_CACHE_DIR`, if it is set.
+    pub fn from_env() -> Option<Cache> {
+        env::var_os(CACHE_DIR_VAR).map(Cache::new)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Where the entry for the day's input is stored.
+    pub fn entry_path(&self, day: u8, input: &str) -> PathBuf {
+        self.dir
+            .join(format!("day{}-{:016x}.bin", day, input_hash(input)))
+    }
+
+    /// Loads the parsed input from its entry.
+    pub fn load(&self, solution: &dyn DynSolution, input: &str) -> Result<Box<dyn Any>, Lookup> {
+        let bytes = match fs::read(self.entry_path(solution.day(), input)) {
+            Ok(bytes) => bytes,
+            Err(_) => return Err(Lookup::Missing),
+        };
+        if bytes.len() < HEADER_LEN || bytes[..HEADER_LEN] != header(solution, input) {
+            return Err(Lookup::Stale);
+        }
+        solution
+            .parse_bytes(&bytes[HEADER_LEN..])
+            .map_err(|_| Lookup::Stale)
+    }
+
+    /// Writes the entry for the parsed input. The entry is written to a temporary file of its own first, so that a run
+    /// or thread that loads or stores it at the same time never sees half of it.
+    pub fn store(
+        &self,
+        solution: &dyn DynSolution,
+        input: &str,
+        parsed: &dyn Any,
+    ) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let mut bytes = header(solution, input);
+        bytes.extend(solution.to_bytes(parsed));
+        let path = self.entry_path(solution.day(), input);
+        let temp = path.with_extension(format!(
+            "tmp{}-{}",
+            process::id(),
+            TEMP_FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&temp, bytes)?;
+        fs::rename(&temp, &path)
+    }
+
+    /// Loads the parsed input from the cache, or parses it and stores it if the entry is missing or stale.
+    pub fn parse(
+        &self,
+        solution: &dyn DynSolution,
+        input: &str,
+    ) -> Result<(Box<dyn Any>, Lookup), ParseError> {
+        match self.load(solution, input) {
+            Ok(parsed) => Ok((parsed, Lookup::Hit)),
+            Err(lookup) => {
+                let parsed = solution.parse(input)?;
+                // An entry that cannot be written only costs the next run a parse.
+                let _ = self.store(solution, input, parsed.as_ref());
+                Ok((parsed, lookup))
+            }
+        }
+    }
+}
+
+/// Parses the input through the cache named by `AOC_CACHE_DIR`, or directly if it is not set.
+pub fn parse_cached(solution: &dyn DynSolution, input: &str) -> Result<Box<dyn Any>, ParseError> {
+    match Cache::from_env() {
+        Some(cache) => cache.parse(solution, input).map(|(parsed, _)| parsed),
+        None => solution.parse(input),
+    }
+}
+
+/// The 64-bit FNV-1a hash of the input, which unlike the standard library's hasher is the same on every platform and
+/// in every release of Rust.
+pub fn input_hash(input: &str) -> u64 {
+    input.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// A hash of the crate version and the name and version of the day's parsed model. The binary encoding does not
+/// describe its own fields, so an entry written by another release or before the model changed could otherwise decode
+/// into the wrong values.
+pub fn model_fingerprint(solution: &dyn DynSolution) -> u64 {
+    input_hash(&format!(
+        "{} {} {}",
+        env!("CARGO_PKG_VERSION"),
+        solution.model_name(),
+        solution.model_version()
+    ))
+}
+
+fn header(solution: &dyn DynSolution, input: &str) -> Vec<u8> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend(MAGIC);
+    header.extend(CACHE_VERSION.to_le_bytes());
+    header.push(solution.day());
+    header.extend(model_fingerprint(solution).to_le_bytes());
+    header.extend((input.len() as u64).to_le_bytes());
+    header.extend(input_hash(input).to_le_bytes());
+    header
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::{input_hash, model_fingerprint, Cache, Lookup, CACHE_VERSION};
+    use crate::day8::Day8;
+    use crate::error::ParseError;
+    use crate::input::read_fixture;
+    use crate::registry::{solution, solutions};
+    use crate::solution::{DynSolution, Solution};
+    use itertools::Itertools;
+    use serde_json::Value;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::thread;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("aoc-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_input_hash() {
+        assert_eq!(input_hash(""), 0xcbf29ce484222325);
+        assert_eq!(input_hash("a"), 0xaf63dc4c8601ec8c);
+        assert_ne!(input_hash("nop +0\n"), input_hash("nop +1\n"));
+    }
+
+    #[test]
+    fn test_model_fingerprint() {
+        let fingerprint = model_fingerprint(solution(8).unwrap());
+        assert_eq!(fingerprint, model_fingerprint(solution(8).unwrap()));
+        assert_ne!(fingerprint, model_fingerprint(solution(20).unwrap()));
+
+        // The same model after a change to its layout.
+        struct Bumped;
+        impl Solution for Bumped {
+            const DAY: u8 = 8;
+            type Input = <Day8 as Solution>::Input;
+            const MODEL_VERSION: u32 = 2;
+
+            fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+                Solution::parse(&Day8, input)
+            }
+            fn part1(&self, input: &Self::Input) -> Option<String> {
+                Day8.part1(input)
+            }
+            fn part2(&self, input: &Self::Input) -> Option<String> {
+                Day8.part2(input)
+            }
+        }
+        assert_eq!(Bumped.model_name(), solution(8).unwrap().model_name());
+        assert_ne!(fingerprint, model_fingerprint(&Bumped));
+    }
+
+    /// The shape of a parsed input's JSON, without its values.
+    fn layout(value: &Value) -> String {
+        match value {
+            Value::Null => "null".to_string(),
+            Value::Bool(_) => "bool".to_string(),
+            Value::Number(_) => "number".to_string(),
+            Value::String(_) => "string".to_string(),
+            Value::Array(items) => {
+                format!("[{}]", items.iter().map(layout).sorted().dedup().join("|"))
+            }
+            Value::Object(fields) => format!(
+                "{{{}}}",
+                fields
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, layout(value)))
+                    .join(", ")
+            ),
+        }
+    }
+
+    #[test]
+    fn test_model_versions() {
+        // A changed layout hash means the day's model changed: bump its `MODEL_VERSION` and update both here.
+        let expected = [
+            (1, 1, 0xd0f1337e3780b6ee),
+            (2, 1, 0x0ba6eca3ed272c70),
+            (3, 1, 0x9c9b9994a0258e29),
+            (4, 1, 0x42e128ea65acba9d),
+            (5, 1, 0xfa29a86408b12cbb),
+            (6, 1, 0x45d1a8c7e0e06f0a),
+            (7, 1, 0xe266fd3a84ef5423),
+            (8, 1, 0x5426f9c4ca7d07e7),
+            (9, 1, 0xd0f1337e3780b6ee),
+            (10, 1, 0xd0f1337e3780b6ee),
+            (11, 1, 0x35353a2b97943484),
+            (12, 1, 0xf2167f0831afee8b),
+            (13, 1, 0x7b9f48251957a418),
+            (14, 1, 0xb7b7243fc4c57c18),
+            (15, 1, 0xd0f1337e3780b6ee),
+            (16, 1, 0x9b0702729be804fe),
+            (17, 1, 0x3fb59a691ee66080),
+            (18, 1, 0x0ac1cb7118d6bf1e),
+            (19, 1, 0x6e86e7ba42789ec9),
+            (20, 1, 0x1451024c50816b6f),
+            (21, 1, 0x1a781308145a0226),
+            (22, 1, 0x3fb59a691ee66080),
+            (23, 1, 0xd0f1337e3780b6ee),
+            (24, 1, 0x3fb59a691ee66080),
+            (25, 1, 0xd0f1337e3780b6ee),
+        ];
+        assert_eq!(solutions().len(), expected.len());
+        for (s, (day, version, hash)) in solutions().iter().zip(expected) {
+            let input = read_fixture(&format!("day{}.txt", s.day()));
+            let json = s.to_json(s.parse(&input).unwrap().as_ref());
+            let layout_hash = input_hash(&layout(&serde_json::from_str(&json).unwrap()));
+            assert_eq!(
+                (s.day(), s.model_version(), layout_hash),
+                (day, version, hash),
+                "Day {} model changed",
+                day
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let cache = Cache::new(temp_dir("parse"));
+        let s = solution(20).unwrap();
+        let input = read_fixture("day20.txt");
+        let (parsed, lookup) = cache.parse(s, &input).unwrap();
+        assert_eq!(lookup, Lookup::Missing);
+        assert!(cache.entry_path(20, &input).exists());
+        let (cached, lookup) = cache.parse(s, &input).unwrap();
+        assert_eq!(lookup, Lookup::Hit);
+        assert_eq!(s.solve(1, cached.as_ref()), s.solve(1, parsed.as_ref()));
+
+        // A different input has its own entry.
+        let other = input.replacen("Tile 2311:", "Tile 2312:", 1);
+        assert_ne!(cache.entry_path(20, &other), cache.entry_path(20, &input));
+        assert_eq!(cache.load(s, &other).err(), Some(Lookup::Missing));
+
+        assert!(cache.parse(s, "Tile 1: not a tile\n").is_err());
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_stores() {
+        // `aoc all` parses both parts of a day at once, and both store the same entry.
+        let cache = Cache::new(temp_dir("concurrent"));
+        let s = solution(20).unwrap();
+        let input = read_fixture("day20.txt");
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let parsed = s.parse(&input).unwrap();
+                    for _ in 0..20 {
+                        cache.store(s, &input, parsed.as_ref()).unwrap();
+                        let cached = cache.load(s, &input).unwrap();
+                        assert_eq!(s.solve(1, cached.as_ref()), s.solve(1, parsed.as_ref()));
+                    }
+                });
+            }
+        });
+        assert_eq!(fs::read_dir(cache.dir()).unwrap().count(), 1);
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_stale() {
+        let cache = Cache::new(temp_dir("stale"));
+        let s = solution(8).unwrap();
+        let input = read_fixture("day8.txt");
+        let path = cache.entry_path(8, &input);
+        cache.parse(s, &input).unwrap();
+        let entry = fs::read(&path).unwrap();
+
+        let mut old_version = entry.clone();
+        old_version[4..6].copy_from_slice(&(CACHE_VERSION + 1).to_le_bytes());
+        let mut other_day = entry.clone();
+        other_day[6] = 9;
+        let mut other_model = entry.clone();
+        other_model[7..15].copy_from_slice(&(model_fingerprint(s) ^ 1).to_le_bytes());
+        let mut other_input = entry.clone();
+        other_input[15] ^= 1;
+        let mut garbage = entry.clone();
+        garbage.truncate(entry.len() - 1);
+        for stale in [
+            old_version,
+            other_day,
+            other_model,
+            other_input,
+            garbage,
+            b"AOCP".to_vec(),
+        ] {
+            fs::write(&path, stale).unwrap();
+            assert_eq!(cache.load(s, &input).err(), Some(Lookup::Stale));
+            // Parsing rebuilds the entry.
+            let (parsed, lookup) = cache.parse(s, &input).unwrap();
+            assert_eq!(lookup, Lookup::Stale);
+            assert_eq!(s.solve(1, parsed.as_ref()), Some("5".to_string()));
+            assert_eq!(fs::read(&path).unwrap(), entry);
+        }
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+}
@@ -0,0 +1,82 @@
+//! Runs the solvers that back their answers with certificates, and checks each certificate independently of the
+//! solver that produced it.
+use crate::solution::Certify;
+use crate::{day1, day16, day20, day8, day9};
+
+/// A certificate for one part and what its checker made of it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checked {
+    pub certificate: String,
+    /// The answer proved by the certificate, or why the checker rejected it.
+    pub checked: Result<String, String>,
+    /// The solver's own answer, which should be the same.
+    pub solved: Option<String>,
+}
+
+impl Checked {
+    pub fn is_valid(&self) -> bool {
+        self.checked.as_ref().ok() == self.solved.as_ref()
+    }
+}
+
+/// The days that produce certificates for at least one part.
+pub const CERTIFIED_DAYS: [u8; 5] = [1, 8, 9, 16, 20];
+
+/// Solves the part with a certificate and checks it. Returns `None` if the part has no solution or no certificate.
+pub fn certify(day: u8, part: u8, input: &str) -> Result<Option<Checked>, String> {
+    match day {
+        1 => certify_with(&day1::Day1, part, input),
+        8 => certify_with(&day8::Day8, part, input),
+        9 => certify_with(&day9::Day9, part, input),
+        16 => certify_with(&day16::Day16, part, input),
+        20 => certify_with(&day20::Day20, part, input),
+        _ => Err(format!("Day {} has no certificates", day)),
+    }
+}
+
+fn certify_with<S: Certify>(
+    solution: &S,
+    part: u8,
+    input: &str,
+) -> Result<Option<Checked>, String> {
+    let input = solution.parse(input).map_err(|e| e.to_string())?;
+    let solved = match part {
+        1 => solution.part1(&input),
+        _ => solution.part2(&input),
+    };
+    Ok(solution.certify(part, &input).map(|certificate| Checked {
+        certificate: solution.describe(&certificate),
+        checked: solution.check(part, &input, &certificate),
+        solved,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::certificate::{certify, CERTIFIED_DAYS};
+    use crate::input::{read_fixture, InputSource};
+
+    #[test]
+    fn test_certify() {
+        for day in CERTIFIED_DAYS.iter() {
+            let input = InputSource::Day(*day).read().unwrap();
+            let checked = (1..=2)
+                .filter_map(|part| certify(*day, part, &input).unwrap())
+                .collect::<Vec<_>>();
+            assert!(!checked.is_empty());
+            for checked in checked {
+                assert!(checked.is_valid(), "Day {}: {:?}", day, checked);
+            }
+        }
+
+        let checked = certify(1, 1, &read_fixture("day1.txt")).unwrap().unwrap();
+        assert!(checked.certificate.ends_with(" = 2020"));
+        assert_eq!(checked.checked, Ok("514579".to_string()));
+        assert_eq!(certify(8, 1, "nop +0\n"), Ok(None));
+        assert!(certify(8, 2, "hcf +0\n").is_err());
+        assert_eq!(
+            certify(2, 1, ""),
+            Err("Day 2 has no certificates".to_string())
+        );
+    }
+}
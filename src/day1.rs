@@ -1,43 +1,113 @@
+use crate::error::{parse_number, ParseError};
+use crate::input::read_day_lines;
+use crate::solution::{lines, Certify, Solution};
+use itertools::Itertools;
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
-use std::iter::FromIterator;
-use std::str::FromStr;
 
-/// Assuming file does not contain duplicates (mine does not)
-/// Assuming the sum does not pair (or triple) a number with itself (1010 is not in the file)
+// Assuming file does not contain duplicates (mine does not)
+// Assuming the sum does not pair (or triple) a number with itself (1010 is not in the file)
 
-fn read_file_as_set() -> HashSet<u32> {
-    let file = File::open("input/day1.txt").unwrap();
-    HashSet::from_iter(
-        BufReader::new(file)
-            .lines()
-            .map(|line| u32::from_str(line.unwrap().as_str()).unwrap()),
-    )
+pub fn read_file_as_set() -> Result<HashSet<u32>, ParseError> {
+    parse_set(read_day_lines(Day1::DAY))
 }
 
-fn find_pair_with_sum(input: &HashSet<u32>, sum: u32) -> Option<(u32, u32)> {
+pub fn parse_set(iter: impl Iterator<Item = String>) -> Result<HashSet<u32>, ParseError> {
+    iter.enumerate()
+        .map(|(i, line)| parse_number(Day1::DAY, i + 1, &line, &line))
+        .collect()
+}
+
+pub fn find_pair_with_sum(input: &HashSet<u32>, sum: u32) -> Option<(u32, u32)> {
     input
         .iter()
         .filter(|v| **v < sum)
         .find_map(|v1| input.get(&(sum - *v1)).map(|v2| (*v1, *v2)))
 }
 
-fn find_triple_with_sum(input: &HashSet<u32>, sum: u32) -> Option<(u32, u32, u32)> {
+pub fn find_triple_with_sum(input: &HashSet<u32>, sum: u32) -> Option<(u32, u32, u32)> {
     input
         .iter()
         .filter(|v| **v < sum)
         .find_map(|v1| find_pair_with_sum(input, sum - *v1).map(|(v2, v3)| (*v1, v2, v3)))
 }
 
+/// Checks that `entries` are different entries of the report that add up to `sum`, and returns their product.
+pub fn check_entries(input: &HashSet<u32>, entries: &[u32], sum: u32) -> Result<u64, String> {
+    if let Some(entry) = entries.iter().find(|entry| !input.contains(entry)) {
+        return Err(format!("{} is not in the report", entry));
+    }
+    if !entries.iter().all_unique() {
+        return Err(format!("{:?} repeats an entry", entries));
+    }
+    let total: u64 = entries.iter().map(|entry| *entry as u64).sum();
+    if total != sum as u64 {
+        return Err(format!("{:?} adds up to {}, not {}", entries, total, sum));
+    }
+    Ok(entries.iter().map(|entry| *entry as u64).product())
+}
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    const DAY: u8 = 1;
+    type Input = HashSet<u32>;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_set(lines(input))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        find_pair_with_sum(input, 2020).map(|(v1, v2)| (v1 * v2).to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        find_triple_with_sum(input, 2020).map(|(v1, v2, v3)| (v1 * v2 * v3).to_string())
+    }
+}
+
+impl Certify for Day1 {
+    /// The two or three entries that add up to 2020.
+    type Certificate = Vec<u32>;
+
+    fn certify(&self, part: u8, input: &Self::Input) -> Option<Self::Certificate> {
+        match part {
+            1 => find_pair_with_sum(input, 2020).map(|(v1, v2)| vec![v1, v2]),
+            2 => find_triple_with_sum(input, 2020).map(|(v1, v2, v3)| vec![v1, v2, v3]),
+            _ => None,
+        }
+    }
+
+    fn check(
+        &self,
+        part: u8,
+        input: &Self::Input,
+        certificate: &Self::Certificate,
+    ) -> Result<String, String> {
+        if certificate.len() != part as usize + 1 {
+            return Err(format!(
+                "Expected {} entries, found {}",
+                part + 1,
+                certificate.len()
+            ));
+        }
+        check_entries(input, certificate, 2020).map(|product| product.to_string())
+    }
+
+    fn describe(&self, certificate: &Self::Certificate) -> String {
+        format!("{} = 2020", certificate.iter().join(" + "))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::day1::{find_pair_with_sum, find_triple_with_sum, read_file_as_set};
+    use crate::day1::{
+        check_entries, find_pair_with_sum, find_triple_with_sum, read_file_as_set, Day1,
+    };
+    use crate::solution::Certify;
 
     #[test]
     fn part1() {
-        let input = read_file_as_set();
+        let input = read_file_as_set().unwrap();
         match find_pair_with_sum(&input, 2020) {
             Some((v1, v2)) => println!("{}", v1 * v2),
             None => println!("No solution found"),
@@ -46,10 +116,32 @@ mod tests {
 
     #[test]
     fn part2() {
-        let input = read_file_as_set();
+        let input = read_file_as_set().unwrap();
         match find_triple_with_sum(&input, 2020) {
             Some((v1, v2, v3)) => println!("{}", v1 * v2 * v3),
             None => println!("No solution found"),
         }
     }
+
+    #[test]
+    fn test_certificates() {
+        let mut input = [1721, 979, 366, 299, 675, 1456].iter().copied().collect();
+        let pair = Day1.certify(1, &input).unwrap();
+        assert_eq!(Day1.check(1, &input, &pair), Ok("514579".to_string()));
+        let triple = Day1.certify(2, &input).unwrap();
+        assert_eq!(Day1.check(2, &input, &triple), Ok("241861950".to_string()));
+        assert!(Day1.check(2, &input, &pair).is_err());
+
+        assert_eq!(check_entries(&input, &[1721, 299], 2020), Ok(514579));
+        assert_eq!(
+            check_entries(&input, &[1721, 300], 2020),
+            Err("300 is not in the report".to_string())
+        );
+        assert!(check_entries(&input, &[1721, 979], 2020).is_err());
+        input.insert(1010);
+        assert_eq!(
+            check_entries(&input, &[1010, 1010], 2020),
+            Err("[1010, 1010] repeats an entry".to_string())
+        );
+    }
 }
@@ -1,73 +1,124 @@
+use crate::error::{parse_number, ParseError};
+use crate::input::read_day_lines;
+use crate::numeric::{exact_option, Natural, Overflow};
+use crate::solution::{lines, Solution};
 use itertools::Itertools;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
-use std::str::FromStr;
-use std::vec::Vec;
-
-fn read_file() -> impl Iterator<Item = u64> {
-    let file = File::open("input/day10.txt").unwrap();
-    BufReader::new(file)
-        .lines()
-        .map(|s| u64::from_str(s.unwrap().as_str()).unwrap())
+
+pub fn read_file() -> Result<Vec<u64>, ParseError> {
+    parse_numbers(read_day_lines(Day10::DAY))
+}
+
+pub fn parse_numbers(iter: impl Iterator<Item = String>) -> Result<Vec<u64>, ParseError> {
+    iter.enumerate()
+        .map(|(i, line)| parse_number(Day10::DAY, i + 1, &line, &line))
+        .collect()
 }
 
-fn joltage_distribution(adapters: &mut Vec<u64>) -> (u64, u64) {
+/// Adds the outlet and your device to the adapters and sorts them into the chain that uses them all. Returns `None` if
+/// two adapters are the same or more than three jolts apart, since then there is no such chain, or if your device's
+/// joltage does not fit in a `u64`.
+fn chain_adapters(adapters: &mut Vec<u64>) -> Option<()> {
     // Include zero and your final adapter.
     adapters.push(0);
-    adapters.push(adapters.iter().max().unwrap() + 3);
+    adapters.push(adapters.iter().max().unwrap().checked_add(3)?);
     adapters.sort();
+    if adapters
+        .iter()
+        .tuple_windows()
+        .all(|(v1, v2)| (1..=3).contains(&(v2 - v1)))
+    {
+        Some(())
+    } else {
+        None
+    }
+}
+
+/// Counts the differences of one and three jolts along the chain of every adapter. Returns `None` if there is no such
+/// chain.
+pub fn joltage_distribution(adapters: &mut Vec<u64>) -> Option<(u64, u64)> {
+    chain_adapters(adapters)?;
 
     // Since we know the distance is never greater than three, just count the distances between
     // adjacent adapters.
-    adapters
-        .iter()
-        .tuple_windows()
-        .fold((0, 0), |(ones, threes), (v1, v2)| match v2 - v1 {
-            1 => (ones + 1, threes),
-            3 => (ones, threes + 1),
-            2 => (ones, threes),
-            x => panic!("Unexpected difference: {}", x),
-        })
+    Some(
+        adapters
+            .iter()
+            .tuple_windows()
+            .fold((0, 0), |(ones, threes), (v1, v2)| match v2 - v1 {
+                1 => (ones + 1, threes),
+                3 => (ones, threes + 1),
+                _ => (ones, threes),
+            }),
+    )
 }
 
-fn count_arrangements(adapters: &mut Vec<u64>) -> u64 {
-    // Include zero and your final adapter.
-    adapters.push(0);
-    adapters.push(adapters.iter().max().unwrap() + 3);
-    adapters.sort();
+/// Returns `None` if there is no chain of every adapter, as for `joltage_distribution`.
+pub fn count_arrangements(adapters: &mut Vec<u64>) -> Option<u64> {
+    count_arrangements_as(adapters).expect("The number of arrangements overflowed")
+}
+
+/// Same as `count_arrangements`, counting with any `Natural`.
+pub fn count_arrangements_as<N: Natural>(adapters: &mut Vec<u64>) -> Result<Option<N>, Overflow> {
+    if chain_adapters(adapters).is_none() {
+        return Ok(None);
+    }
 
     // Use bottom-up dynamic programming to avoid recalculating overlapping sub-problems.
     // Sum the counts including and excluding each previous adapter within a distance of 3.
     // You really only need up to three counts, but there are only 100 rows in the file.
-    let mut counts: Vec<u64> = Vec::with_capacity(adapters.len());
-    counts.push(1);
+    let mut counts: Vec<N> = Vec::with_capacity(adapters.len());
+    counts.push(N::one());
     for i in 1..adapters.len() {
         let adapter = adapters[i];
         let mut j: i32 = i as i32 - 1;
 
-        let mut count = 0;
+        let mut count = N::zero();
         while j >= 0 && adapter - adapters[j as usize] <= 3 {
-            count += counts[j as usize];
+            count = count.add(&counts[j as usize])?;
             j -= 1;
         }
 
         counts.push(count);
     }
 
-    counts[adapters.len() - 1]
+    Ok(Some(counts.swap_remove(adapters.len() - 1)))
+}
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    const DAY: u8 = 10;
+    type Input = Vec<u64>;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_numbers(lines(input))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        let (ones, threes) = joltage_distribution(&mut input.clone())?;
+        Some((ones * threes).to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        exact_option(count_arrangements_as(&mut input.clone()), || {
+            count_arrangements_as(&mut input.clone())
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day10::{count_arrangements, joltage_distribution, read_file};
-    use itertools::Itertools;
+    use crate::day10::{
+        count_arrangements, count_arrangements_as, joltage_distribution, read_file, Day10,
+    };
+    use crate::numeric::{BigUint, Overflow};
+    use crate::solution::Solution;
 
     #[test]
     fn part1_example1() {
         let mut adapters = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
         let res = joltage_distribution(&mut adapters);
-        assert_eq!((7, 5), res)
+        assert_eq!(Some((7, 5)), res)
     }
 
     #[test]
@@ -77,23 +128,34 @@ mod tests {
             8, 17, 7, 9, 4, 2, 34, 10, 3,
         ];
         let res = joltage_distribution(&mut adapters);
-        assert_eq!((22, 10), res)
+        assert_eq!(Some((22, 10)), res)
     }
 
     #[test]
     fn part1() {
-        let mut adapters = read_file().collect_vec();
-        let (ones, threes) = joltage_distribution(&mut adapters);
+        let mut adapters = read_file().unwrap();
+        let (ones, threes) = joltage_distribution(&mut adapters).unwrap();
         let product = ones * threes;
         println!("{}", product);
         assert_eq!(2590, product);
     }
 
+    #[test]
+    fn part1_unusable_adapters() {
+        // A gap of more than three jolts, from the outlet or between adapters, or two equal adapters break the chain.
+        for adapters in [vec![5], vec![1, 5], vec![0], vec![1, 1], vec![u64::MAX]] {
+            assert_eq!(joltage_distribution(&mut adapters.clone()), None);
+            assert_eq!(count_arrangements(&mut adapters.clone()), None);
+            assert_eq!(Day10.part1(&adapters), None);
+            assert_eq!(Day10.part2(&adapters), None);
+        }
+    }
+
     #[test]
     fn part2_example1() {
         let mut adapters = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
         let res = count_arrangements(&mut adapters);
-        assert_eq!(res, 8);
+        assert_eq!(res, Some(8));
     }
 
     #[test]
@@ -103,14 +165,33 @@ mod tests {
             8, 17, 7, 9, 4, 2, 34, 10, 3,
         ];
         let res = count_arrangements(&mut adapters);
-        assert_eq!(res, 19208);
+        assert_eq!(res, Some(19208));
     }
 
     #[test]
     fn part2() {
-        let mut adapters = read_file().collect_vec();
+        let mut adapters = read_file().unwrap();
         let res = count_arrangements(&mut adapters);
-        println!("{}", res);
-        assert_eq!(226775649501184, res);
+        println!("{:?}", res);
+        assert_eq!(Some(226775649501184), res);
+    }
+
+    #[test]
+    fn part2_overflow() {
+        // Every adapter from 1 to 100 can be skipped as long as no gap is bigger than 3, so the count grows like the
+        // tribonacci numbers and passes u64::MAX at 74 adapters.
+        let adapters = (1..=100).collect::<Vec<u64>>();
+        assert_eq!(
+            count_arrangements_as::<u64>(&mut adapters.clone()),
+            Err(Overflow)
+        );
+        let res = count_arrangements_as::<BigUint>(&mut adapters.clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.to_string(), "180396380815100901214157639");
+        assert_eq!(
+            Day10.part2(&adapters),
+            Some("180396380815100901214157639".to_string())
+        );
     }
 }
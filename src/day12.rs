@@ -1,15 +1,14 @@
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
-use std::str::FromStr;
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day12.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap().to_string())
+use crate::error::{parse_number, ParseError};
+use crate::input::read_day_lines;
+use crate::solution::{lines, Solution};
+use serde::{Deserialize, Serialize};
+
+pub fn read_file() -> impl Iterator<Item = String> {
+    read_day_lines(Day12::DAY)
 }
 
-#[derive(Clone, PartialEq, Eq)]
-enum Direction {
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
     North,
     South,
     East,
@@ -17,7 +16,7 @@ enum Direction {
 }
 
 impl Direction {
-    fn turn(&self, turn: Turn, degrees: i32) -> Direction {
+    pub fn turn(&self, turn: Turn, degrees: i32) -> Direction {
         if degrees == 0 {
             self.clone()
         } else {
@@ -36,7 +35,7 @@ impl Direction {
         }
     }
 
-    fn y_amount(&self) -> i32 {
+    pub fn y_amount(&self) -> i32 {
         match self {
             Direction::North => 1,
             Direction::South => -1,
@@ -44,7 +43,7 @@ impl Direction {
         }
     }
 
-    fn x_amount(&self) -> i32 {
+    pub fn x_amount(&self) -> i32 {
         match self {
             Direction::East => 1,
             Direction::West => -1,
@@ -53,20 +52,21 @@ impl Direction {
     }
 }
 
-#[derive(PartialEq, Eq)]
-enum Turn {
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Turn {
     Left,
     Right,
 }
 
 impl Turn {
-    fn rotate(&self, x: i32, y: i32, degrees: i32) -> (i32, i32) {
+    /// Returns `None` if a coordinate is `i32::MIN`, which has no negation.
+    pub fn rotate(&self, x: i32, y: i32, degrees: i32) -> Option<(i32, i32)> {
         if degrees == 0 {
-            (x, y)
+            Some((x, y))
         } else {
             let (new_x, new_y) = match self {
-                Turn::Right => (y, -x),
-                _ => (-y, x),
+                Turn::Right => (y, x.checked_neg()?),
+                _ => (y.checked_neg()?, x),
             };
 
             self.rotate(new_x, new_y, degrees - 90)
@@ -74,125 +74,186 @@ impl Turn {
     }
 }
 
-enum Action {
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Action {
     Move(Direction, i32),
     Turn(Turn, i32),
     Forward(i32),
 }
 
-fn parse_actions(iter: impl Iterator<Item = String>) -> impl Iterator<Item = Action> {
-    iter.map(|line| {
-        let (action_str, value_str) = line.split_at(1);
-        let value = i32::from_str(value_str).unwrap();
-        match action_str {
-            "N" => Action::Move(Direction::North, value),
-            "S" => Action::Move(Direction::South, value),
-            "E" => Action::Move(Direction::East, value),
-            "W" => Action::Move(Direction::West, value),
-            "L" => Action::Turn(Turn::Left, value),
-            "R" => Action::Turn(Turn::Right, value),
-            "F" => Action::Forward(value),
-            _ => panic!("Unknown action: {}", action_str),
-        }
-    })
+pub fn parse_actions(iter: impl Iterator<Item = String>) -> Result<Vec<Action>, ParseError> {
+    iter.enumerate()
+        .map(|(i, line)| {
+            let line_no = i + 1;
+            let action_str = match line.chars().next() {
+                Some(ch) => &line[..ch.len_utf8()],
+                None => {
+                    return Err(ParseError::new(
+                        Day12::DAY,
+                        line_no,
+                        1,
+                        "Expected an action",
+                    ))
+                }
+            };
+            let value_str = &line[action_str.len()..];
+            let value: i32 = parse_number(Day12::DAY, line_no, &line, value_str)?;
+            if (action_str == "L" || action_str == "R") && (value < 0 || value % 90 != 0) {
+                return Err(ParseError::at(
+                    Day12::DAY,
+                    line_no,
+                    &line,
+                    value_str,
+                    "Expected a non-negative multiple of 90 degrees",
+                ));
+            }
+            Ok(match action_str {
+                "N" => Action::Move(Direction::North, value),
+                "S" => Action::Move(Direction::South, value),
+                "E" => Action::Move(Direction::East, value),
+                "W" => Action::Move(Direction::West, value),
+                "L" => Action::Turn(Turn::Left, value),
+                "R" => Action::Turn(Turn::Right, value),
+                "F" => Action::Forward(value),
+                _ => {
+                    return Err(ParseError::new(
+                        Day12::DAY,
+                        line_no,
+                        1,
+                        format!("Unknown action: {}", action_str),
+                    ))
+                }
+            })
+        })
+        .collect()
+}
+
+/// Moves `position` by `amount` steps of `step`. Returns `None` if it goes past the range of an `i32`.
+fn advance(position: i32, amount: i32, step: i32) -> Option<i32> {
+    position.checked_add(amount.checked_mul(step)?)
+}
+
+fn manhattan_distance(x: i32, y: i32) -> Option<i32> {
+    x.checked_abs()?.checked_add(y.checked_abs()?)
 }
 
-fn execute_actions_part1(actions: impl Iterator<Item = Action>) -> i32 {
-    let (x, y, _) = actions.fold(
+/// Returns `None` if the ship goes further than an `i32` can count.
+pub fn execute_actions_part1(mut actions: impl Iterator<Item = Action>) -> Option<i32> {
+    let (x, y, _) = actions.try_fold(
         (0, 0, Direction::East),
         |(x, y, dir), action| match action {
-            Action::Move(move_dir, amount) => (
-                x + amount * move_dir.x_amount(),
-                y + amount * move_dir.y_amount(),
+            Action::Move(move_dir, amount) => Some((
+                advance(x, amount, move_dir.x_amount())?,
+                advance(y, amount, move_dir.y_amount())?,
                 dir,
-            ),
-            Action::Forward(amount) => (
-                x + amount * dir.x_amount(),
-                y + amount * dir.y_amount(),
+            )),
+            Action::Forward(amount) => Some((
+                advance(x, amount, dir.x_amount())?,
+                advance(y, amount, dir.y_amount())?,
                 dir,
-            ),
-            Action::Turn(turn, degrees) => (x, y, dir.turn(turn, degrees)),
+            )),
+            Action::Turn(turn, degrees) => Some((x, y, dir.turn(turn, degrees))),
         },
-    );
-    x.abs() + y.abs()
+    )?;
+    manhattan_distance(x, y)
 }
 
-fn execute_actions_part2(actions: impl Iterator<Item = Action>) -> i32 {
-    let (x, y, _, _) = actions.fold(
+/// Returns `None` if the ship or the waypoint goes further than an `i32` can count.
+pub fn execute_actions_part2(mut actions: impl Iterator<Item = Action>) -> Option<i32> {
+    let (x, y, _, _) = actions.try_fold(
         (0, 0, 10, 1),
-        |(ship_x, ship_y, waypoint_x, waypoint_y), action| {
-            let res = match action {
-                Action::Move(move_dir, amount) => (
-                    ship_x,
-                    ship_y,
-                    waypoint_x + amount * move_dir.x_amount(),
-                    waypoint_y + amount * move_dir.y_amount(),
-                ),
-                Action::Forward(amount) => (
-                    ship_x + amount * waypoint_x,
-                    ship_y + amount * waypoint_y,
-                    waypoint_x,
-                    waypoint_y,
-                ),
-                Action::Turn(turn, degrees) => {
-                    let (new_waypoint_x, new_waypoint_y) =
-                        turn.rotate(waypoint_x, waypoint_y, degrees);
-                    (ship_x, ship_y, new_waypoint_x, new_waypoint_y)
-                }
-            };
-            res
+        |(ship_x, ship_y, waypoint_x, waypoint_y), action| match action {
+            Action::Move(move_dir, amount) => Some((
+                ship_x,
+                ship_y,
+                advance(waypoint_x, amount, move_dir.x_amount())?,
+                advance(waypoint_y, amount, move_dir.y_amount())?,
+            )),
+            Action::Forward(amount) => Some((
+                advance(ship_x, amount, waypoint_x)?,
+                advance(ship_y, amount, waypoint_y)?,
+                waypoint_x,
+                waypoint_y,
+            )),
+            Action::Turn(turn, degrees) => {
+                let (new_waypoint_x, new_waypoint_y) =
+                    turn.rotate(waypoint_x, waypoint_y, degrees)?;
+                Some((ship_x, ship_y, new_waypoint_x, new_waypoint_y))
+            }
         },
-    );
-    x.abs() + y.abs()
+    )?;
+    manhattan_distance(x, y)
+}
+
+pub struct Day12;
+
+impl Solution for Day12 {
+    const DAY: u8 = 12;
+    type Input = Vec<Action>;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_actions(lines(input))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        execute_actions_part1(input.iter().cloned()).map(|distance| distance.to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        execute_actions_part2(input.iter().cloned()).map(|distance| distance.to_string())
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day12::{execute_actions_part1, execute_actions_part2, parse_actions, read_file};
+    use crate::day12::{
+        execute_actions_part1, execute_actions_part2, parse_actions, read_file, Day12,
+    };
+    use crate::input::read_fixture;
+    use crate::solution::{lines, Solution};
 
     #[test]
     fn part1_example() {
-        let example = "
-F10
-N3
-F7
-R90
-F11
-"
-        .to_string();
-        let actions = parse_actions(example[1..].lines().map(|s| s.to_string()));
-        let res = execute_actions_part1(actions);
-        assert_eq!(res, 25);
+        let actions = parse_actions(lines(&read_fixture("day12.txt"))).unwrap();
+        let res = execute_actions_part1(actions.into_iter());
+        assert_eq!(res, Some(25));
     }
 
     #[test]
     fn part1() {
         let iter = read_file();
-        let actions = parse_actions(iter);
-        let res = execute_actions_part1(actions);
-        println!("{}", res);
+        let actions = parse_actions(iter).unwrap();
+        let res = execute_actions_part1(actions.into_iter());
+        println!("{:?}", res);
     }
 
     #[test]
     fn part2_example() {
-        let example = "
-F10
-N3
-F7
-R90
-F11
-"
-        .to_string();
-        let actions = parse_actions(example[1..].lines().map(|s| s.to_string()));
-        let res = execute_actions_part2(actions);
-        assert_eq!(res, 286);
+        let actions = parse_actions(lines(&read_fixture("day12.txt"))).unwrap();
+        let res = execute_actions_part2(actions.into_iter());
+        assert_eq!(res, Some(286));
     }
 
     #[test]
     fn part2() {
         let iter = read_file();
-        let actions = parse_actions(iter);
-        let res = execute_actions_part2(actions);
-        println!("{}", res);
+        let actions = parse_actions(iter).unwrap();
+        let res = execute_actions_part2(actions.into_iter());
+        println!("{:?}", res);
+    }
+
+    #[test]
+    fn overflow() {
+        let actions = Day12.parse("F2147483647\nF2147483647\n").unwrap();
+        assert_eq!(Day12.part1(&actions), None);
+        assert_eq!(Day12.part2(&actions), None);
+        let actions = Day12.parse("F2147483647\n").unwrap();
+        assert_eq!(Day12.part1(&actions), Some("2147483647".to_string()));
+        assert_eq!(Day12.part2(&actions), None);
+        // The waypoint cannot turn once it is at i32::MIN, since that has no opposite.
+        let actions = Day12.parse("W2147483647\nW11\n").unwrap();
+        assert_eq!(Day12.part2(&actions), Some("0".to_string()));
+        let actions = Day12.parse("W2147483647\nW11\nR90\n").unwrap();
+        assert_eq!(Day12.part2(&actions), None);
     }
 }
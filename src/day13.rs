@@ -1,144 +1,261 @@
-use itertools::Itertools;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
-use std::str::FromStr;
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day13.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap().to_string())
+use crate::error::{parse_number, ParseError};
+use crate::input::read_day_lines;
+use crate::numeric::{exact_option, Natural, Overflow};
+use crate::solution::{lines, Solution};
+
+pub fn read_file() -> impl Iterator<Item = String> {
+    read_day_lines(Day13::DAY)
 }
 
-///
-/// Part 1
-///
+//
+// Part 1
+//
 
-fn parse_file_part1(lines: impl Iterator<Item = String>) -> (u32, Vec<u32>) {
-    let (line1, line2) = lines.take(2).collect_tuple().unwrap();
-    let timestamp = u32::from_str(line1.as_str()).unwrap();
+pub fn parse_file_part1(
+    mut lines: impl Iterator<Item = String>,
+) -> Result<(u32, Vec<u32>), ParseError> {
+    let line1 = lines
+        .next()
+        .ok_or_else(|| ParseError::new(Day13::DAY, 1, 1, "Expected a timestamp"))?;
+    let timestamp = parse_number(Day13::DAY, 1, &line1, &line1)?;
+    let line2 = lines.next().unwrap_or_default();
     let bus_numbers = line2
-        .split(",")
+        .split(',')
         .filter(|n| *n != "x")
-        .map(|n| u32::from_str(n).unwrap())
-        .collect_vec();
-    (timestamp, bus_numbers)
+        .map(|n| match parse_number(Day13::DAY, 2, &line2, n)? {
+            0 => Err(ParseError::at(
+                Day13::DAY,
+                2,
+                &line2,
+                n,
+                "Expected a bus number above 0",
+            )),
+            bus_number => Ok(bus_number),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if bus_numbers.is_empty() {
+        return Err(ParseError::new(
+            Day13::DAY,
+            2,
+            1,
+            "Expected at least one bus",
+        ));
+    }
+    Ok((timestamp, bus_numbers))
 }
 
-fn find_earliest_bus_and_wait_time(timestamp: u32, bus_numbers: &Vec<u32>) -> (u32, u32) {
+pub fn find_earliest_bus_and_wait_time(timestamp: u32, bus_numbers: &[u32]) -> (u32, u32) {
     bus_numbers
         .iter()
         .map(|bus_number| {
-            let arrival_time = (timestamp / bus_number) * bus_number
-                + (if timestamp % bus_number != 0 {
-                    *bus_number
-                } else {
-                    0
-                });
-            let wait_time = arrival_time - timestamp;
+            let wait_time = (bus_number - timestamp % bus_number) % bus_number;
             (*bus_number, wait_time)
         })
         .min_by_key(|(_bus_number, wait_time)| *wait_time)
         .unwrap()
 }
 
-///
-/// Part 2
-///
+//
+// Part 2
+//
 
-fn parse_file_part2(lines: impl Iterator<Item = String>) -> Vec<(u64, u64)> {
-    let line = lines.skip(1).next().unwrap();
-    parse_line_part2(&line)
+pub fn parse_file_part2(
+    mut lines: impl Iterator<Item = String>,
+) -> Result<Vec<(u64, u64)>, ParseError> {
+    let line = lines.nth(1).unwrap_or_default();
+    parse_line_part2(&line).map_err(|e| e.on_line(2))
 }
 
 /// Returns vector of tuples of first departure time and bus number
-fn parse_line_part2(line: &String) -> Vec<(u64, u64)> {
-    line.split(",")
+pub fn parse_line_part2(line: &str) -> Result<Vec<(u64, u64)>, ParseError> {
+    let buses = line
+        .split(',')
         .enumerate()
         .filter(|(_, n)| *n != "x")
-        .map(|(i, n)| (i as u64, u64::from_str(n).unwrap()))
-        .collect_vec()
+        .map(|(i, n)| match parse_number(Day13::DAY, 1, line, n)? {
+            0 => Err(ParseError::at(
+                Day13::DAY,
+                1,
+                line,
+                n,
+                "Expected a bus number above 0",
+            )),
+            bus_number => Ok((i as u64, bus_number)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if buses.is_empty() {
+        return Err(ParseError::new(
+            Day13::DAY,
+            1,
+            1,
+            "Expected at least one bus",
+        ));
+    }
+    Ok(buses)
+}
+
+/// Returns `None` if no timestamp lines up every bus, which can only happen when bus numbers share a factor.
+pub fn solve_contest(inputs: &[(u64, u64)]) -> Option<u64> {
+    solve_contest_as(inputs).expect("The timestamp overflowed")
 }
 
-fn solve_contest(inputs: &Vec<(u64, u64)>) -> u64 {
-    let mut t = 1;
-    let mut period = 1;
-    for (departure_time, bus_number) in inputs.iter() {
-        loop {
-            if (t + departure_time) % bus_number == 0 {
-                period *= bus_number;
-                break;
+/// Same as `solve_contest`, with the timestamp in any `Natural`.
+pub fn solve_contest_as<N: Natural>(inputs: &[(u64, u64)]) -> Result<Option<N>, Overflow> {
+    let mut t = N::one();
+    let mut period = N::one();
+    for (i, &(departure_time, bus_number)) in inputs.iter().enumerate() {
+        let remainder = (bus_number - departure_time % bus_number) % bus_number;
+        // Adding the period comes back to the same remainder after `cycle` steps, so if none of those match, no
+        // timestamp does. The new period is the least common multiple of the old one and the bus number.
+        let cycle = bus_number / gcd(period.rem_u64(bus_number), bus_number);
+        let mut steps = 0;
+        while t.rem_u64(bus_number) != remainder {
+            steps += 1;
+            if steps == cycle {
+                return Ok(None);
             }
-            t += period
+            t = t.add(&period)?;
+        }
+        // The period is only needed for the buses that follow, and may not fit after the last one.
+        if i + 1 < inputs.len() {
+            period = period.mul(&N::from_u64(cycle))?;
         }
     }
-    t
+    Ok(Some(t))
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+pub struct Day13;
+
+impl Solution for Day13 {
+    const DAY: u8 = 13;
+    type Input = ((u32, Vec<u32>), Vec<(u64, u64)>);
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        Ok((
+            parse_file_part1(lines(input))?,
+            parse_file_part2(lines(input))?,
+        ))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        let ((timestamp, bus_numbers), _) = input;
+        let (bus_number, wait_time) = find_earliest_bus_and_wait_time(*timestamp, bus_numbers);
+        bus_number
+            .checked_mul(wait_time)
+            .map(|answer| answer.to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        exact_option(solve_contest_as(&input.1), || solve_contest_as(&input.1))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::day13::{
         find_earliest_bus_and_wait_time, parse_file_part1, parse_file_part2, parse_line_part2,
-        read_file, solve_contest,
+        read_file, solve_contest, solve_contest_as, Day13,
     };
+    use crate::input::read_fixture;
+    use crate::numeric::{BigUint, Overflow};
+    use crate::solution::{lines, Solution};
 
     #[test]
     fn test_part1_example() {
-        let example = "
-939
-7,13,x,x,59,x,31,19
-";
-        let (timestamp, bus_numbers) =
-            parse_file_part1(example[1..].lines().map(|s| s.to_string()));
+        let (timestamp, bus_numbers) = parse_file_part1(lines(&read_fixture("day13.txt"))).unwrap();
         let (bus_number, wait_time) = find_earliest_bus_and_wait_time(timestamp, &bus_numbers);
         assert_eq!(295, bus_number * wait_time);
     }
 
     #[test]
     fn test_part1() {
-        let (timestamp, bus_numbers) = parse_file_part1(read_file());
+        let (timestamp, bus_numbers) = parse_file_part1(read_file()).unwrap();
         let (bus_number, wait_time) = find_earliest_bus_and_wait_time(timestamp, &bus_numbers);
         println!("{}", bus_number * wait_time);
         assert_eq!(3035, bus_number * wait_time);
     }
 
+    #[test]
+    fn test_part1_overflow() {
+        let notes = Day13.parse("939\n4294967291\n").unwrap();
+        assert_eq!(Day13.part1(&notes), None);
+        let notes = Day13.parse("4294967295\n2\n").unwrap();
+        assert_eq!(Day13.part1(&notes), Some("2".to_string()));
+    }
+
     #[test]
     fn test_part2_example() {
         let line = "17,x,13,19";
-        let input = parse_line_part2(&line.to_string());
+        let input = parse_line_part2(line).unwrap();
         let res = solve_contest(&input);
-        assert_eq!(3417, res);
+        assert_eq!(Some(3417), res);
 
         let line = "67,7,59,61";
-        let input = parse_line_part2(&line.to_string());
+        let input = parse_line_part2(line).unwrap();
         let res = solve_contest(&input);
-        assert_eq!(754018, res);
+        assert_eq!(Some(754018), res);
 
         let line = "67,x,7,59,61";
-        let input = parse_line_part2(&line.to_string());
+        let input = parse_line_part2(line).unwrap();
         let res = solve_contest(&input);
-        assert_eq!(779210, res);
+        assert_eq!(Some(779210), res);
 
         let line = "67,7,x,59,61";
-        let input = parse_line_part2(&line.to_string());
+        let input = parse_line_part2(line).unwrap();
         let res = solve_contest(&input);
-        assert_eq!(1261476, res);
+        assert_eq!(Some(1261476), res);
 
         let line = "1789,37,47,1889";
-        let input = parse_line_part2(&line.to_string());
+        let input = parse_line_part2(line).unwrap();
         let res = solve_contest(&input);
-        assert_eq!(1202161486, res);
+        assert_eq!(Some(1202161486), res);
 
         let line = "7,13,x,x,59,x,31,19";
-        let input = parse_line_part2(&line.to_string());
+        let input = parse_line_part2(line).unwrap();
         let res = solve_contest(&input);
-        assert_eq!(1068781, res);
+        assert_eq!(Some(1068781), res);
     }
 
     #[test]
     fn test_part2() {
-        let input = parse_file_part2(read_file());
+        let input = parse_file_part2(read_file()).unwrap();
         let res = solve_contest(&input);
-        println!("{}", res);
-        assert_eq!(725169163285238, res);
+        println!("{:?}", res);
+        assert_eq!(Some(725169163285238), res);
+    }
+
+    #[test]
+    fn test_part2_overflow() {
+        let input = parse_line_part2("1000003,1000033,x,1000037,1000039").unwrap();
+        assert_eq!(solve_contest_as::<u64>(&input), Err(Overflow));
+        let res = solve_contest_as::<BigUint>(&input).unwrap().unwrap();
+        assert_eq!(res.to_string(), "473310087046366870096527");
+        for (departure_time, bus_number) in input {
+            assert_eq!((&res + departure_time) % bus_number, BigUint::from(0u64));
+        }
+    }
+
+    #[test]
+    fn test_part2_shared_factors() {
+        // Bus 2 leaves at even timestamps, so bus 4 can never leave one minute later.
+        let input = parse_line_part2("2,4").unwrap();
+        assert_eq!(solve_contest_as::<u64>(&input), Ok(None));
+        assert_eq!(solve_contest_as::<BigUint>(&input), Ok(None));
+        let notes = Day13.parse("0\n2,4\n").unwrap();
+        assert_eq!(Day13.part2(&notes), None);
+
+        // Buses 4 and 6 share a factor but still line up, leaving at 52 and 54.
+        let input = parse_line_part2("10,x,4,x,6").unwrap();
+        assert_eq!(solve_contest(&input), Some(50));
+        let input = parse_line_part2("4,x,6,6").unwrap();
+        assert_eq!(solve_contest(&input), None);
     }
 }
@@ -1,20 +1,20 @@
+use crate::error::{column_of, parse_number, ParseError};
+use crate::input::read_day_lines;
+use crate::solution::{lines, Solution};
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
-use std::str::FromStr;
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day14.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap().to_string())
+
+pub fn read_file() -> impl Iterator<Item = String> {
+    read_day_lines(Day14::DAY)
 }
 
-///
-/// Part 1
-///
+//
+// Part 1
+//
 
-enum Instruction {
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Instruction {
     Mask {
         ones: u64,
         zeroes: u64,
@@ -26,50 +26,112 @@ enum Instruction {
     },
 }
 
-fn parse_file(iter: impl Iterator<Item = String>) -> Vec<Instruction> {
-    iter.map(|line| {
-        let (lhs, rhs) = line.split(" = ").collect_tuple().unwrap();
-        match lhs {
-            "mask" => Instruction::Mask {
-                ones: rhs
-                    .chars()
-                    .map(|ch| match ch {
-                        '1' => 1,
-                        _ => 0,
-                    })
-                    .fold(0, |acc, digit| (acc << 1) | digit),
-                zeroes: rhs
-                    .chars()
-                    .map(|ch| match ch {
-                        '0' => 0,
-                        _ => 1,
-                    })
-                    .fold(0, |acc, digit| (acc << 1) | digit),
-                x_indexes: rhs
-                    .chars()
-                    .enumerate()
-                    .filter_map(|(i, ch)| {
-                        if ch == 'X' {
-                            Some((rhs.len() - i - 1) as u8)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect_vec(),
-            },
-            _ => Instruction::Mem {
-                address: u64::from_str(&lhs[4..lhs.len() - 1]).unwrap(),
-                value: u64::from_str(&rhs).unwrap(),
-            },
-        }
+pub fn parse_file(iter: impl Iterator<Item = String>) -> Result<Vec<Instruction>, ParseError> {
+    let mut instructions = Vec::new();
+    for (i, line) in iter.enumerate() {
+        let line_no = i + 1;
+        let (lhs, rhs) = line.split_once(" = ").ok_or_else(|| {
+            ParseError::new(Day14::DAY, line_no, 1, "Expected '<target> = <value>'")
+        })?;
+        let instruction = match lhs {
+            "mask" => parse_mask(line_no, &line, rhs)?,
+            _ => {
+                let address = lhs
+                    .strip_prefix("mem[")
+                    .and_then(|rest| rest.strip_suffix(']'))
+                    .ok_or_else(|| {
+                        ParseError::new(
+                            Day14::DAY,
+                            line_no,
+                            1,
+                            "Expected 'mask' or 'mem[<address>]'",
+                        )
+                    })?;
+                if instructions.is_empty() {
+                    return Err(ParseError::new(
+                        Day14::DAY,
+                        line_no,
+                        1,
+                        "Expected a mask before the first write",
+                    ));
+                }
+                Instruction::Mem {
+                    address: parse_36_bit(line_no, &line, address, "address")?,
+                    value: parse_36_bit(line_no, &line, rhs, "value")?,
+                }
+            }
+        };
+        instructions.push(instruction);
+    }
+    Ok(instructions)
+}
+
+/// Parses an address or a value, which like the masks must fit in 36 bits.
+fn parse_36_bit(line_no: usize, line: &str, part: &str, what: &str) -> Result<u64, ParseError> {
+    let n: u64 = parse_number(Day14::DAY, line_no, line, part)?;
+    if n >= 1 << 36 {
+        return Err(ParseError::at(
+            Day14::DAY,
+            line_no,
+            line,
+            part,
+            format!("Expected a 36-bit {}", what),
+        ));
+    }
+    Ok(n)
+}
+
+fn parse_mask(line_no: usize, line: &str, mask: &str) -> Result<Instruction, ParseError> {
+    if let Some((i, ch)) = mask.chars().find_position(|ch| !"01X".contains(*ch)) {
+        return Err(ParseError::new(
+            Day14::DAY,
+            line_no,
+            column_of(line, mask) + i,
+            format!("Unexpected mask character: '{}'", ch),
+        ));
+    }
+    if mask.len() != 36 {
+        return Err(ParseError::at(
+            Day14::DAY,
+            line_no,
+            line,
+            mask,
+            "Expected a 36-bit mask",
+        ));
+    }
+    Ok(Instruction::Mask {
+        ones: mask
+            .chars()
+            .map(|ch| match ch {
+                '1' => 1,
+                _ => 0,
+            })
+            .fold(0, |acc, digit| (acc << 1) | digit),
+        zeroes: mask
+            .chars()
+            .map(|ch| match ch {
+                '0' => 0,
+                _ => 1,
+            })
+            .fold(0, |acc, digit| (acc << 1) | digit),
+        x_indexes: mask
+            .chars()
+            .enumerate()
+            .filter_map(|(i, ch)| {
+                if ch == 'X' {
+                    Some((mask.len() - i - 1) as u8)
+                } else {
+                    None
+                }
+            })
+            .collect_vec(),
     })
-    .collect_vec()
 }
 
-fn process_instructions(instructions: &Vec<Instruction>) -> u64 {
+pub fn process_instructions(instructions: &[Instruction]) -> u64 {
     let mut mem = HashMap::new();
-    let mut ones_mask = u64::min_value();
-    let mut zeroes_mask = u64::max_value();
+    let mut ones_mask = u64::MIN;
+    let mut zeroes_mask = u64::MAX;
 
     for instruction in instructions {
         match instruction {
@@ -91,12 +153,17 @@ fn process_instructions(instructions: &Vec<Instruction>) -> u64 {
     mem.values().sum()
 }
 
-///
-/// Part 2
-///
-fn process_instructions_v2(instructions: &Vec<Instruction>) -> u64 {
+//
+// Part 2
+//
+
+/// The most floating bits a mask may have for part 2, which writes to every address they can make.
+pub const MAX_FLOATING_BITS: usize = 16;
+
+/// Returns `None` if a mask with more than `MAX_FLOATING_BITS` floating bits is used to write to memory.
+pub fn process_instructions_v2(instructions: &[Instruction]) -> Option<u64> {
     let mut mem = HashMap::new();
-    let mut ones_mask = u64::min_value();
+    let mut ones_mask = u64::MIN;
     let mut x_indexes_mask = None;
 
     for instruction in instructions {
@@ -110,19 +177,23 @@ fn process_instructions_v2(instructions: &Vec<Instruction>) -> u64 {
                 x_indexes_mask.replace(x_indexes);
             }
             Instruction::Mem { address, value } => {
+                let x_indexes = x_indexes_mask.unwrap();
+                if x_indexes.len() > MAX_FLOATING_BITS {
+                    return None;
+                }
                 let base_address = address | ones_mask;
-                for final_address in enumerate_addresses(base_address, x_indexes_mask.unwrap()) {
+                for final_address in enumerate_addresses(base_address, x_indexes) {
                     mem.insert(final_address, *value);
                 }
             }
         };
     }
 
-    mem.values().sum()
+    Some(mem.values().sum())
 }
 
-fn enumerate_addresses(base_address: u64, x_indexes: &Vec<u8>) -> VecDeque<u64> {
-    let capacity: usize = (2 as usize).pow(x_indexes.len() as u32) as usize;
+pub fn enumerate_addresses(base_address: u64, x_indexes: &[u8]) -> VecDeque<u64> {
+    let capacity: usize = 2_usize.pow(x_indexes.len() as u32);
     let mut res = VecDeque::with_capacity(capacity);
     res.push_back(base_address);
 
@@ -140,26 +211,94 @@ fn enumerate_addresses(base_address: u64, x_indexes: &Vec<u8>) -> VecDeque<u64>
     res
 }
 
+pub struct Day14;
+
+impl Solution for Day14 {
+    const DAY: u8 = 14;
+    type Input = Vec<Instruction>;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_file(lines(input))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        Some(process_instructions(input).to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        process_instructions_v2(input).map(|sum| sum.to_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::day14::{parse_file, process_instructions, process_instructions_v2, read_file};
+    use crate::day14::{
+        enumerate_addresses, parse_file, process_instructions, process_instructions_v2, read_file,
+        MAX_FLOATING_BITS,
+    };
+    use crate::error::ParseError;
+    use crate::generate::{self, Rng};
+    use crate::input::read_fixture;
+    use crate::solution::lines;
+    use itertools::Itertools;
+    use proptest::prelude::*;
+    use std::collections::HashMap;
+
+    /// Runs a program a bit at a time, straight from the mask strings. Returns the sums of memory for both parts.
+    fn brute_force(program: &str) -> (u64, u64) {
+        let mut mask = "";
+        let mut mem1 = HashMap::new();
+        let mut mem2 = HashMap::new();
+        for line in program.lines() {
+            let (lhs, rhs) = line.split_once(" = ").unwrap();
+            if lhs == "mask" {
+                mask = rhs;
+                continue;
+            }
+            let address: u64 = lhs[4..lhs.len() - 1].parse().unwrap();
+            let value: u64 = rhs.parse().unwrap();
+
+            let mut masked = 0;
+            let mut addresses = vec![0];
+            for (i, ch) in mask.chars().enumerate() {
+                let bit = 35 - i;
+                let value_bit = (value >> bit) & 1;
+                let address_bit = (address >> bit) & 1;
+                masked |= match ch {
+                    'X' => value_bit,
+                    '1' => 1,
+                    _ => 0,
+                } << bit;
+                addresses = match ch {
+                    'X' => addresses
+                        .iter()
+                        .flat_map(|a| vec![*a, *a | (1 << bit)])
+                        .collect_vec(),
+                    '1' => addresses.iter().map(|a| a | (1 << bit)).collect_vec(),
+                    _ => addresses
+                        .iter()
+                        .map(|a| a | (address_bit << bit))
+                        .collect_vec(),
+                };
+            }
+            mem1.insert(address, masked);
+            for address in addresses {
+                mem2.insert(address, value);
+            }
+        }
+        (mem1.values().sum(), mem2.values().sum())
+    }
 
     #[test]
     fn test_part1_example() {
-        let example = "
-mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X
-mem[8] = 11
-mem[7] = 101
-mem[8] = 0
-";
-        let instructions = parse_file(example[1..].lines().map(|s| s.to_string()));
+        let instructions = parse_file(lines(&read_fixture("day14.txt"))).unwrap();
         let res = process_instructions(&instructions);
         assert_eq!(res, 165);
     }
 
     #[test]
     fn part1() {
-        let instructions = parse_file(read_file());
+        let instructions = parse_file(read_file()).unwrap();
         let res = process_instructions(&instructions);
         println!("{}", res);
         assert_eq!(res, 4886706177792);
@@ -167,22 +306,95 @@ mem[8] = 0
 
     #[test]
     fn test_part2_example() {
-        let example = "
-mask = 000000000000000000000000000000X1001X
-mem[42] = 100
-mask = 00000000000000000000000000000000X0XX
-mem[26] = 1
-";
-        let instructions = parse_file(example[1..].lines().map(|s| s.to_string()));
+        let instructions = parse_file(lines(&read_fixture("day14-2.txt"))).unwrap();
         let res = process_instructions_v2(&instructions);
-        assert_eq!(res, 208);
+        assert_eq!(res, Some(208));
+    }
+
+    #[test]
+    fn part2_too_many_floating_bits() {
+        let floating = |count: usize| {
+            let mask = format!("{:0>36}", "X".repeat(count));
+            parse_file(lines(&format!("mask = {}\nmem[8] = 11\n", mask))).unwrap()
+        };
+        assert_eq!(
+            process_instructions_v2(&floating(MAX_FLOATING_BITS)),
+            Some(11 << MAX_FLOATING_BITS)
+        );
+        assert_eq!(
+            process_instructions_v2(&floating(MAX_FLOATING_BITS + 1)),
+            None
+        );
+        assert_eq!(process_instructions_v2(&floating(36)), None);
+        // A mask that is replaced before any write is never expanded.
+        let mut instructions = floating(36);
+        instructions.remove(1);
+        instructions.extend(floating(0));
+        assert_eq!(process_instructions_v2(&instructions), Some(11));
     }
 
     #[test]
     fn part2() {
-        let instructions = parse_file(read_file());
+        let instructions = parse_file(read_file()).unwrap();
         let res = process_instructions_v2(&instructions);
-        println!("{}", res);
-        assert_eq!(res, 3348493585827);
+        println!("{:?}", res);
+        assert_eq!(res, Some(3348493585827));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn masking_matches_brute_force(seed in any::<u64>(), size in 2..40usize) {
+            let program = generate::day14(&mut Rng::new(seed), size);
+            let instructions = parse_file(lines(&program)).unwrap();
+            let (part1, part2) = brute_force(&program);
+            prop_assert_eq!(process_instructions(&instructions), part1);
+            prop_assert_eq!(process_instructions_v2(&instructions), Some(part2));
+        }
+
+        #[test]
+        fn floating_addresses_are_distinct(
+            base in 0..1u64 << 36,
+            x_indexes in proptest::sample::subsequence((0..36u8).collect_vec(), 0..=9),
+        ) {
+            let addresses = enumerate_addresses(base, &x_indexes);
+            prop_assert_eq!(addresses.len(), 1 << x_indexes.len());
+            prop_assert_eq!(addresses.iter().unique().count(), addresses.len());
+            let fixed = !x_indexes.iter().fold(0u64, |mask, i| mask | (1 << i));
+            prop_assert!(addresses.iter().all(|a| a & fixed == base & fixed));
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |s: &str| parse_file(s.lines().map(|l| l.to_string()));
+        let mask = "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X";
+        assert_eq!(
+            parse(&format!("{}\nmem[8] = eleven", mask)).unwrap_err(),
+            ParseError::new(14, 2, 10, "Invalid number: 'eleven'")
+        );
+        assert_eq!(
+            parse("mask = XX2X").unwrap_err(),
+            ParseError::new(14, 1, 10, "Unexpected mask character: '2'")
+        );
+        assert_eq!(
+            parse("mask = X01").unwrap_err(),
+            ParseError::new(14, 1, 8, "Expected a 36-bit mask")
+        );
+        assert_eq!(
+            parse("mem[8] = 11").unwrap_err(),
+            ParseError::new(14, 1, 1, "Expected a mask before the first write")
+        );
+        assert_eq!(parse(&format!("{}\nmem 8 = 11", mask)).unwrap_err().line, 2);
+        assert_eq!(
+            parse(&format!("{}\nmem[0] = 18446744073709551615", mask)).unwrap_err(),
+            ParseError::new(14, 2, 10, "Expected a 36-bit value")
+        );
+        assert_eq!(
+            parse(&format!("{}\nmem[68719476736] = 1", mask)).unwrap_err(),
+            ParseError::new(14, 2, 5, "Expected a 36-bit address")
+        );
+        assert!(parse(&format!("{}\nmem[68719476735] = 68719476735", mask)).is_ok());
     }
 }
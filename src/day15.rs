@@ -1,12 +1,18 @@
-use itertools::Itertools;
+use crate::budget::{Budget, Interrupted};
+use crate::error::{parse_number, ParseError};
+use crate::input::read_day;
+use crate::solution::Solution;
 use std::collections::{HashMap, VecDeque};
-use std::str::FromStr;
 
-fn parse_input(input: &str) -> Vec<u64> {
+pub fn read_file() -> Result<Vec<u64>, ParseError> {
+    parse_input(read_day(Day15::DAY).trim())
+}
+
+pub fn parse_input(input: &str) -> Result<Vec<u64>, ParseError> {
     input
-        .split(",")
-        .map(|s| u64::from_str(s).unwrap())
-        .collect_vec()
+        .split(',')
+        .map(|s| parse_number(Day15::DAY, 1, input, s))
+        .collect()
 }
 
 struct MemoryGame {
@@ -46,7 +52,17 @@ impl MemoryGame {
     }
 }
 
-fn memory_game(starting_numbers: &Vec<u64>, iterations: u64) -> u64 {
+pub fn memory_game(starting_numbers: &[u64], iterations: u64) -> u64 {
+    memory_game_within(starting_numbers, iterations, &mut Budget::unlimited())
+        .expect("An unlimited budget never runs out")
+}
+
+/// Same as `memory_game`, stopping if the budget runs out. Each turn is a step.
+pub fn memory_game_within(
+    starting_numbers: &[u64],
+    iterations: u64,
+    budget: &mut Budget,
+) -> Result<u64, Interrupted> {
     let mut game = MemoryGame::new();
     for n in starting_numbers {
         game.speak(*n);
@@ -55,26 +71,60 @@ fn memory_game(starting_numbers: &Vec<u64>, iterations: u64) -> u64 {
     while game.turn < iterations {
         let next = game.next();
         game.speak(next);
+        budget.tick(Some(iterations))?;
+    }
+
+    Ok(game.next())
+}
+
+pub struct Day15;
+
+impl Solution for Day15 {
+    const DAY: u8 = 15;
+    type Input = Vec<u64>;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input.trim())
     }
 
-    game.next()
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        Some(memory_game(input, 2020).to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        Some(memory_game(input, 30000000).to_string())
+    }
+
+    fn solve_within(
+        &self,
+        part: u8,
+        input: &Self::Input,
+        budget: &mut Budget,
+    ) -> Result<Option<String>, Interrupted> {
+        match part {
+            1 => Ok(self.part1(input)),
+            2 => memory_game_within(input, 30000000, budget).map(|res| Some(res.to_string())),
+            _ => Ok(None),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day15::{memory_game, parse_input};
+    use crate::budget::{Budget, Interrupted, Progress, CHECK_INTERVAL};
+    use crate::day15::{memory_game, memory_game_within, parse_input, read_file};
+    use std::time::Duration;
 
     #[test]
     fn test_part1_example() {
         let example = "0,3,6";
-        let inputs = parse_input(example);
+        let inputs = parse_input(example).unwrap();
         assert_eq!(memory_game(&inputs, 2020), 436);
     }
 
     #[test]
     fn test_part1() {
-        let example = "12,1,16,3,11,0";
-        let inputs = parse_input(example);
+        let inputs = read_file().unwrap();
         let res = memory_game(&inputs, 2020);
         println!("{}", res);
         assert_eq!(memory_game(&inputs, 2020), 1696);
@@ -83,16 +133,41 @@ mod tests {
     #[test]
     fn test_part2_example() {
         let example = "0,3,6";
-        let inputs = parse_input(example);
+        let inputs = parse_input(example).unwrap();
         assert_eq!(memory_game(&inputs, 2020), 436);
     }
 
     #[test]
     fn test_part2() {
-        let example = "12,1,16,3,11,0";
-        let inputs = parse_input(example);
+        let inputs = read_file().unwrap();
         let res = memory_game(&inputs, 30000000);
         println!("{}", res);
         assert_eq!(res, 37385);
     }
+
+    #[test]
+    fn test_budget() {
+        // The turns after the three starting numbers are the steps.
+        let iterations = 3 * CHECK_INTERVAL + 10;
+        let mut reports = 0;
+        let mut budget = Budget::unlimited().with_progress(|_| reports += 1);
+        let res = memory_game_within(&[0, 3, 6], iterations, &mut budget);
+        assert_eq!(res, Ok(memory_game(&[0, 3, 6], iterations)));
+        assert_eq!(budget.done(), iterations - 4);
+        drop(budget);
+        assert_eq!(reports, 3);
+
+        let res = memory_game_within(
+            &[0, 3, 6],
+            30000000,
+            &mut Budget::time_limit(Duration::ZERO),
+        );
+        assert_eq!(
+            res,
+            Err(Interrupted::TimedOut(Progress {
+                done: CHECK_INTERVAL,
+                total: Some(30000000)
+            }))
+        );
+    }
 }
@@ -1,80 +1,141 @@
+use crate::assignment::{assign_with, Assignment};
+use crate::error::{parse_number, ParseError};
+use crate::input::read_day;
+use crate::solution::{Certify, Solution};
+use crate::trace::{Event, Trace};
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
-use std::iter::FromIterator;
-use std::str::FromStr;
 
-fn read_file() -> String {
-    let file = File::open("input/day16.txt").unwrap();
-    let mut res = String::new();
-    BufReader::new(file).read_to_string(&mut res).unwrap();
-    res
+pub fn read_file() -> String {
+    read_day(Day16::DAY)
 }
 
-struct Notes {
-    fields: Vec<(String, u64, u64, u64, u64)>,
-    my_ticket: Vec<u64>,
-    nearby_tickets: Vec<Vec<u64>>,
+#[derive(Serialize, Deserialize)]
+pub struct Notes {
+    pub fields: Vec<(String, u64, u64, u64, u64)>,
+    pub my_ticket: Vec<u64>,
+    pub nearby_tickets: Vec<Vec<u64>>,
 }
 
 impl Notes {
-    fn is_valid_for_any_field(&self, num: u64) -> bool {
+    pub fn is_valid_for_any_field(&self, num: u64) -> bool {
         self.fields.iter().any(|(_, min1, max1, min2, max2)| {
             (num >= *min1 && num <= *max1) || (num >= *min2 && num <= *max2)
         })
     }
 }
 
-fn parse_file(file: &String) -> Notes {
-    let (part1, part2, part3) = file.split("\n\n").collect_tuple().unwrap();
-
-    let fields = part1
-        .lines()
-        .map(|line| {
-            let (field, ranges) = line.split(": ").collect_tuple().unwrap();
-            let (range1, range2) = ranges.split(" or ").collect_tuple().unwrap();
-            let (min1, max1) = range1
-                .split("-")
-                .map(|num| u64::from_str(num).unwrap())
-                .collect_tuple()
-                .unwrap();
-            let (min2, max2) = range2
-                .split("-")
-                .map(|num| u64::from_str(num).unwrap())
-                .collect_tuple()
-                .unwrap();
-            (field.to_string(), min1, max1, min2, max2)
-        })
-        .collect_vec();
+pub fn parse_file(file: &str) -> Result<Notes, ParseError> {
+    let mut lines = file.lines().enumerate().map(|(i, line)| (i + 1, line));
+    let end = file.lines().count() + 1;
 
-    let my_ticket = part2
-        .lines()
-        .skip(1)
-        .flat_map(|line| line.split(","))
-        .map(|num| u64::from_str(num).unwrap())
-        .collect_vec();
+    let mut fields = Vec::new();
+    for (line_no, line) in lines.by_ref().take_while(|(_, line)| !line.is_empty()) {
+        fields.push(parse_field(line_no, line)?);
+    }
 
-    let nearby_tickets = part3
-        .lines()
-        .skip(1)
-        .map(|line| {
-            line.split(",")
-                .map(|num| u64::from_str(num).unwrap())
-                .collect_vec()
-        })
-        .collect_vec();
+    expect_header(lines.next(), end, "your ticket:")?;
+    let my_ticket = match lines.next() {
+        Some((line_no, line)) => parse_ticket(line_no, line, fields.len())?,
+        None => return Err(ParseError::new(Day16::DAY, end, 1, "Expected my ticket")),
+    };
+
+    if let Some((line_no, line)) = lines.next().filter(|(_, line)| !line.is_empty()) {
+        return Err(ParseError::new(
+            Day16::DAY,
+            line_no,
+            1,
+            format!("Expected an empty line, found '{}'", line),
+        ));
+    }
+    expect_header(lines.next(), end, "nearby tickets:")?;
+    let nearby_tickets = lines
+        .map(|(line_no, line)| parse_ticket(line_no, line, fields.len()))
+        .collect::<Result<Vec<_>, _>>()?;
 
-    Notes {
+    Ok(Notes {
         fields,
         my_ticket,
         nearby_tickets,
+    })
+}
+
+fn parse_field(line_no: usize, line: &str) -> Result<(String, u64, u64, u64, u64), ParseError> {
+    let (field, ranges) = line.split_once(": ").ok_or_else(|| {
+        ParseError::new(
+            Day16::DAY,
+            line_no,
+            1,
+            "Expected '<field>: <range> or <range>'",
+        )
+    })?;
+    let (range1, range2) = ranges.split_once(" or ").ok_or_else(|| {
+        ParseError::at(
+            Day16::DAY,
+            line_no,
+            line,
+            ranges,
+            "Expected two ranges separated by ' or '",
+        )
+    })?;
+    let (min1, max1) = parse_range(line_no, line, range1)?;
+    let (min2, max2) = parse_range(line_no, line, range2)?;
+    Ok((field.to_string(), min1, max1, min2, max2))
+}
+
+fn parse_range(line_no: usize, line: &str, range: &str) -> Result<(u64, u64), ParseError> {
+    let (min, max) = range.split_once('-').ok_or_else(|| {
+        ParseError::at(
+            Day16::DAY,
+            line_no,
+            line,
+            range,
+            "Expected a range of the form min-max",
+        )
+    })?;
+    Ok((
+        parse_number(Day16::DAY, line_no, line, min)?,
+        parse_number(Day16::DAY, line_no, line, max)?,
+    ))
+}
+
+fn parse_ticket(line_no: usize, line: &str, field_count: usize) -> Result<Vec<u64>, ParseError> {
+    let ticket = line
+        .split(',')
+        .map(|num| parse_number(Day16::DAY, line_no, line, num))
+        .collect::<Result<Vec<_>, _>>()?;
+    if ticket.len() != field_count {
+        return Err(ParseError::new(
+            Day16::DAY,
+            line_no,
+            1,
+            format!("Expected {} values, found {}", field_count, ticket.len()),
+        ));
+    }
+    Ok(ticket)
+}
+
+fn expect_header(line: Option<(usize, &str)>, end: usize, header: &str) -> Result<(), ParseError> {
+    match line {
+        Some((_, line)) if line == header => Ok(()),
+        Some((line_no, _)) => Err(ParseError::new(
+            Day16::DAY,
+            line_no,
+            1,
+            format!("Expected '{}'", header),
+        )),
+        None => Err(ParseError::new(
+            Day16::DAY,
+            end,
+            1,
+            format!("Expected '{}'", header),
+        )),
     }
 }
 
 // Part 1
-fn ticket_scanning_error_rate(notes: &Notes) -> u64 {
+pub fn ticket_scanning_error_rate(notes: &Notes) -> u64 {
     notes
         .nearby_tickets
         .iter()
@@ -84,96 +145,217 @@ fn ticket_scanning_error_rate(notes: &Notes) -> u64 {
 }
 
 // Part 2
-fn assign_fields(notes: &Notes) -> Vec<&String> {
+/// Works out which field is at each index of the tickets, ignoring tickets with values that are invalid for any field.
+pub fn assign_fields(notes: &Notes) -> Assignment<&String> {
+    assign_fields_traced(notes, &mut Trace::off())
+}
+
+/// Same as `assign_fields`, recording a `discard` event for each invalid nearby ticket, a `candidates` event with the
+/// fields each index could hold, an `eliminate` event for each round that settles an index and takes its field away
+/// from the others, then the `assignment`.
+pub fn assign_fields_traced<'a>(notes: &'a Notes, trace: &mut Trace) -> Assignment<&'a String> {
     let nearby_valid = notes
         .nearby_tickets
         .iter()
-        .filter(|ticket| {
-            ticket
+        .enumerate()
+        .filter(|(ticket_index, ticket)| {
+            match ticket
                 .iter()
-                .all(|value| notes.is_valid_for_any_field(*value))
+                .find(|value| !notes.is_valid_for_any_field(**value))
+            {
+                Some(value) => {
+                    trace.emit(|| {
+                        Event::new("discard")
+                            .with("ticket", ticket_index)
+                            .with("value", value)
+                    });
+                    false
+                }
+                None => true,
+            }
         })
+        .map(|(_, ticket)| ticket)
         .collect_vec();
 
-    // For each ticket index, find candidate fields. Multiple fields can be candidates.
-    let mut candidates: Vec<HashSet<&String>> = (0..notes.fields.len())
+    // For each ticket index, find the fields for which all tickets are valid for the values at that index.
+    let candidates = (0..notes.fields.len())
         .map(|field_index| {
-            // Find field names for which all tickets are valid for the values at the field index.
-            let field_names =
-                notes
-                    .fields
-                    .iter()
-                    .filter_map(|(field_name, min1, max1, min2, max2)| {
-                        let matches = nearby_valid.iter().all(|ticket| {
-                            let value = ticket[field_index];
-                            (value >= *min1 && value <= *max1) || (value >= *min2 && value <= *max2)
-                        });
-
-                        if matches {
-                            Some(field_name)
-                        } else {
-                            None
-                        }
-                    });
-
-            HashSet::from_iter(field_names)
+            notes
+                .fields
+                .iter()
+                .filter(|(_, min1, max1, min2, max2)| {
+                    nearby_valid.iter().all(|ticket| {
+                        let value = ticket[field_index];
+                        (value >= *min1 && value <= *max1) || (value >= *min2 && value <= *max2)
+                    })
+                })
+                .map(|(field_name, _, _, _, _)| field_name)
+                .collect::<HashSet<_>>()
         })
         .collect_vec();
-
-    // Remove unique field assignments from the other sets.
-    // Continue until all sets are unique.
-    loop {
-        let singletons = candidates
+    trace.emit(|| {
+        let fields = candidates
             .iter()
-            .filter_map(|fields| {
-                if fields.len() == 1 {
-                    Some(*fields.iter().next().unwrap())
-                } else {
-                    None
-                }
-            })
+            .map(|fields| fields.iter().sorted().collect_vec())
             .collect_vec();
+        Event::new("candidates").with("fields", fields)
+    });
+
+    let assignment = assign_with(&candidates, |index, field, removed_from| {
+        trace.emit(|| {
+            Event::new("eliminate")
+                .with("index", index)
+                .with("field", field)
+                .with("removed_from", removed_from)
+        })
+    });
+    trace.emit(|| match &assignment {
+        Assignment::Unique(fields) => Event::new("assignment").with("fields", fields),
+        Assignment::Ambiguous(fields, alternatives) => Event::new("ambiguous")
+            .with("fields", fields)
+            .with("alternatives", alternatives),
+        Assignment::Unsatisfiable => Event::new("unsatisfiable"),
+    });
+    assignment
+}
+
+/// Multiplies the values on my ticket for the fields whose names start with "departure".
+pub fn departure_product(notes: &Notes, field_order: &[&String]) -> u64 {
+    field_order
+        .iter()
+        .enumerate()
+        .filter_map(|(i, field)| {
+            if field.starts_with("departure") {
+                Some(notes.my_ticket[i])
+            } else {
+                None
+            }
+        })
+        .product()
+}
+
+/// Checks that `field_order` names every field once, and that every value at each index of the valid nearby tickets is
+/// in the ranges of the field named for that index. Returns the departure product of my ticket.
+pub fn check_field_order(notes: &Notes, field_order: &[String]) -> Result<u64, String> {
+    if field_order.len() != notes.fields.len() {
+        return Err(format!(
+            "Expected {} fields, found {}",
+            notes.fields.len(),
+            field_order.len()
+        ));
+    }
+    if let Some(name) = field_order.iter().duplicates().next() {
+        return Err(format!("Field '{}' is used twice", name));
+    }
+    let fields = field_order
+        .iter()
+        .map(|name| {
+            notes
+                .fields
+                .iter()
+                .find(|field| field.0 == *name)
+                .ok_or_else(|| format!("There is no field '{}'", name))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
-        if singletons.len() == candidates.len() {
-            break;
+    let in_ranges = |(_, min1, max1, min2, max2): &(String, u64, u64, u64, u64), value: u64| {
+        (*min1..=*max1).contains(&value) || (*min2..=*max2).contains(&value)
+    };
+    let valid_tickets = notes.nearby_tickets.iter().filter(|ticket| {
+        ticket
+            .iter()
+            .all(|value| notes.fields.iter().any(|field| in_ranges(field, *value)))
+    });
+    for ticket in valid_tickets {
+        if let Some((i, value)) = ticket
+            .iter()
+            .enumerate()
+            .find(|(i, value)| !in_ranges(fields[*i], **value))
+        {
+            return Err(format!(
+                "{} at index {} is not a valid '{}'",
+                value, i, field_order[i]
+            ));
         }
+    }
+    Ok(departure_product(notes, &field_order.iter().collect_vec()))
+}
 
-        for field in singletons {
-            for v in candidates.iter_mut() {
-                if v.len() > 1 {
-                    v.remove(field);
-                }
+pub struct Day16;
+
+impl Solution for Day16 {
+    const DAY: u8 = 16;
+    type Input = Notes;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_file(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        Some(ticket_scanning_error_rate(input).to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        let field_order = assign_fields(input);
+        Some(departure_product(input, field_order.unique()?).to_string())
+    }
+
+    fn explain(&self, part: u8, input: &Self::Input, trace: &mut Trace) -> Option<String> {
+        match part {
+            1 => self.part1(input),
+            2 => {
+                let field_order = assign_fields_traced(input, trace);
+                Some(departure_product(input, field_order.unique()?).to_string())
             }
+            _ => None,
         }
     }
+}
 
-    candidates
-        .iter()
-        .map(|v| *v.iter().next().unwrap())
-        .collect_vec()
+impl Certify for Day16 {
+    /// The name of the field at each index of the tickets, for part 2.
+    type Certificate = Vec<String>;
+
+    fn certify(&self, part: u8, input: &Self::Input) -> Option<Self::Certificate> {
+        match part {
+            2 => Some(
+                assign_fields(input)
+                    .unique()?
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    fn check(
+        &self,
+        _part: u8,
+        input: &Self::Input,
+        certificate: &Self::Certificate,
+    ) -> Result<String, String> {
+        check_field_order(input, certificate).map(|product| product.to_string())
+    }
+
+    fn describe(&self, certificate: &Self::Certificate) -> String {
+        certificate.join(", ")
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day16::{assign_fields, parse_file, read_file, ticket_scanning_error_rate};
+    use crate::day16::{
+        assign_fields, assign_fields_traced, check_field_order, departure_product, parse_file,
+        read_file, ticket_scanning_error_rate,
+    };
+    use crate::error::ParseError;
+    use crate::input::read_fixture;
+    use crate::trace::Trace;
 
     #[test]
     fn test_part1_example() {
-        let example = "
-class: 1-3 or 5-7
-row: 6-11 or 33-44
-seat: 13-40 or 45-50
-
-your ticket:
-7,1,14
-
-nearby tickets:
-7,3,47
-40,4,50
-55,2,20
-38,6,12
-";
-        let notes = parse_file(&example[1..].to_string());
+        let notes = parse_file(&read_fixture("day16.txt")).unwrap();
         let res = ticket_scanning_error_rate(&notes);
         assert_eq!(res, 71);
     }
@@ -181,50 +363,119 @@ nearby tickets:
     #[test]
     fn test_part1() {
         let file = read_file();
-        let notes = parse_file(&file);
+        let notes = parse_file(&file).unwrap();
         let res = ticket_scanning_error_rate(&notes);
         println!("{}", res);
     }
 
     #[test]
     fn test_part2_example() {
-        let example = "
-class: 0-1 or 4-19
-row: 0-5 or 8-19
-seat: 0-13 or 16-19
-
-your ticket:
-11,12,13
-
-nearby tickets:
-3,9,18
-15,1,5
-5,14,9
-";
-        let notes = parse_file(&example[1..].to_string());
+        let notes = parse_file(&read_fixture("day16-2.txt")).unwrap();
         let res = assign_fields(&notes);
         println!("{:?}", res);
-        assert_eq!(res, vec!["row", "class", "seat"]);
+        assert_eq!(res.unique().unwrap(), ["row", "class", "seat"]);
+
+        let order = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            check_field_order(&notes, &order(&["row", "class", "seat"])),
+            Ok(1)
+        );
+        assert_eq!(
+            check_field_order(&notes, &order(&["class", "row", "seat"])),
+            Err("3 at index 0 is not a valid 'class'".to_string())
+        );
+        assert_eq!(
+            check_field_order(&notes, &order(&["row", "row", "seat"])),
+            Err("Field 'row' is used twice".to_string())
+        );
+        assert!(check_field_order(&notes, &order(&["row", "class"])).is_err());
+        assert!(check_field_order(&notes, &order(&["row", "class", "zone"])).is_err());
     }
 
     #[test]
     fn test_part2() {
         let file = read_file();
-        let notes = parse_file(&file);
+        let notes = parse_file(&file).unwrap();
         let field_order = assign_fields(&notes);
 
-        let res: u64 = field_order
-            .iter()
-            .enumerate()
-            .filter_map(|(i, field)| {
-                if field.starts_with("departure") {
-                    Some(notes.my_ticket[i])
-                } else {
-                    None
-                }
-            })
-            .product();
+        let res = departure_product(&notes, field_order.unique().unwrap());
 
         println!("{}", res);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let notes =
+            "class: 1-3 or 5-7\nrow: 6-11 or 33-44\n\nyour ticket:\n7,1\n\nnearby tickets:\n";
+        assert!(parse_file(&format!("{}7,3\n40,4", notes)).is_ok());
+        assert_eq!(
+            parse_file(&format!("{}7,3\n40,4,50", notes)).err(),
+            Some(ParseError::new(16, 9, 1, "Expected 2 values, found 3"))
+        );
+        assert_eq!(
+            parse_file(&format!("{}7,x", notes)).err(),
+            Some(ParseError::new(16, 8, 3, "Invalid number: 'x'"))
+        );
+        assert_eq!(
+            parse_file("class: 1-3 or 5\n").err(),
+            Some(ParseError::new(
+                16,
+                1,
+                15,
+                "Expected a range of the form min-max"
+            ))
+        );
+        assert_eq!(
+            parse_file("class: 1-3 or 5-7\n\nyour tickets:\n7").err(),
+            Some(ParseError::new(16, 3, 1, "Expected 'your ticket:'"))
+        );
+        assert_eq!(
+            parse_file("class: 1-3 or 5-7\n\nyour ticket:\n7").err(),
+            Some(ParseError::new(16, 5, 1, "Expected 'nearby tickets:'"))
+        );
+    }
+
+    #[test]
+    fn test_trace() {
+        let notes = parse_file(&read_fixture("day16-2.txt")).unwrap();
+        let mut events = Vec::new();
+        let res = assign_fields_traced(&notes, &mut Trace::to(|event| events.push(event)));
+        assert_eq!(res, assign_fields(&notes));
+        let events = events
+            .iter()
+            .map(|event| event.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                r#"candidates fields=[["row"],["class","row"],["class","row","seat"]]"#,
+                "eliminate index=0 field=row removed_from=[1,2]",
+                "eliminate index=1 field=class removed_from=[2]",
+                "eliminate index=2 field=seat removed_from=[]",
+                r#"assignment fields=["row","class","seat"]"#,
+            ]
+        );
+
+        let notes = parse_file(&read_fixture("day16.txt")).unwrap();
+        let mut events = Vec::new();
+        assign_fields_traced(&notes, &mut Trace::to(|event| events.push(event)));
+        let discarded = events
+            .iter()
+            .filter(|event| event.step == "discard")
+            .map(|event| event.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            discarded,
+            vec![
+                "discard ticket=1 value=4",
+                "discard ticket=2 value=55",
+                "discard ticket=3 value=12"
+            ]
+        );
+    }
 }
@@ -1,128 +1,167 @@
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day17.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap().to_string())
+use crate::automaton::{life, Automaton, Lattice};
+use crate::error::ParseError;
+use crate::grid::Grid;
+use crate::input::read_day;
+use crate::solution::Solution;
+
+pub fn read_file() -> String {
+    read_day(Day17::DAY)
 }
 
-type Coord = (i64, i64, i64, i64);
+/// Returns the (x, y) coordinates of the active cubes in the initial slice.
+pub fn parse_slice(input: &str) -> Result<Vec<(i64, i64)>, ParseError> {
+    let slice = Grid::parse(Day17::DAY, input, |ch| match ch {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })?;
+    Ok(slice
+        .iter()
+        .filter(|(_, active)| **active)
+        .map(|((y, x), _)| (x as i64, y as i64))
+        .collect())
+}
 
-fn initial_state(iter: impl Iterator<Item = String>, has_w: bool) -> HashMap<Coord, bool> {
-    let mut state = HashMap::new();
-    for (y, line) in iter.enumerate() {
-        for (x, char) in line.chars().enumerate() {
-            let active = char == '#';
-            let coords = (x as i64, y as i64, 0, 0);
-            state.insert(coords, active);
+/// Runs the boot process in `N` dimensions, starting from the active cubes of a slice, and counts the active cubes
+/// after `cycles` cycles.
+pub fn boot_process<const N: usize>(active: &[(i64, i64)], cycles: usize) -> usize {
+    run_boot_process::<N>(active, cycles, |_| {})
+}
 
-            if active {
-                for c in neighbors(&coords, has_w) {
-                    if !state.contains_key(&c) {
-                        state.insert(c, false);
-                    }
-                }
-            }
-        }
+/// Same as `boot_process`, calling `observe` with the initial cubes and after every cycle.
+pub fn run_boot_process<const N: usize>(
+    active: &[(i64, i64)],
+    cycles: usize,
+    mut observe: impl FnMut(&Automaton<Lattice<N>, bool>),
+) -> usize {
+    let cubes = active.iter().map(|(x, y)| {
+        let mut coord = [0; N];
+        coord[0] = *x;
+        coord[1] = *y;
+        (coord, true)
+    });
+    let mut automaton = Automaton::new(Lattice::<N>::new(), cubes);
+    observe(&automaton);
+    for _ in 0..cycles {
+        automaton.step(life(&[3], &[2, 3]));
+        observe(&automaton);
     }
-    state
+    automaton.count(true)
 }
 
-fn neighbors(coords: &Coord, has_w: bool) -> impl Iterator<Item = Coord> {
-    let (x, y, z, w) = *coords;
-    let (w_min, w_max) = if has_w { (-1, 2) } else { (0, 1) };
-    (-1..2)
-        .flat_map(move |xd| {
-            (-1..2).flat_map(move |yd| {
-                (-1..2).flat_map(move |zd| (w_min..w_max).map(move |wd| (xd, yd, zd, wd)))
-            })
-        })
-        .filter(|(xd, yd, zd, wd)| !(*xd == 0 && *yd == 0 && *zd == 0 && *wd == 0))
-        .map(move |(xd, yd, zd, wd)| (x + xd, y + yd, z + zd, w + wd))
+/// The active cubes before the first cycle and after each cycle of the boot process in `N` dimensions.
+pub fn boot_cycles<const N: usize>(active: &[(i64, i64)], cycles: usize) -> Vec<Vec<[i64; N]>> {
+    let mut res = Vec::new();
+    run_boot_process::<N>(active, cycles, |automaton| {
+        res.push(automaton.cells().map(|(coord, _)| coord).collect())
+    });
+    res
 }
 
-fn boot_process(state: &mut HashMap<Coord, bool>, cycles: usize, has_w: bool) {
-    let mut changes: HashMap<Coord, bool> = HashMap::new();
-    for _ in 0..cycles {
-        for (coords, active) in state.iter() {
-            let active_neighbors: i64 = neighbors(coords, has_w)
-                .map(|c| {
-                    state
-                        .get(&c)
-                        .map(|active| if *active { 1 } else { 0 })
-                        .unwrap_or(0)
-                })
-                .sum();
-
-            if *active && active_neighbors != 2 && active_neighbors != 3 {
-                changes.insert(*coords, false);
-            } else if !*active && active_neighbors == 3 {
-                changes.insert(*coords, true);
-            }
+/// Draws every z slice of a state side by side, and every w slice under the one before, with a gap of `None` between
+/// slices. All states are drawn with the same bounds so that the frames line up.
+pub fn slice_frames<const N: usize>(states: &[Vec<[i64; N]>]) -> Vec<Grid<Option<bool>>> {
+    assert!((2..=4).contains(&N), "Only 2 to 4 dimensions can be drawn");
+    let mut min = [i64::MAX; N];
+    let mut max = [i64::MIN; N];
+    for coord in states.iter().flatten() {
+        for axis in 0..N {
+            min[axis] = min[axis].min(coord[axis]);
+            max[axis] = max[axis].max(coord[axis]);
         }
+    }
+    if states.iter().all(|state| state.is_empty()) {
+        return states
+            .iter()
+            .map(|_| Grid::filled(1, 1, Some(false)))
+            .collect();
+    }
 
-        for (coords, active) in changes.iter() {
-            state.insert(*coords, *active);
-
-            if *active {
-                for c in neighbors(coords, has_w) {
-                    if !state.contains_key(&c) {
-                        state.insert(c, false);
+    let size = |axis: usize| {
+        if axis < N {
+            (max[axis] - min[axis] + 1) as usize
+        } else {
+            1
+        }
+    };
+    let (slice_w, slice_h) = (size(0), size(1));
+    let width = size(2) * (slice_w + 1) - 1;
+    let height = size(3) * (slice_h + 1) - 1;
+    states
+        .iter()
+        .map(|state| {
+            let mut frame = Grid::from_fn(height, width, |(r, c)| {
+                Some(false).filter(|_| r % (slice_h + 1) < slice_h && c % (slice_w + 1) < slice_w)
+            });
+            for coord in state {
+                let offset = |axis: usize| {
+                    if axis < N {
+                        (coord[axis] - min[axis]) as usize
+                    } else {
+                        0
                     }
-                }
+                };
+                let r = offset(3) * (slice_h + 1) + offset(1);
+                let c = offset(2) * (slice_w + 1) + offset(0);
+                frame[(r, c)] = Some(true);
             }
-        }
+            frame
+        })
+        .collect()
+}
+
+pub struct Day17;
+
+impl Solution for Day17 {
+    const DAY: u8 = 17;
+    type Input = Vec<(i64, i64)>;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_slice(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        Some(boot_process::<3>(input, 6).to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        Some(boot_process::<4>(input, 6).to_string())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day17::{boot_process, initial_state, read_file};
+    use crate::day17::{boot_process, parse_slice, read_file};
+    use crate::input::read_fixture;
 
     #[test]
     fn test_part1_example() {
-        let example = "
-.#.
-..#
-###
-";
-        let mut state = initial_state(example[1..].lines().map(|line| line.to_string()), false);
-        boot_process(&mut state, 6, false);
-        let res = state.values().filter(|active| **active).count();
+        let active = parse_slice(&read_fixture("day17.txt")).unwrap();
+        let res = boot_process::<3>(&active, 6);
         // println!("{}", res);
         assert_eq!(res, 112);
     }
 
     #[test]
     fn test_part1() {
-        let mut state = initial_state(read_file(), false);
-        boot_process(&mut state, 6, false);
-        let res = state.values().filter(|active| **active).count();
+        let active = parse_slice(&read_file()).unwrap();
+        let res = boot_process::<3>(&active, 6);
         println!("{}", res);
         assert_eq!(res, 322);
     }
 
     #[test]
     fn test_part2_example() {
-        let example = "
-.#.
-..#
-###
-";
-        let mut state = initial_state(example[1..].lines().map(|line| line.to_string()), true);
-        boot_process(&mut state, 6, true);
-        let res = state.values().filter(|active| **active).count();
+        let active = parse_slice(&read_fixture("day17.txt")).unwrap();
+        let res = boot_process::<4>(&active, 6);
         // println!("{}", res);
         assert_eq!(res, 848);
     }
 
     #[test]
     fn test_part2() {
-        let mut state = initial_state(read_file(), true);
-        boot_process(&mut state, 6, true);
-        let res = state.values().filter(|active| **active).count();
+        let active = parse_slice(&read_file()).unwrap();
+        let res = boot_process::<4>(&active, 6);
         // println!("{}", res);
         assert_eq!(res, 2000);
     }
@@ -1,57 +1,93 @@
-use itertools::Itertools;
-use std::borrow::Borrow;
+use crate::error::ParseError;
+use crate::input::read_day_lines;
+use crate::numeric::{exact, Natural, Overflow};
+use crate::solution::{lines, Solution};
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
 
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day18.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap().to_string())
+pub fn read_file() -> impl Iterator<Item = String> {
+    read_day_lines(Day18::DAY)
 }
 
-#[derive(Copy, Clone)]
-enum Operator {
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub enum Operator {
     Plus,
     Mult,
 }
 
-enum Token {
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Token {
     Op(Operator),
     Number(u64),
     LeftParen,
     RightParen,
 }
 
-fn tokenize(line: &str) -> Vec<Token> {
-    line.chars()
-        .filter(|c| !c.is_whitespace())
-        .map(|s| match s {
+/// Splits a line into tokens, checking that operands and operators alternate and that parentheses are balanced.
+pub fn tokenize(line: &str) -> Result<Vec<Token>, ParseError> {
+    let error = |column: usize, message: &str| ParseError::new(Day18::DAY, 1, column, message);
+    let mut tokens = Vec::new();
+    let mut expect_operand = true;
+    let mut depth = 0;
+    for (i, c) in line.chars().enumerate().filter(|(_, c)| !c.is_whitespace()) {
+        let token = match c {
             '+' => Token::Op(Operator::Plus),
             '*' => Token::Op(Operator::Mult),
             '(' => Token::LeftParen,
             ')' => Token::RightParen,
-            x => Token::Number(x.to_digit(10).unwrap() as u64),
-        })
-        .collect_vec()
+            x => match x.to_digit(10) {
+                Some(digit) => Token::Number(digit as u64),
+                None => {
+                    return Err(error(i + 1, &format!("Unexpected character: '{}'", x)));
+                }
+            },
+        };
+        match (&token, expect_operand) {
+            (Token::Number(_), true) => expect_operand = false,
+            (Token::LeftParen, true) => depth += 1,
+            (Token::Op(_), false) => expect_operand = true,
+            (Token::RightParen, false) if depth > 0 => depth -= 1,
+            (Token::RightParen, false) => return Err(error(i + 1, "Unmatched ')'")),
+            (_, true) => return Err(error(i + 1, "Expected a number or '('")),
+            (_, false) => return Err(error(i + 1, "Expected an operator or ')'")),
+        }
+        tokens.push(token);
+    }
+
+    let end = line.chars().count() + 1;
+    if expect_operand {
+        Err(error(end, "Expected a number or '('"))
+    } else if depth > 0 {
+        Err(error(end, "Expected ')'"))
+    } else {
+        Ok(tokens)
+    }
 }
 
-enum Node {
+pub fn parse_expressions(
+    iter: impl Iterator<Item = String>,
+) -> Result<Vec<Vec<Token>>, ParseError> {
+    iter.enumerate()
+        .map(|(i, line)| tokenize(&line).map_err(|e| e.on_line(i + 1)))
+        .collect()
+}
+
+pub enum Node {
     Number(u64),
     Expr(Box<Node>, Operator, Box<Node>),
 }
 
-fn expression_part1(tokens: &mut VecDeque<Token>) -> Box<Node> {
+pub fn expression_part1(tokens: &mut VecDeque<Token>) -> Box<Node> {
     let mut res = factor_part1(tokens);
     while let Some(Token::Op(op)) = tokens.front() {
-        let op = op.clone();
+        let op = *op;
         tokens.pop_front();
         res = Box::new(Node::Expr(res, op, factor_part1(tokens)));
     }
     res
 }
 
-fn expression_part2(tokens: &mut VecDeque<Token>) -> Box<Node> {
+pub fn expression_part2(tokens: &mut VecDeque<Token>) -> Box<Node> {
     let mut res = term(tokens);
     while let Some(Token::Op(Operator::Mult)) = tokens.front() {
         tokens.pop_front();
@@ -60,7 +96,7 @@ fn expression_part2(tokens: &mut VecDeque<Token>) -> Box<Node> {
     res
 }
 
-fn term(tokens: &mut VecDeque<Token>) -> Box<Node> {
+pub fn term(tokens: &mut VecDeque<Token>) -> Box<Node> {
     let mut res = factor_part2(tokens);
     while let Some(Token::Op(Operator::Plus)) = tokens.front() {
         tokens.pop_front();
@@ -69,7 +105,7 @@ fn term(tokens: &mut VecDeque<Token>) -> Box<Node> {
     res
 }
 
-fn factor_part1(tokens: &mut VecDeque<Token>) -> Box<Node> {
+pub fn factor_part1(tokens: &mut VecDeque<Token>) -> Box<Node> {
     match tokens.pop_front() {
         Some(Token::Number(n)) => Box::new(Node::Number(n)),
         Some(Token::LeftParen) => {
@@ -81,7 +117,7 @@ fn factor_part1(tokens: &mut VecDeque<Token>) -> Box<Node> {
     }
 }
 
-fn factor_part2(tokens: &mut VecDeque<Token>) -> Box<Node> {
+pub fn factor_part2(tokens: &mut VecDeque<Token>) -> Box<Node> {
     match tokens.pop_front() {
         Some(Token::Number(n)) => Box::new(Node::Number(n)),
         Some(Token::LeftParen) => {
@@ -93,51 +129,107 @@ fn factor_part2(tokens: &mut VecDeque<Token>) -> Box<Node> {
     }
 }
 
-fn evaluate_node(node: &Box<Node>) -> u64 {
-    match node.borrow() {
-        Node::Number(n) => *n,
-        Node::Expr(lhs, Operator::Plus, rhs) => evaluate_node(lhs) + evaluate_node(rhs),
-        Node::Expr(lhs, Operator::Mult, rhs) => evaluate_node(lhs) * evaluate_node(rhs),
+pub fn evaluate_node(node: &Node) -> u64 {
+    evaluate_node_as(node).expect("The expression overflowed")
+}
+
+/// Same as `evaluate_node`, evaluating with any `Natural`.
+pub fn evaluate_node_as<N: Natural>(node: &Node) -> Result<N, Overflow> {
+    match node {
+        Node::Number(n) => Ok(N::from_u64(*n)),
+        Node::Expr(lhs, Operator::Plus, rhs) => {
+            evaluate_node_as::<N>(lhs)?.add(&evaluate_node_as(rhs)?)
+        }
+        Node::Expr(lhs, Operator::Mult, rhs) => {
+            evaluate_node_as::<N>(lhs)?.mul(&evaluate_node_as(rhs)?)
+        }
     }
 }
 
-fn evaluate_part1(tokens: Vec<Token>) -> u64 {
+/// Sums the expressions, parsed by `expression` and evaluated with any `Natural`.
+pub fn sum_expressions<N: Natural>(
+    expressions: &[Vec<Token>],
+    expression: fn(&mut VecDeque<Token>) -> Box<Node>,
+) -> Result<N, Overflow> {
+    N::sum(expressions.iter().map(|tokens| {
+        let node = expression(&mut VecDeque::from(tokens.clone()));
+        evaluate_node_as(&node)
+    }))
+}
+
+pub fn evaluate_part1(tokens: Vec<Token>) -> u64 {
     let mut inputs = VecDeque::from(tokens);
     let node = expression_part1(&mut inputs);
     evaluate_node(&node)
 }
 
-fn evaluate_part2(tokens: Vec<Token>) -> u64 {
+pub fn evaluate_part2(tokens: Vec<Token>) -> u64 {
     let mut inputs = VecDeque::from(tokens);
     let node = expression_part2(&mut inputs);
     evaluate_node(&node)
 }
 
+pub struct Day18;
+
+impl Solution for Day18 {
+    const DAY: u8 = 18;
+    type Input = Vec<Vec<Token>>;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_expressions(lines(input))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        Some(exact(sum_expressions(input, expression_part1), || {
+            sum_expressions(input, expression_part1)
+        }))
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        Some(exact(sum_expressions(input, expression_part2), || {
+            sum_expressions(input, expression_part2)
+        }))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::day18::{evaluate_part1, evaluate_part2, read_file, tokenize};
+    use crate::day18::{
+        evaluate_part1, evaluate_part2, expression_part1, expression_part2, read_file,
+        sum_expressions, tokenize,
+    };
+    use crate::numeric::{BigUint, Overflow};
 
     #[test]
     fn test_part1_example() {
-        assert_eq!(71, evaluate_part1(tokenize("1 + 2 * 3 + 4 * 5 + 6")));
-        assert_eq!(51, evaluate_part1(tokenize("1 + (2 * 3) + (4 * (5 + 6))")));
-        assert_eq!(26, evaluate_part1(tokenize("2 * 3 + (4 * 5)")));
-        assert_eq!(437, evaluate_part1(tokenize("5 + (8 * 3 + 9 + 3 * 4 * 3)")));
+        assert_eq!(
+            71,
+            evaluate_part1(tokenize("1 + 2 * 3 + 4 * 5 + 6").unwrap())
+        );
+        assert_eq!(
+            51,
+            evaluate_part1(tokenize("1 + (2 * 3) + (4 * (5 + 6))").unwrap())
+        );
+        assert_eq!(26, evaluate_part1(tokenize("2 * 3 + (4 * 5)").unwrap()));
+        assert_eq!(
+            437,
+            evaluate_part1(tokenize("5 + (8 * 3 + 9 + 3 * 4 * 3)").unwrap())
+        );
         assert_eq!(
             12240,
-            evaluate_part1(tokenize("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))"))
+            evaluate_part1(tokenize("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))").unwrap())
         );
         assert_eq!(
             13632,
-            evaluate_part1(tokenize("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2"))
+            evaluate_part1(tokenize("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2").unwrap())
         );
     }
 
     #[test]
     fn test_part1() {
         let res: u64 = read_file()
-            .map(|line| tokenize(line.as_str()))
-            .map(|inputs| evaluate_part1(inputs))
+            .map(|line| tokenize(line.as_str()).unwrap())
+            .map(evaluate_part1)
             .sum();
         println!("{}", res);
         assert_eq!(202553439706, res);
@@ -145,30 +237,55 @@ mod tests {
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(231, evaluate_part2(tokenize("1 + 2 * 3 + 4 * 5 + 6")));
-        assert_eq!(51, evaluate_part2(tokenize("1 + (2 * 3) + (4 * (5 + 6))")));
-        assert_eq!(46, evaluate_part2(tokenize("2 * 3 + (4 * 5)")));
+        assert_eq!(
+            231,
+            evaluate_part2(tokenize("1 + 2 * 3 + 4 * 5 + 6").unwrap())
+        );
+        assert_eq!(
+            51,
+            evaluate_part2(tokenize("1 + (2 * 3) + (4 * (5 + 6))").unwrap())
+        );
+        assert_eq!(46, evaluate_part2(tokenize("2 * 3 + (4 * 5)").unwrap()));
         assert_eq!(
             1445,
-            evaluate_part2(tokenize("5 + (8 * 3 + 9 + 3 * 4 * 3)"))
+            evaluate_part2(tokenize("5 + (8 * 3 + 9 + 3 * 4 * 3)").unwrap())
         );
         assert_eq!(
             669060,
-            evaluate_part2(tokenize("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))"))
+            evaluate_part2(tokenize("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))").unwrap())
         );
         assert_eq!(
             23340,
-            evaluate_part2(tokenize("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2"))
+            evaluate_part2(tokenize("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2").unwrap())
         );
     }
 
     #[test]
     fn test_part2() {
         let res: u64 = read_file()
-            .map(|line| tokenize(line.as_str()))
-            .map(|inputs| evaluate_part2(inputs))
+            .map(|line| tokenize(line.as_str()).unwrap())
+            .map(evaluate_part2)
             .sum();
         println!("{}", res);
         assert_eq!(88534268715686, res);
     }
+
+    #[test]
+    fn test_overflow() {
+        // (9 * 9 * ... * 9) with 21 nines is 9^21, above u64::MAX, in both parts.
+        let expressions = vec![
+            tokenize(&vec!["9"; 21].join(" * ")).unwrap(),
+            tokenize("1 + 2 * 3").unwrap(),
+        ];
+        for &expression in &[expression_part1, expression_part2] {
+            assert_eq!(
+                sum_expressions::<u64>(&expressions, expression),
+                Err(Overflow)
+            );
+        }
+        let res = sum_expressions::<BigUint>(&expressions, expression_part1).unwrap();
+        assert_eq!(res.to_string(), "109418989131512359218");
+        let res = sum_expressions::<BigUint>(&expressions, expression_part2).unwrap();
+        assert_eq!(res.to_string(), "109418989131512359218");
+    }
 }
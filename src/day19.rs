@@ -1,95 +1,163 @@
+use crate::error::{column_of, parse_number, ParseError};
+use crate::input::read_day_lines;
+use crate::solution::{lines, Solution};
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
-use std::str::FromStr;
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day19.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap().to_string())
+
+pub fn read_file() -> impl Iterator<Item = String> {
+    read_day_lines(Day19::DAY)
 }
 
-enum Rule {
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Rule {
     Terminal(char),
     SubRule(Vec<Vec<u64>>),
 }
 
-fn parse_input(
+pub type Rules = HashMap<u64, Rule>;
+
+pub fn parse_input(
     iter: impl Iterator<Item = String>,
     part2: bool,
-) -> (HashMap<u64, Rule>, Vec<Vec<char>>) {
+) -> Result<(Rules, Vec<Vec<char>>), ParseError> {
     let mut rules = HashMap::new();
     let mut messages = Vec::new();
+    let mut references = Vec::new();
     let mut parse_rules = true;
-    for line in iter {
+    let mut line_count = 0;
+    for (i, line) in iter.enumerate() {
+        let line_no = i + 1;
+        line_count = line_no;
         if line.is_empty() {
             parse_rules = false
         } else if parse_rules {
-            let (index_str, rule_str) = line.split(": ").collect_tuple().unwrap();
-            let index = u64::from_str(index_str).unwrap();
-            let rule = if rule_str.starts_with("\"") {
-                Rule::Terminal(rule_str.chars().nth(1).unwrap())
+            let (index_str, rule_str) = line.split_once(": ").ok_or_else(|| {
+                ParseError::new(Day19::DAY, line_no, 1, "Expected '<index>: <rule>'")
+            })?;
+            let index = parse_number(Day19::DAY, line_no, &line, index_str)?;
+            let rule = if rule_str.starts_with('"') {
+                match rule_str.chars().collect_vec().as_slice() {
+                    ['"', c, '"'] => Rule::Terminal(*c),
+                    _ => {
+                        return Err(ParseError::at(
+                            Day19::DAY,
+                            line_no,
+                            &line,
+                            rule_str,
+                            "Expected a single quoted character",
+                        ))
+                    }
+                }
             } else {
-                Rule::SubRule(
-                    rule_str
-                        .split(" | ")
-                        .map(|r| {
-                            r.split(" ")
-                                .map(|n| u64::from_str(n).unwrap())
-                                .collect_vec()
-                        })
-                        .collect_vec(),
-                )
+                let mut sub_rules = Vec::new();
+                for r in rule_str.split(" | ") {
+                    let mut sub_rule = Vec::new();
+                    for n in r.split(' ') {
+                        let sub_rule_index = parse_number(Day19::DAY, line_no, &line, n)?;
+                        references.push((line_no, column_of(&line, n), sub_rule_index));
+                        sub_rule.push(sub_rule_index);
+                    }
+                    sub_rules.push(sub_rule);
+                }
+                Rule::SubRule(sub_rules)
             };
-            rules.insert(index, rule);
+            if rules.insert(index, rule).is_some() {
+                return Err(ParseError::at(
+                    Day19::DAY,
+                    line_no,
+                    &line,
+                    index_str,
+                    format!("Rule {} is defined twice", index),
+                ));
+            }
         } else {
             messages.push(line.chars().collect_vec());
         }
     }
 
-    // Blow out rule 0 to 0: 42+ 42*n 13*n to remove loops.
-    // Rules 8 and 11 are now unnecessary.
+    if let Some((line_no, column, index)) = references
+        .into_iter()
+        .find(|(_, _, index)| !rules.contains_key(index))
+    {
+        return Err(ParseError::new(
+            Day19::DAY,
+            line_no,
+            column,
+            format!("Undefined rule: {}", index),
+        ));
+    }
+    if !rules.contains_key(&0) {
+        return Err(ParseError::new(
+            Day19::DAY,
+            line_count + 1,
+            1,
+            "Expected a rule 0",
+        ));
+    }
+
     if part2 {
-        let reps = 5;
-        let mut sub_rules = Vec::new();
-        for i in 1..reps {
-            for j in (i + 1)..(i + 1 + reps) {
-                let mut v: Vec<u64> = Vec::new();
-                for _ in 0..j {
-                    v.push(42);
-                }
-                for _ in 0..i {
-                    v.push(31);
-                }
-                sub_rules.push(v);
-            }
-        }
+        unroll_loops(&mut rules);
+    }
 
-        sub_rules.reverse();
-        rules.insert(0, Rule::SubRule(sub_rules));
+    Ok((rules, messages))
+}
+
+/// Blow out rule 0 to 0: 42+ 42*n 13*n to remove loops.
+/// Rules 8 and 11 are now unnecessary.
+pub fn unroll_loops(rules: &mut HashMap<u64, Rule>) {
+    let reps = 5;
+    let mut sub_rules = Vec::new();
+    for i in 1..reps {
+        for j in (i + 1)..(i + 1 + reps) {
+            let mut v: Vec<u64> = vec![42; j];
+            v.extend(vec![31; i]);
+            sub_rules.push(v);
+        }
     }
 
-    (rules, messages)
+    sub_rules.reverse();
+    rules.insert(0, Rule::SubRule(sub_rules));
 }
 
-fn is_valid(msg: &Vec<char>, rules: &HashMap<u64, Rule>) -> bool {
-    let res = match is_valid_for_rule(&msg, &rules, 0, 0) {
-        Some(i) if i == msg.len() => true,
-        _ => false,
-    };
+pub fn is_valid(msg: &[char], rules: &HashMap<u64, Rule>) -> bool {
+    let res = matches!(is_valid_for_rule(msg, rules, 0, 0), Some(i) if i == msg.len());
     // println!("{:?}, {}", msg, res);
     res
 }
 
-fn is_valid_for_rule(
-    msg: &Vec<char>,
+/// Returns the index after the part of `msg` from `char_index` that matches the rule, if any.
+pub fn is_valid_for_rule(
+    msg: &[char],
+    rules: &HashMap<u64, Rule>,
+    rule_index: u64,
+    char_index: usize,
+) -> Option<usize> {
+    is_valid_for_rule_from(msg, rules, rule_index, char_index, &mut Vec::new())
+}
+
+/// Same as `is_valid_for_rule`, where `path` holds the rules being matched and the indexes they started at. A rule
+/// that comes back to itself without matching a character would recurse forever, so that alternative fails instead.
+/// Every other rule on the path starts further along the message, which keeps the depth within the message length.
+fn is_valid_for_rule_from(
+    msg: &[char],
     rules: &HashMap<u64, Rule>,
     rule_index: u64,
     char_index: usize,
+    path: &mut Vec<(u64, usize)>,
 ) -> Option<usize> {
-    // println!("{} {}", rule_index, char_index);
-    let res = match rules.get(&rule_index).unwrap() {
+    // The indexes only grow along the path, so the rules started at this index are at its end.
+    if path
+        .iter()
+        .rev()
+        .take_while(|(_, i)| *i == char_index)
+        .any(|(r, _)| *r == rule_index)
+    {
+        return None;
+    }
+
+    path.push((rule_index, char_index));
+    let res = match rules.get(&rule_index)? {
         Rule::Terminal(c) => match msg.get(char_index) {
             Some(m) if m == c => Some(char_index + 1),
             _ => None,
@@ -98,123 +166,130 @@ fn is_valid_for_rule(
             let mut new_char_index = Some(char_index);
             for sub_rule_index in sub_rule {
                 new_char_index = match new_char_index {
-                    Some(i) => is_valid_for_rule(msg, rules, *sub_rule_index, i),
+                    Some(i) => is_valid_for_rule_from(msg, rules, *sub_rule_index, i, path),
                     None => break,
                 }
             }
             new_char_index
         }),
     };
+    path.pop();
     res
 }
 
+pub struct Day19;
+
+impl Solution for Day19 {
+    const DAY: u8 = 19;
+    type Input = (HashMap<u64, Rule>, Vec<Vec<char>>);
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(lines(input), false)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        let (rules, messages) = input;
+        let res = messages.iter().filter(|msg| is_valid(msg, rules)).count();
+        Some(res.to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        let (rules, messages) = input;
+        if !rules.contains_key(&42) || !rules.contains_key(&31) {
+            return None;
+        }
+        let mut rules = rules.clone();
+        unroll_loops(&mut rules);
+        let res = messages.iter().filter(|msg| is_valid(msg, &rules)).count();
+        Some(res.to_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::day19::{is_valid, parse_input, read_file};
+    use crate::day19::{is_valid, parse_input, read_file, Day19};
+    use crate::error::ParseError;
+    use crate::input::read_fixture;
+    use crate::solution::{lines, Solution};
 
     #[test]
     fn test_part1_example() {
-        let example = "
-0: 4 1 5
-1: 2 3 | 3 2
-2: 4 4 | 5 5
-3: 4 5 | 5 4
-4: \"a\"
-5: \"b\"
-
-ababbb
-bababa
-abbbab
-aaabbb
-aaaabbb
-";
-        let (rules, messages) =
-            parse_input(example[1..].lines().map(|line| line.to_string()), false);
-        let res = messages.iter().filter(|msg| is_valid(*msg, &rules)).count();
+        let (rules, messages) = parse_input(lines(&read_fixture("day19.txt")), false).unwrap();
+        let res = messages.iter().filter(|msg| is_valid(msg, &rules)).count();
         assert_eq!(2, res);
     }
 
     #[test]
     fn test_part1() {
-        let (rules, messages) = parse_input(read_file(), false);
-        let res = messages.iter().filter(|msg| is_valid(*msg, &rules)).count();
+        let (rules, messages) = parse_input(read_file(), false).unwrap();
+        let res = messages.iter().filter(|msg| is_valid(msg, &rules)).count();
         println!("{}", res);
         assert_eq!(173, res);
     }
 
-    const EXAMPLE2: &str = "
-42: 9 14 | 10 1
-9: 14 27 | 1 26
-10: 23 14 | 28 1
-1: \"a\"
-11: 42 31
-5: 1 14 | 15 1
-19: 14 1 | 14 14
-12: 24 14 | 19 1
-16: 15 1 | 14 14
-31: 14 17 | 1 13
-6: 14 14 | 1 14
-2: 1 24 | 14 4
-0: 8 11
-13: 14 3 | 1 12
-15: 1 | 14
-17: 14 2 | 1 7
-23: 25 1 | 22 14
-28: 16 1
-4: 1 1
-20: 14 14 | 1 15
-3: 5 14 | 16 1
-27: 1 6 | 14 18
-14: \"b\"
-21: 14 1 | 1 14
-25: 1 1 | 1 14
-22: 14 14
-8: 42
-26: 14 22 | 1 20
-18: 15 15
-7: 14 5 | 1 21
-24: 14 1
-
-abbbbbabbbaaaababbaabbbbabababbbabbbbbbabaaaa
-bbabbbbaabaabba
-babbbbaabbbbbabbbbbbaabaaabaaa
-aaabbbbbbaaaabaababaabababbabaaabbababababaaa
-bbbbbbbaaaabbbbaaabbabaaa
-bbbababbbbaaaaaaaabbababaaababaabab
-ababaaaaaabaaab
-ababaaaaabbbaba
-baabbaaaabbaaaababbaababb
-abbbbabbbbaaaababbbbbbaaaababb
-aaaaabbaabaaaaababaa
-aaaabbaaaabbaaa
-aaaabbaabbaaaaaaabbbabbbaaabbaabaaa
-babaaabbbaaabaababbaabababaaab
-aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba
-";
-
     #[test]
     fn test_part2_example1() {
-        let (rules, messages) =
-            parse_input(EXAMPLE2[1..].lines().map(|line| line.to_string()), false);
-        let res = messages.iter().filter(|msg| is_valid(*msg, &rules)).count();
+        let (rules, messages) = parse_input(lines(&read_fixture("day19-2.txt")), false).unwrap();
+        let res = messages.iter().filter(|msg| is_valid(msg, &rules)).count();
         assert_eq!(3, res);
     }
 
     #[test]
     fn test_part2_example2() {
-        let (rules, messages) =
-            parse_input(EXAMPLE2[1..].lines().map(|line| line.to_string()), true);
-        assert_eq!(true, is_valid(&messages[2], &rules));
+        let (rules, messages) = parse_input(lines(&read_fixture("day19-2.txt")), true).unwrap();
+        assert!(is_valid(&messages[2], &rules));
 
-        let res = messages.iter().filter(|msg| is_valid(*msg, &rules)).count();
+        let res = messages.iter().filter(|msg| is_valid(msg, &rules)).count();
         assert_eq!(12, res);
     }
 
     #[test]
     fn test_part2() {
-        let (rules, messages) = parse_input(read_file(), true);
-        let res = messages.iter().filter(|msg| is_valid(*msg, &rules)).count();
+        let (rules, messages) = parse_input(read_file(), true).unwrap();
+        let res = messages.iter().filter(|msg| is_valid(msg, &rules)).count();
         println!("{}", res);
         assert_eq!(367, res);
     }
+
+    #[test]
+    fn test_loops() {
+        let count = |s: &str| {
+            let (rules, messages) = parse_input(lines(s), false).unwrap();
+            Day19.part1(&(rules, messages))
+        };
+        // Rules that come back to themselves before matching anything match nothing.
+        assert_eq!(count("0: 0\n\na\n"), Some("0".to_string()));
+        assert_eq!(count("0: 1\n1: 0\n\na\nb\n"), Some("0".to_string()));
+        assert_eq!(
+            count("0: 0 1 | 1\n1: \"a\"\n\na\naa\n"),
+            Some("1".to_string())
+        );
+        // Loops that match a character each time are fine.
+        assert_eq!(
+            count("0: 1 0 | 1\n1: \"a\"\n\na\naaaa\nab\n"),
+            Some("2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |s: &str| parse_input(s.lines().map(|l| l.to_string()), false);
+        assert!(parse("0: 1 1\n1: \"a\"\n\naa").is_ok());
+        assert_eq!(
+            parse("0: 1 2\n1: \"a\"\n\naa").unwrap_err(),
+            ParseError::new(19, 1, 6, "Undefined rule: 2")
+        );
+        assert_eq!(
+            parse("0: 1 x\n1: \"a\"").unwrap_err(),
+            ParseError::new(19, 1, 6, "Invalid number: 'x'")
+        );
+        assert_eq!(
+            parse("0: 1\n1: \"ab\"").unwrap_err(),
+            ParseError::new(19, 2, 4, "Expected a single quoted character")
+        );
+        assert_eq!(
+            parse("1: \"a\"\n\na").unwrap_err(),
+            ParseError::new(19, 4, 1, "Expected a rule 0")
+        );
+    }
 }
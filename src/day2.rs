@@ -1,68 +1,134 @@
+use crate::error::{parse_number, ParseError};
+use crate::input::read_day_lines;
+use crate::solution::{lines, Solution};
 use itertools::Itertools;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
-use std::str::FromStr;
-
-#[derive(Debug)]
-struct PasswordRecord {
-    min: usize,
-    max: usize,
-    letter: char,
-    password: String,
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PasswordRecord {
+    pub min: usize,
+    pub max: usize,
+    pub letter: char,
+    pub password: String,
 }
 
 impl PasswordRecord {
-    fn is_valid_part1(&self) -> bool {
+    pub fn is_valid_part1(&self) -> bool {
         let count = self.password.chars().filter(|c| *c == self.letter).count();
         count >= self.min && count <= self.max
     }
 
-    fn is_valid_part2(&self) -> bool {
-        let slice = &self.password[(self.min - 1)..self.max];
-        slice.starts_with(self.letter) ^ slice.ends_with(self.letter)
+    pub fn is_valid_part2(&self) -> bool {
+        let has_letter_at = |pos: usize| self.password.chars().nth(pos - 1) == Some(self.letter);
+        has_letter_at(self.min) ^ has_letter_at(self.max)
     }
 }
 
-fn read_password_database() -> impl Iterator<Item = PasswordRecord> {
-    let file = File::open("input/day2.txt").unwrap();
-    BufReader::new(file).lines().map(|line_res| {
-        let line = line_res.unwrap();
-        let mut parts = line.split_whitespace();
-        let (min, max) = parts
-            .next()
-            .unwrap()
-            .split("-")
-            .map(|s| usize::from_str(s).unwrap())
-            .collect_tuple()
-            .unwrap();
-        let letter = parts.next().unwrap().chars().nth(0).unwrap();
-        let password = parts.next().unwrap().to_string();
-
-        PasswordRecord {
-            min,
-            max,
-            letter,
-            password,
-        }
+pub fn read_password_database() -> Result<Vec<PasswordRecord>, ParseError> {
+    parse_password_database(read_day_lines(Day2::DAY))
+}
+
+pub fn parse_password_database(
+    iter: impl Iterator<Item = String>,
+) -> Result<Vec<PasswordRecord>, ParseError> {
+    iter.enumerate()
+        .map(|(i, line)| parse_record(i + 1, &line))
+        .collect()
+}
+
+/// Parses a line of the form `1-3 a: abcde`.
+fn parse_record(line_no: usize, line: &str) -> Result<PasswordRecord, ParseError> {
+    let error = |part: &str, message: &str| ParseError::at(Day2::DAY, line_no, line, part, message);
+
+    let (policy, letter, password) = line
+        .split_whitespace()
+        .collect_tuple()
+        .ok_or_else(|| error(line, "Expected a policy, a letter and a password"))?;
+    let (min_str, max_str) = policy
+        .split('-')
+        .collect_tuple()
+        .ok_or_else(|| error(policy, "Expected a range of the form min-max"))?;
+    let min: usize = parse_number(Day2::DAY, line_no, line, min_str)?;
+    let max: usize = parse_number(Day2::DAY, line_no, line, max_str)?;
+    if min == 0 || min > max {
+        return Err(error(policy, "Expected a range with 0 < min <= max"));
+    }
+
+    let letter = match letter.strip_suffix(':').map(|l| l.chars().collect_vec()) {
+        Some(chars) if chars.len() == 1 => chars[0],
+        _ => return Err(error(letter, "Expected a single letter followed by ':'")),
+    };
+
+    Ok(PasswordRecord {
+        min,
+        max,
+        letter,
+        password: password.to_string(),
     })
 }
 
+pub struct Day2;
+
+impl Solution for Day2 {
+    const DAY: u8 = 2;
+    type Input = Vec<PasswordRecord>;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_password_database(lines(input))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        Some(
+            input
+                .iter()
+                .filter(|r| r.is_valid_part1())
+                .count()
+                .to_string(),
+        )
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        Some(
+            input
+                .iter()
+                .filter(|r| r.is_valid_part2())
+                .count()
+                .to_string(),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::day2::read_password_database;
+    use crate::day2::{parse_password_database, read_password_database};
+    use crate::error::ParseError;
 
     #[test]
     fn part1() {
-        let db = read_password_database();
-        let valid_count = db.filter(|r| r.is_valid_part1()).count();
+        let db = read_password_database().unwrap();
+        let valid_count = db.iter().filter(|r| r.is_valid_part1()).count();
         println!("{}", valid_count);
     }
 
     #[test]
     fn part2() {
-        let db = read_password_database();
-        let valid_count = db.filter(|r| r.is_valid_part2()).count();
+        let db = read_password_database().unwrap();
+        let valid_count = db.iter().filter(|r| r.is_valid_part2()).count();
         println!("{}", valid_count);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |s: &str| parse_password_database(s.lines().map(|l| l.to_string()));
+        assert!(parse("1-3 a: abcde\n2-9 c: ccccccccc").is_ok());
+        assert_eq!(
+            parse("1-3 a: abcde\n1-x b: cdefg").unwrap_err(),
+            ParseError::new(2, 2, 3, "Invalid number: 'x'")
+        );
+        assert_eq!(
+            parse("1-3 ab: abcde").unwrap_err(),
+            ParseError::new(2, 1, 5, "Expected a single letter followed by ':'")
+        );
+        assert_eq!(parse("1-3 a:").unwrap_err().column, 1);
+    }
 }
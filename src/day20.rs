@@ -1,95 +1,127 @@
+use crate::error::{parse_number, ParseError};
+use crate::grid::{Grid, Pos};
+use crate::input::read_day_lines;
+use crate::solution::{lines, Certify, Solution};
+use crate::trace::{Event, Trace};
 use itertools::Itertools;
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::iter::FromIterator;
-use std::str::FromStr;
 
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day20.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap().to_string())
+pub fn read_file() -> impl Iterator<Item = String> {
+    read_day_lines(Day20::DAY)
 }
 
-#[derive(Clone)]
-struct Tile {
-    tile_no: u64,
-    tile: Vec<Vec<char>>,
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Tile {
+    pub tile_no: u64,
+    pub tile: Grid<char>,
 }
 
 impl Tile {
-    fn dim(&self) -> usize {
-        self.tile.len()
+    pub fn dim(&self) -> usize {
+        self.tile.height()
     }
 
-    fn empty(&self) -> Tile {
-        let mut new_tile = Vec::new();
-        for _ in 0..self.dim() {
-            let mut row = Vec::with_capacity(self.dim());
-            for _ in 0..self.dim() {
-                row.push(' ');
-            }
-            new_tile.push(row)
-        }
-
+    pub fn rotate(&self) -> Tile {
         Tile {
             tile_no: self.tile_no,
-            tile: new_tile,
-        }
-    }
-
-    fn rotate(&self) -> Tile {
-        let mut new_tile = self.empty();
-
-        for r in 0..self.dim() {
-            for c in 0..self.dim() {
-                new_tile.tile[c][self.dim() - r - 1] = self.tile[r][c];
-            }
+            tile: self.tile.rotate(),
         }
-        new_tile
     }
 
-    fn flip(&self) -> Tile {
-        let mut new_tile = self.empty();
-        for r in 0..self.dim() {
-            for c in 0..self.dim() {
-                new_tile.tile[self.dim() - r - 1][c] = self.tile[r][c];
-            }
+    pub fn flip(&self) -> Tile {
+        Tile {
+            tile_no: self.tile_no,
+            tile: self.tile.flip(),
         }
-        new_tile
     }
 }
 
-fn parse_tiles(iter: impl Iterator<Item = String>) -> Vec<Tile> {
-    let mut tile = Some(Vec::new());
-    let mut tile_no = 0;
+pub fn parse_tiles(iter: impl Iterator<Item = String>) -> Result<Vec<Tile>, ParseError> {
+    let mut current: Option<(usize, u64, Vec<Vec<char>>)> = None;
     let mut res = Vec::new();
+    let mut line_count = 0;
 
-    for line in iter {
+    for (i, line) in iter.enumerate() {
+        let line_no = i + 1;
+        line_count = line_no;
         if line.is_empty() {
-            if let Some(v) = tile.replace(Vec::new()) {
-                res.push(Tile { tile_no, tile: v });
+            if let Some((header_line, tile_no, rows)) = current.take() {
+                res.push(check_tile(header_line, tile_no, rows, res.first())?);
+            }
+        } else if let Some((_, _, rows)) = current.as_mut() {
+            if let Some((col, c)) = line.chars().find_position(|c| *c != '#' && *c != '.') {
+                return Err(ParseError::new(
+                    Day20::DAY,
+                    line_no,
+                    col + 1,
+                    format!("Unexpected character: '{}'", c),
+                ));
             }
-        } else if line.starts_with("Tile") {
-            tile_no = u64::from_str(
-                line.strip_prefix("Tile ")
-                    .unwrap()
-                    .strip_suffix(":")
-                    .unwrap(),
-            )
-            .unwrap();
-        } else if let Some(v) = tile.as_mut() {
-            v.push(line.chars().collect_vec());
+            rows.push(line.chars().collect_vec());
+        } else {
+            let tile_no = line
+                .strip_prefix("Tile ")
+                .and_then(|rest| rest.strip_suffix(':'))
+                .ok_or_else(|| {
+                    ParseError::new(Day20::DAY, line_no, 1, "Expected 'Tile <number>:'")
+                })?;
+            let tile_no = parse_number(Day20::DAY, line_no, &line, tile_no)?;
+            current = Some((line_no, tile_no, Vec::new()));
         }
     }
 
-    if let Some(v) = tile.replace(Vec::new()) {
-        res.push(Tile { tile_no, tile: v });
+    if let Some((header_line, tile_no, rows)) = current.take() {
+        res.push(check_tile(header_line, tile_no, rows, res.first())?);
     }
-    res
+
+    let dim = image_dim(&res);
+    if res.is_empty() || dim * dim != res.len() {
+        return Err(ParseError::new(
+            Day20::DAY,
+            line_count + 1,
+            1,
+            format!("Expected a square number of tiles, found {}", res.len()),
+        ));
+    }
+    Ok(res)
 }
 
-fn border_coords(side: usize, dim: usize) -> Vec<(usize, usize)> {
+/// Checks that a tile is square and the same size as the first tile.
+fn check_tile(
+    header_line: usize,
+    tile_no: u64,
+    rows: Vec<Vec<char>>,
+    first: Option<&Tile>,
+) -> Result<Tile, ParseError> {
+    let dim = match first {
+        Some(first) => first.dim(),
+        None => rows.first().map_or(0, |row| row.len()),
+    };
+    if let Some(r) = rows.iter().position(|row| row.len() != dim) {
+        return Err(ParseError::new(
+            Day20::DAY,
+            header_line + r + 1,
+            1,
+            format!("Expected a row of {} pixels", dim),
+        ));
+    }
+    if rows.len() != dim || dim == 0 {
+        return Err(ParseError::new(
+            Day20::DAY,
+            header_line,
+            1,
+            format!("Expected {} rows of {} pixels", dim.max(1), dim.max(1)),
+        ));
+    }
+    Ok(Tile {
+        tile_no,
+        tile: Grid::from_rows(rows),
+    })
+}
+
+pub fn border_coords(side: usize, dim: usize) -> Vec<(usize, usize)> {
     match side {
         1 => (0..dim).map(|c| (0, c)).collect_vec(),
         2 => (0..dim).map(|r| (r, dim - 1)).collect_vec(),
@@ -98,9 +130,9 @@ fn border_coords(side: usize, dim: usize) -> Vec<(usize, usize)> {
     }
 }
 
-fn find_matches(tile1: &Tile, tile2: &Tile) -> Vec<usize> {
+pub fn find_matches(tile1: &Tile, tile2: &Tile) -> Vec<usize> {
     let mut res = Vec::new();
-    let dim = tile1.tile.len();
+    let dim = tile1.dim();
     for side1 in 1..5 {
         for side2 in 1..5 {
             for reverse in [false, true].iter() {
@@ -113,7 +145,7 @@ fn find_matches(tile1: &Tile, tile2: &Tile) -> Vec<usize> {
                 let matches = coords1
                     .iter()
                     .zip(coords2.iter())
-                    .all(|((r1, c1), (r2, c2))| tile1.tile[*r1][*c1] == tile2.tile[*r2][*c2]);
+                    .all(|((r1, c1), (r2, c2))| tile1.tile[(*r1, *c1)] == tile2.tile[(*r2, *c2)]);
                 if matches {
                     res.push(side1);
                 }
@@ -123,29 +155,39 @@ fn find_matches(tile1: &Tile, tile2: &Tile) -> Vec<usize> {
     res
 }
 
-fn find_upper_left_corner(tiles: &Vec<Tile>) -> &Tile {
-    tiles
+/// Finds a corner tile, one whose borders match those of exactly two other tiles, and turns it so that the matching
+/// borders face right and down. Corners that already face that way are preferred, then the other orientations in
+/// order. Returns `None` if there is no corner.
+pub fn find_upper_left_corner(tiles: &[Tile]) -> Option<Tile> {
+    if let [tile] = tiles {
+        return Some(tile.clone());
+    }
+    let matching_sides = |tile: &Tile| {
+        tiles
+            .iter()
+            .filter(|other_tile| other_tile.tile_no != tile.tile_no)
+            .flat_map(|other_tile| find_matches(tile, other_tile))
+            .collect_vec()
+    };
+    let corners = tiles
         .iter()
-        .find_map(|tile| {
-            let mut res = Vec::new();
-
-            for other_tile in tiles.iter() {
-                if other_tile.tile_no != tile.tile_no {
-                    let mut matches = find_matches(tile, other_tile);
-                    res.append(&mut matches);
-                }
-            }
+        .filter(|tile| matching_sides(tile).len() == 2)
+        .map(|tile| (tile.tile_no, tile.tile.orientations()))
+        .collect_vec();
 
-            if res.len() == 2 && res.contains(&2) && res.contains(&3) {
-                Some(tile)
-            } else {
-                None
-            }
+    (0..8).find_map(|orientation| {
+        corners.iter().find_map(|(tile_no, orientations)| {
+            let tile = Tile {
+                tile_no: *tile_no,
+                tile: orientations[orientation].clone(),
+            };
+            let sides = matching_sides(&tile);
+            (sides.contains(&2) && sides.contains(&3)).then_some(tile)
         })
-        .unwrap()
+    })
 }
 
-fn match_side(tile1: &Tile, tile2: &Tile, side1: usize, side2: usize) -> Option<Tile> {
+pub fn match_side(tile1: &Tile, tile2: &Tile, side1: usize, side2: usize) -> Option<Tile> {
     let coords1 = border_coords(side1, tile1.dim());
     let coords2 = border_coords(side2, tile2.dim());
     let mut tile2 = tile2.clone();
@@ -159,7 +201,7 @@ fn match_side(tile1: &Tile, tile2: &Tile, side1: usize, side2: usize) -> Option<
             let matches = coords1
                 .iter()
                 .zip(coords2.iter())
-                .all(|((r1, c1), (r2, c2))| tile1.tile[*r1][*c1] == tile2.tile[*r2][*c2]);
+                .all(|((r1, c1), (r2, c2))| tile1.tile[(*r1, *c1)] == tile2.tile[(*r2, *c2)]);
 
             if matches {
                 return Some(tile2);
@@ -171,273 +213,550 @@ fn match_side(tile1: &Tile, tile2: &Tile, side1: usize, side2: usize) -> Option<
     None
 }
 
-fn arrange_tiles(tiles: &Vec<Tile>, dim: usize) -> Vec<Vec<Tile>> {
+/// The number of tiles along each side of the square image.
+pub fn image_dim(tiles: &[Tile]) -> usize {
+    (tiles.len() as f64).sqrt() as usize
+}
+
+/// Returns `None` if the product does not fit in a `u64`.
+pub fn corner_product(arrangement: &[Vec<Tile>]) -> Option<u64> {
+    let last = arrangement.len() - 1;
+    [(0, 0), (0, last), (last, 0), (last, last)]
+        .iter()
+        .try_fold(1u64, |product, (r, c)| {
+            product.checked_mul(arrangement[*r][*c].tile_no)
+        })
+}
+
+/// Places the tiles in a square of `dim` by `dim`, turning each so that its borders match its neighbours'. Returns
+/// `None` if the tiles cannot be arranged.
+pub fn arrange_tiles(tiles: &[Tile], dim: usize) -> Option<Vec<Vec<Tile>>> {
+    arrange_tiles_traced(tiles, dim, &mut Trace::off())
+}
+
+/// Same as `arrange_tiles`, recording a `place` event for each tile with its position, which of its orientations it
+/// was placed in, and the tile whose border it matched.
+pub fn arrange_tiles_traced(
+    tiles: &[Tile],
+    dim: usize,
+    trace: &mut Trace,
+) -> Option<Vec<Vec<Tile>>> {
     let mut res: Vec<Vec<Tile>> = Vec::with_capacity(dim);
     let mut remaining_tiles: HashMap<u64, &Tile> =
         HashMap::from_iter(tiles.iter().map(|tile| (tile.tile_no, tile)));
 
-    let upper_left = find_upper_left_corner(&tiles);
-    remaining_tiles.remove(&upper_left.tile_no);
+    let upper_left = find_upper_left_corner(tiles)?;
+    let original = remaining_tiles.remove(&upper_left.tile_no)?;
 
     for r in 0..dim {
         let mut row = Vec::with_capacity(dim);
         if r == 0 {
+            trace.emit(|| placed(&upper_left, original, (0, 0), None));
             row.push(upper_left.clone())
         } else {
+            let above = &res[r - 1][0];
             let tile = remaining_tiles
                 .values()
-                .find_map(|tile| match_side(&res[r - 1][0], *tile, 3, 1))
-                .unwrap();
-            remaining_tiles.remove(&tile.tile_no);
+                .find_map(|tile| match_side(above, tile, 3, 1))?;
+            let original = remaining_tiles.remove(&tile.tile_no)?;
+            trace.emit(|| placed(&tile, original, (r, 0), Some((above, "below"))));
             row.push(tile);
         }
 
         for c in 1..dim {
+            let left = &row[c - 1];
             let tile = remaining_tiles
                 .values()
-                .find_map(|tile| match_side(&row[c - 1], *tile, 2, 4))
-                .unwrap();
-            remaining_tiles.remove(&tile.tile_no);
+                .find_map(|tile| match_side(left, tile, 2, 4))?;
+            let original = remaining_tiles.remove(&tile.tile_no)?;
+            trace.emit(|| placed(&tile, original, (r, c), Some((left, "right of"))));
             row.push(tile);
         }
 
         res.push(row)
     }
 
-    res
+    Some(res)
 }
 
-fn merge_tiles(tiles: &Vec<Vec<Tile>>) -> Tile {
-    let inner_dim = tiles[0][0].dim() - 2;
-    let dim = (inner_dim) * tiles.len();
-    let mut big_tile = Vec::with_capacity(dim);
-    for _ in 0..dim {
-        let mut row = Vec::with_capacity(dim);
-        for _ in 0..dim {
-            row.push(' ');
+/// The event for placing `tile`, an orientation of `original`, next to `neighbour` or as the upper left corner.
+fn placed(
+    tile: &Tile,
+    original: &Tile,
+    (row, column): (usize, usize),
+    neighbour: Option<(&Tile, &str)>,
+) -> Event {
+    let orientation = original
+        .tile
+        .orientations()
+        .iter()
+        .position(|orientation| *orientation == tile.tile);
+    let event = Event::new("place")
+        .with("tile", tile.tile_no)
+        .with("row", row)
+        .with("column", column)
+        .with("orientation", orientation);
+    match neighbour {
+        Some((neighbour, side)) => event.with("matched", neighbour.tile_no).with("side", side),
+        None => event.with("matched", "upper left corner"),
+    }
+}
+
+/// Checks that the arrangement is a square that places every tile once, each in one of its orientations, with the
+/// borders of neighbouring tiles matching. Returns the product of the corner tile numbers.
+pub fn check_arrangement(tiles: &[Tile], arrangement: &[Vec<Tile>]) -> Result<u64, String> {
+    let dim = arrangement.len();
+    if dim * dim != tiles.len() || arrangement.iter().any(|row| row.len() != dim) {
+        return Err(format!("Expected a square of {} tiles", tiles.len()));
+    }
+    let mut unplaced: HashMap<u64, &Tile> = tiles.iter().map(|tile| (tile.tile_no, tile)).collect();
+    for tile in arrangement.iter().flatten() {
+        let original = unplaced
+            .remove(&tile.tile_no)
+            .ok_or_else(|| format!("Tile {} is placed twice or does not exist", tile.tile_no))?;
+        if !original.tile.orientations().contains(&tile.tile) {
+            return Err(format!(
+                "Tile {} is not an orientation of itself",
+                tile.tile_no
+            ));
         }
-        big_tile.push(row);
     }
 
+    let column = |tile: &Tile, c: usize| (0..tile.dim()).map(|r| tile.tile[(r, c)]).collect_vec();
+    let row = |tile: &Tile, r: usize| tile.tile.rows().nth(r).unwrap().to_vec();
+    for (r, tiles) in arrangement.iter().enumerate() {
+        for (c, tile) in tiles.iter().enumerate() {
+            let last = tile.dim() - 1;
+            if c + 1 < dim && column(tile, last) != column(&tiles[c + 1], 0) {
+                return Err(format!(
+                    "Tiles at ({}, {}) and ({}, {}) do not match",
+                    r,
+                    c,
+                    r,
+                    c + 1
+                ));
+            }
+            if r + 1 < dim && row(tile, last) != row(&arrangement[r + 1][c], 0) {
+                return Err(format!(
+                    "Tiles at ({}, {}) and ({}, {}) do not match",
+                    r,
+                    c,
+                    r + 1,
+                    c
+                ));
+            }
+        }
+    }
+    corner_product(arrangement).ok_or_else(|| "The corner product overflows".to_string())
+}
+
+/// Joins the arranged tiles into one image without their borders. Returns `None` if the tiles are too small to have
+/// anything inside their borders.
+pub fn merge_tiles(tiles: &[Vec<Tile>]) -> Option<Tile> {
+    let tile_dim = tiles.first()?.first()?.dim();
+    if tile_dim < 3 {
+        return None;
+    }
+    let inner_dim = tile_dim - 2;
+    let dim = (inner_dim) * tiles.len();
+    let mut big_tile = Grid::filled(dim, dim, ' ');
+
     for (big_r, row) in tiles.iter().enumerate() {
         for (big_c, little_tile) in row.iter().enumerate() {
             for little_r in 1..little_tile.dim() - 1 {
                 for little_c in 1..little_tile.dim() - 1 {
-                    let ch = little_tile.tile[little_r][little_c];
-                    big_tile[big_r * inner_dim + little_r - 1][big_c * inner_dim + little_c - 1] =
-                        ch;
+                    let ch = little_tile.tile[(little_r, little_c)];
+                    big_tile[(
+                        big_r * inner_dim + little_r - 1,
+                        big_c * inner_dim + little_c - 1,
+                    )] = ch;
                 }
             }
         }
     }
 
-    Tile {
+    Some(Tile {
         tile_no: 0,
         tile: big_tile,
-    }
+    })
 }
 
-fn find_sea_monsters(tile: &Tile) -> usize {
+pub fn sea_monster_mask() -> Grid<char> {
     let sea_monster = "
                   # 
 #    ##    ##    ###
  #  #  #  #  #  #   
 ";
 
-    let mask = sea_monster[1..]
-        .lines()
-        .map(|line| line.chars().collect_vec())
-        .collect_vec();
+    Grid::from_rows(
+        sea_monster[1..]
+            .lines()
+            .map(|line| line.chars().collect_vec())
+            .collect_vec(),
+    )
+}
 
-    let mut sea_monsters = 0;
-    let mut image = tile.clone();
+/// Returns the number of `#` that are not part of a sea monster, in the orientation of the image with the most sea
+/// monsters.
+pub fn find_sea_monsters(tile: &Tile) -> usize {
+    let (_, cells) = locate_sea_monsters(tile);
+    count_hashes(&tile.tile) - cells.len()
+}
 
-    for flip in 0..2 {
-        if flip == 1 {
-            image = image.flip();
-        }
+/// Returns the orientation of the image with the most sea monsters, and the cells of that orientation that are part
+/// of a sea monster.
+pub fn locate_sea_monsters(tile: &Tile) -> (Grid<char>, HashSet<Pos>) {
+    let mask = sea_monster_mask();
+    tile.tile
+        .orientations()
+        .into_iter()
+        .map(|image| {
+            let cells = monster_positions(&image, &mask)
+                .flat_map(|(r, c)| {
+                    mask.iter()
+                        .filter(|(_, ch)| **ch == '#')
+                        .map(move |((mask_r, mask_c), _)| (r + mask_r, c + mask_c))
+                })
+                .collect::<HashSet<_>>();
+            (image, cells)
+        })
+        .max_by_key(|(_, cells)| cells.len())
+        .unwrap()
+}
 
-        for _ in 0..4 {
-            for r in 0..image.tile.len() - mask.len() {
-                for c in 0..image.tile.len() - mask[0].len() {
-                    if mask_match(&image.tile, r, c, &mask) {
-                        sea_monsters += 1;
-                    }
-                }
-            }
+/// The positions of the top left corners of the mask wherever it matches the image, including where it touches the
+/// image's last row or column.
+fn monster_positions<'a>(
+    image: &'a Grid<char>,
+    mask: &'a Grid<char>,
+) -> impl Iterator<Item = Pos> + 'a {
+    let rows = (image.height() + 1).saturating_sub(mask.height());
+    let columns = (image.width() + 1).saturating_sub(mask.width());
+    (0..rows)
+        .cartesian_product(0..columns)
+        .filter(move |(r, c)| mask_match(image, *r, *c, mask))
+}
 
-            image = image.rotate();
-        }
-    }
+pub fn count_hashes(tile: &Grid<char>) -> usize {
+    tile.count(|c| *c == '#')
+}
 
-    count_hashes(&tile.tile) - sea_monsters * count_hashes(&mask)
+pub fn mask_match(tile: &Grid<char>, r: usize, c: usize, mask: &Grid<char>) -> bool {
+    mask.iter().all(|((mask_r, mask_c), mask_char)| {
+        *mask_char != '#' || tile[(r + mask_r, c + mask_c)] == '#'
+    })
 }
 
-fn count_hashes(tile: &Vec<Vec<char>>) -> usize {
-    tile.iter()
-        .flat_map(|v| v.iter())
-        .filter(|c| **c == '#')
-        .count()
+pub struct Day20;
+
+impl Solution for Day20 {
+    const DAY: u8 = 20;
+    type Input = Vec<Tile>;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_tiles(lines(input))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        let arrangement = arrange_tiles(input, image_dim(input))?;
+        corner_product(&arrangement).map(|product| product.to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        let arrangement = arrange_tiles(input, image_dim(input))?;
+        Some(find_sea_monsters(&merge_tiles(&arrangement)?).to_string())
+    }
+
+    fn explain(&self, part: u8, input: &Self::Input, trace: &mut Trace) -> Option<String> {
+        let arrangement = arrange_tiles_traced(input, image_dim(input), trace)?;
+        match part {
+            1 => corner_product(&arrangement).map(|product| product.to_string()),
+            2 => Some(find_sea_monsters(&merge_tiles(&arrangement)?).to_string()),
+            _ => None,
+        }
+    }
 }
 
-fn mask_match(tile: &Vec<Vec<char>>, r: usize, c: usize, mask: &Vec<Vec<char>>) -> bool {
-    for mask_r in 0..mask.len() {
-        for mask_c in 0..mask[0].len() {
-            let mask_char = mask[mask_r][mask_c];
-            let tile_char = tile[r + mask_r][c + mask_c];
-            if mask_char == '#' && tile_char != '#' {
-                return false;
-            }
+impl Certify for Day20 {
+    /// The tiles in their places and orientations, for part 1.
+    type Certificate = Vec<Vec<Tile>>;
+
+    fn certify(&self, part: u8, input: &Self::Input) -> Option<Self::Certificate> {
+        match part {
+            1 => arrange_tiles(input, image_dim(input)),
+            _ => None,
         }
     }
-    true
+
+    fn check(
+        &self,
+        _part: u8,
+        input: &Self::Input,
+        certificate: &Self::Certificate,
+    ) -> Result<String, String> {
+        check_arrangement(input, certificate).map(|product| product.to_string())
+    }
+
+    fn describe(&self, certificate: &Self::Certificate) -> String {
+        certificate
+            .iter()
+            .map(|row| row.iter().map(|tile| tile.tile_no).join(" "))
+            .join("\n")
+    }
 }
 
+#[cfg(test)]
 mod tests {
-    use crate::day20::{arrange_tiles, find_sea_monsters, merge_tiles, parse_tiles, read_file};
-
-    const EXAMPLE: &str = "
-Tile 2311:
-..##.#..#.
-##..#.....
-#...##..#.
-####.#...#
-##.##.###.
-##...#.###
-.#.#.#..##
-..#....#..
-###...#.#.
-..###..###
-
-Tile 1951:
-#.##...##.
-#.####...#
-.....#..##
-#...######
-.##.#....#
-.###.#####
-###.##.##.
-.###....#.
-..#.#..#.#
-#...##.#..
-
-Tile 1171:
-####...##.
-#..##.#..#
-##.#..#.#.
-.###.####.
-..###.####
-.##....##.
-.#...####.
-#.##.####.
-####..#...
-.....##...
-
-Tile 1427:
-###.##.#..
-.#..#.##..
-.#.##.#..#
-#.#.#.##.#
-....#...##
-...##..##.
-...#.#####
-.#.####.#.
-..#..###.#
-..##.#..#.
-
-Tile 1489:
-##.#.#....
-..##...#..
-.##..##...
-..#...#...
-#####...#.
-#..#.#.#.#
-...#.#.#..
-##.#...##.
-..##.##.##
-###.##.#..
-
-Tile 2473:
-#....####.
-#..#.##...
-#.##..#...
-######.#.#
-.#...#.#.#
-.#########
-.###.#..#.
-########.#
-##...##.#.
-..###.#.#.
-
-Tile 2971:
-..#.#....#
-#...###...
-#.#.###...
-##.##..#..
-.#####..##
-.#..####.#
-#..#.#..#.
-..####.###
-..#.#.###.
-...#.#.#.#
-
-Tile 2729:
-...#.#.#.#
-####.#....
-..#.#.....
-....#..#.#
-.##..##.#.
-.#.####...
-####.#.#..
-##.####...
-##..#.##..
-#.##...##.
-
-Tile 3079:
-#.#.#####.
-.#..######
-..#.......
-######....
-####.#..#.
-.#...#.##.
-#.#####.##
-..#.###...
-..#.......
-..#.###...
-";
+    use crate::day20::{
+        arrange_tiles, arrange_tiles_traced, border_coords, check_arrangement, find_sea_monsters,
+        locate_sea_monsters, merge_tiles, parse_tiles, read_file, sea_monster_mask, Day20, Tile,
+    };
+    use crate::error::ParseError;
+    use crate::generate::{self, Rng};
+    use crate::grid::Grid;
+    use crate::input::read_fixture;
+    use crate::solution::{lines, Solution};
+    use crate::trace::Trace;
+    use itertools::Itertools;
+    use proptest::prelude::*;
+    use std::collections::HashSet;
+
+    fn border(tile: &Tile, side: usize) -> Vec<char> {
+        border_coords(side, tile.dim())
+            .into_iter()
+            .map(|pos| tile.tile[pos])
+            .collect_vec()
+    }
 
     #[test]
     fn test_part1_example() {
-        let tiles = parse_tiles(EXAMPLE[1..].lines().map(|line| line.to_string()));
-        let res = arrange_tiles(&tiles, 3);
+        let tiles = parse_tiles(lines(&read_fixture("day20.txt"))).unwrap();
+        let res = arrange_tiles(&tiles, 3).unwrap();
         let res = res[0][0].tile_no * res[0][2].tile_no * res[2][0].tile_no * res[2][2].tile_no;
         assert_eq!(20899048083289, res);
     }
 
+    #[test]
+    fn test_corner_orientations() {
+        let tiles = parse_tiles(lines(&read_fixture("day20.txt"))).unwrap();
+        for corner in [1951, 3079, 2971, 1171] {
+            let index = tiles
+                .iter()
+                .position(|tile| tile.tile_no == corner)
+                .unwrap();
+            for orientation in tiles[index].tile.orientations() {
+                let mut turned = tiles.clone();
+                turned[index].tile = orientation;
+                assert_eq!(Day20.part1(&turned), Some("20899048083289".to_string()));
+                assert_eq!(Day20.part2(&turned), Some("273".to_string()));
+            }
+        }
+
+        // Tiles from different parts of the image have no corner to start from.
+        let unrelated = vec![
+            tiles[0].clone(),
+            tiles[1].clone(),
+            tiles[4].clone(),
+            tiles[8].clone(),
+        ];
+        assert_eq!(Day20.part1(&unrelated), None);
+        assert_eq!(Day20.part2(&unrelated), None);
+    }
+
+    #[test]
+    fn test_check_arrangement() {
+        let tiles = parse_tiles(lines(&read_fixture("day20.txt"))).unwrap();
+        let mut arrangement = arrange_tiles(&tiles, 3).unwrap();
+        assert_eq!(check_arrangement(&tiles, &arrangement), Ok(20899048083289));
+
+        arrangement[1][1] = arrangement[1][1].flip();
+        assert_eq!(
+            check_arrangement(&tiles, &arrangement),
+            Err("Tiles at (0, 1) and (1, 1) do not match".to_string())
+        );
+        arrangement[1][1] = arrangement[0][0].clone();
+        assert!(check_arrangement(&tiles, &arrangement).is_err());
+        arrangement.pop();
+        assert!(check_arrangement(&tiles, &arrangement).is_err());
+        let mut scrambled = arrange_tiles(&tiles, 3).unwrap();
+        scrambled[2][2].tile[(0, 0)] = '?';
+        assert_eq!(
+            check_arrangement(&tiles, &scrambled),
+            Err(format!(
+                "Tile {} is not an orientation of itself",
+                scrambled[2][2].tile_no
+            ))
+        );
+    }
+
     #[test]
     fn test_part1() {
-        let tiles = parse_tiles(read_file());
-        let res = arrange_tiles(&tiles, 12);
+        let tiles = parse_tiles(read_file()).unwrap();
+        let res = arrange_tiles(&tiles, 12).unwrap();
         let res = res[0][0].tile_no * res[0][11].tile_no * res[11][0].tile_no * res[11][11].tile_no;
         assert_eq!(7901522557967, res);
     }
 
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn arrangement_borders_match(seed in any::<u64>(), dim in 3..6usize) {
+            let input = generate::day20(&mut Rng::new(seed), dim);
+            let tiles = parse_tiles(lines(&input)).unwrap();
+            let arrangement = arrange_tiles(&tiles, dim).unwrap();
+
+            let tile_nos = arrangement.iter().flatten().map(|tile| tile.tile_no).collect::<HashSet<_>>();
+            prop_assert_eq!(tile_nos, tiles.iter().map(|tile| tile.tile_no).collect());
+            for (r, row) in arrangement.iter().enumerate() {
+                prop_assert_eq!(row.len(), dim);
+                for (c, tile) in row.iter().enumerate() {
+                    if c + 1 < dim {
+                        prop_assert_eq!(border(tile, 2), border(&row[c + 1], 4));
+                    }
+                    if r + 1 < dim {
+                        prop_assert_eq!(border(tile, 3), border(&arrangement[r + 1][c], 1));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sea_monster_at_edge() {
+        // A monster touching the image's last row and last column, and one in the top left corner.
+        let mask = sea_monster_mask();
+        let (dim, r, c) = (24, 24 - mask.height(), 24 - mask.width());
+        let mut image = Grid::filled(dim, dim, '.');
+        for ((mask_r, mask_c), ch) in mask.iter() {
+            if *ch == '#' {
+                image[(r + mask_r, c + mask_c)] = '#';
+                image[(mask_r, mask_c)] = '#';
+            }
+        }
+        let tile = Tile {
+            tile_no: 0,
+            tile: image,
+        };
+        assert_eq!(find_sea_monsters(&tile), 0);
+        let (oriented, cells) = locate_sea_monsters(&tile);
+        assert_eq!(cells.len(), 30);
+        assert_eq!(oriented, tile.tile);
+        assert!(cells.iter().any(|(r, _)| *r == dim - 1));
+        assert!(cells.iter().any(|(_, c)| *c == dim - 1));
+
+        // The image is smaller than a monster.
+        let tile = Tile {
+            tile_no: 0,
+            tile: Grid::filled(8, 8, '#'),
+        };
+        assert_eq!(find_sea_monsters(&tile), 64);
+    }
+
+    #[test]
+    fn test_sea_monsters_in_one_orientation() {
+        // Two monsters, and one upside down that is only rough water in the orientation with the other two.
+        let mask = sea_monster_mask();
+        let mut image = Grid::filled(24, 24, '.');
+        for ((mask_r, mask_c), ch) in mask.iter() {
+            if *ch == '#' {
+                image[(mask_r, mask_c)] = '#';
+                image[(4 + mask_r, 2 + mask_c)] = '#';
+                image[(12 - mask_r, 3 + mask_c)] = '#';
+            }
+        }
+        let tile = Tile {
+            tile_no: 0,
+            tile: image,
+        };
+        assert_eq!(find_sea_monsters(&tile), 15);
+        assert_eq!(locate_sea_monsters(&tile).0, tile.tile);
+    }
+
     #[test]
     fn test_part2_example() {
-        let tiles = parse_tiles(EXAMPLE[1..].lines().map(|line| line.to_string()));
-        let res = arrange_tiles(&tiles, 3);
-        let res = merge_tiles(&res);
+        let tiles = parse_tiles(lines(&read_fixture("day20.txt"))).unwrap();
+        let res = arrange_tiles(&tiles, 3).unwrap();
+        let res = merge_tiles(&res).unwrap();
         let res = find_sea_monsters(&res);
         assert_eq!(273, res);
     }
 
+    #[test]
+    fn test_tiles_without_inside() {
+        // A lone tile is its own corner, but tiles under 3x3 are all border.
+        for tile in ["Tile 1:\n#\n", "Tile 1:\n#.\n.#\n"] {
+            let tiles = parse_tiles(lines(tile)).unwrap();
+            assert_eq!(Day20.part1(&tiles), Some("1".to_string()));
+            assert_eq!(Day20.part2(&tiles), None);
+        }
+    }
+
+    #[test]
+    fn test_corner_product_overflow() {
+        let tiles = parse_tiles(lines("Tile 9999999999:\n#.\n.#\n")).unwrap();
+        assert_eq!(Day20.part1(&tiles), None);
+    }
+
     #[test]
     fn test_part2() {
-        let tiles = parse_tiles(read_file());
-        let res = arrange_tiles(&tiles, 12);
-        let res = merge_tiles(&res);
+        let tiles = parse_tiles(read_file()).unwrap();
+        let res = arrange_tiles(&tiles, 12).unwrap();
+        let res = merge_tiles(&res).unwrap();
         let res = find_sea_monsters(&res);
         println!("{}", res);
         // assert_eq!(273, res);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |s: &str| parse_tiles(s.lines().map(|l| l.to_string()));
+        assert!(parse("Tile 1:\n#.\n.#").is_ok());
+        assert_eq!(
+            parse("Tile 1:\n#.\n.x").unwrap_err(),
+            ParseError::new(20, 3, 2, "Unexpected character: 'x'")
+        );
+        assert_eq!(
+            parse("Tile one:\n#.\n.#").unwrap_err(),
+            ParseError::new(20, 1, 6, "Invalid number: 'one'")
+        );
+        assert_eq!(
+            parse("Tile 1:\n#.\n.#.").unwrap_err(),
+            ParseError::new(20, 3, 1, "Expected a row of 2 pixels")
+        );
+        assert_eq!(
+            parse("Tile 1:\n#.\n.#\n..").unwrap_err(),
+            ParseError::new(20, 1, 1, "Expected 2 rows of 2 pixels")
+        );
+        assert_eq!(
+            parse("Tile 1:\n#.\n.#\n\nTile 2:\n#.\n.#").unwrap_err(),
+            ParseError::new(20, 8, 1, "Expected a square number of tiles, found 2")
+        );
+    }
+
+    #[test]
+    fn test_trace() {
+        let tiles = parse_tiles(lines(&read_fixture("day20.txt"))).unwrap();
+        let mut events = Vec::new();
+        let arrangement =
+            arrange_tiles_traced(&tiles, 3, &mut Trace::to(|event| events.push(event))).unwrap();
+        assert_eq!(events.len(), 9);
+        assert!(events[0]
+            .to_string()
+            .ends_with("row=0 column=0 orientation=0 matched=upper left corner"));
+        for (event, (r, c)) in events.iter().zip((0..3).cartesian_product(0..3)) {
+            assert_eq!(event.step, "place");
+            assert_eq!(*event.get("tile").unwrap(), arrangement[r][c].tile_no);
+            assert_eq!(*event.get("row").unwrap(), r);
+            assert_eq!(*event.get("column").unwrap(), c);
+            assert!(event.get("orientation").unwrap().is_u64());
+            let (neighbour, side) = match (r, c) {
+                (0, 0) => continue,
+                (_, 0) => (&arrangement[r - 1][0], "below"),
+                _ => (&arrangement[r][c - 1], "right of"),
+            };
+            assert_eq!(*event.get("matched").unwrap(), neighbour.tile_no);
+            assert_eq!(*event.get("side").unwrap(), side);
+        }
+    }
 }
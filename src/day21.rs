@@ -1,39 +1,60 @@
+use crate::assignment::assign;
+use crate::error::ParseError;
+use crate::input::read_day_lines;
+use crate::solution::{lines, Solution};
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
 use std::iter::FromIterator;
 
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day21.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap().to_string())
+pub fn read_file() -> impl Iterator<Item = String> {
+    read_day_lines(Day21::DAY)
 }
 
-struct Food {
-    ingredients: HashSet<String>,
-    allergens: Vec<String>,
+#[derive(Serialize, Deserialize)]
+pub struct Food {
+    pub ingredients: HashSet<String>,
+    pub allergens: Vec<String>,
 }
 
-fn parse_foods(iter: impl Iterator<Item = String>) -> Vec<Food> {
-    iter.map(|line| {
-        let (part1, part2) = line.split(" (contains ").collect_tuple().unwrap();
-        let ingredients = HashSet::from_iter(part1.split_whitespace().map(|s| s.to_string()));
-        let allergens = part2
-            .strip_suffix(")")
-            .unwrap()
-            .split(", ")
-            .map(|s| s.to_string())
-            .collect_vec();
-        Food {
-            ingredients,
-            allergens,
-        }
-    })
-    .collect_vec()
+/// Parses one food per line. The "(contains ...)" list is optional since allergens are not always marked.
+pub fn parse_foods(iter: impl Iterator<Item = String>) -> Result<Vec<Food>, ParseError> {
+    iter.enumerate()
+        .map(|(i, line)| {
+            let line_no = i + 1;
+            let (part1, part2) = match line.split_once(" (contains ") {
+                Some((part1, part2)) => (part1, Some(part2)),
+                None => (line.as_str(), None),
+            };
+            let ingredients = HashSet::from_iter(part1.split_whitespace().map(|s| s.to_string()));
+            if ingredients.is_empty() {
+                return Err(ParseError::new(
+                    Day21::DAY,
+                    line_no,
+                    1,
+                    "Expected at least one ingredient",
+                ));
+            }
+            let allergens = match part2 {
+                Some(part2) => part2
+                    .strip_suffix(')')
+                    .ok_or_else(|| {
+                        ParseError::new(Day21::DAY, line_no, line.chars().count(), "Expected ')'")
+                    })?
+                    .split(", ")
+                    .map(|s| s.to_string())
+                    .collect_vec(),
+                None => Vec::new(),
+            };
+            Ok(Food {
+                ingredients,
+                allergens,
+            })
+        })
+        .collect()
 }
 
-fn find_allergen_candidates(foods: &Vec<Food>) -> HashMap<&String, HashSet<&String>> {
+pub fn find_allergen_candidates(foods: &[Food]) -> HashMap<&String, HashSet<&String>> {
     let mut allergens: HashMap<&String, HashSet<&String>> = HashMap::new();
     for food in foods.iter() {
         for allergen in food.allergens.iter() {
@@ -49,54 +70,27 @@ fn find_allergen_candidates(foods: &Vec<Food>) -> HashMap<&String, HashSet<&Stri
     allergens
 }
 
-fn find_safe_ingredients(foods: &Vec<Food>) -> Vec<&String> {
+pub fn find_safe_ingredients(foods: &[Food]) -> Vec<&String> {
     let allergens = find_allergen_candidates(foods);
 
     foods
         .iter()
         .flat_map(|food| food.ingredients.iter())
-        .into_iter()
         .filter(|i| allergens.values().all(|set| !set.contains(*i)))
         .collect_vec()
 }
 
-fn find_unsafe_ingredients(foods: &Vec<Food>) -> HashMap<&String, &String> {
-    let mut allergens = find_allergen_candidates(foods);
-
-    // Remove unique field assignments from the other sets.
-    // Continue until all sets are unique.
-    loop {
-        let singletons = allergens
-            .iter()
-            .filter_map(|(_allergen, ingredients)| {
-                if ingredients.len() == 1 {
-                    Some(*ingredients.iter().next().unwrap())
-                } else {
-                    None
-                }
-            })
-            .collect_vec();
-
-        if singletons.len() == allergens.len() {
-            break;
-        }
-
-        for ingredient in singletons {
-            for (_allergen, ingredients) in allergens.iter_mut() {
-                if ingredients.len() > 1 {
-                    ingredients.remove(ingredient);
-                }
-            }
-        }
-    }
-
-    allergens
-        .iter()
-        .map(|(allergen, ingredients)| (*allergen, *ingredients.iter().next().unwrap()))
-        .collect()
+/// Works out which ingredient contains each allergen. Returns `None` unless the foods allow exactly one answer.
+pub fn find_unsafe_ingredients(foods: &[Food]) -> Option<HashMap<&String, &String>> {
+    let (allergens, candidates): (Vec<_>, Vec<_>) = find_allergen_candidates(foods)
+        .into_iter()
+        .sorted_by_key(|(allergen, _)| *allergen)
+        .unzip();
+    let ingredients = assign(&candidates).unique()?.to_vec();
+    Some(allergens.into_iter().zip(ingredients).collect())
 }
 
-fn to_canonical_list(allergens: &HashMap<&String, &String>) -> String {
+pub fn to_canonical_list(allergens: &HashMap<&String, &String>) -> String {
     allergens
         .iter()
         .sorted_by_key(|(allergen, _ingredient)| **allergen)
@@ -104,21 +98,36 @@ fn to_canonical_list(allergens: &HashMap<&String, &String>) -> String {
         .join(",")
 }
 
+pub struct Day21;
+
+impl Solution for Day21 {
+    const DAY: u8 = 21;
+    type Input = Vec<Food>;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_foods(lines(input))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        Some(find_safe_ingredients(input).len().to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        Some(to_canonical_list(&find_unsafe_ingredients(input)?))
+    }
+}
+
+#[cfg(test)]
 mod tests {
     use crate::day21::{
         find_safe_ingredients, find_unsafe_ingredients, parse_foods, read_file, to_canonical_list,
     };
-
-    const EXAMPLE: &str = "
-mxmxvkd kfcds sqjhc nhms (contains dairy, fish)
-trh fvjkl sbzzf mxmxvkd (contains dairy)
-sqjhc fvjkl (contains soy)
-sqjhc mxmxvkd sbzzf (contains fish)
-";
+    use crate::input::read_fixture;
+    use crate::solution::lines;
 
     #[test]
     fn test_part1_example() {
-        let foods = parse_foods(EXAMPLE[1..].lines().map(|line| line.to_string()));
+        let foods = parse_foods(lines(&read_fixture("day21.txt"))).unwrap();
         let res = find_safe_ingredients(&foods);
         println!("{:?}", res);
         assert_eq!(5, res.len());
@@ -126,7 +135,7 @@ sqjhc mxmxvkd sbzzf (contains fish)
 
     #[test]
     fn test_part1() {
-        let foods = parse_foods(read_file());
+        let foods = parse_foods(read_file()).unwrap();
         let res = find_safe_ingredients(&foods);
         println!("{}", res.len());
         assert_eq!(1829, res.len());
@@ -134,16 +143,16 @@ sqjhc mxmxvkd sbzzf (contains fish)
 
     #[test]
     fn test_part2_example() {
-        let foods = parse_foods(EXAMPLE[1..].lines().map(|line| line.to_string()));
-        let res = find_unsafe_ingredients(&foods);
+        let foods = parse_foods(lines(&read_fixture("day21.txt"))).unwrap();
+        let res = find_unsafe_ingredients(&foods).unwrap();
         println!("{:?}", res);
         assert_eq!("mxmxvkd,sqjhc,fvjkl", to_canonical_list(&res));
     }
 
     #[test]
     fn test_part2() {
-        let foods = parse_foods(read_file());
-        let res = find_unsafe_ingredients(&foods);
+        let foods = parse_foods(read_file()).unwrap();
+        let res = find_unsafe_ingredients(&foods).unwrap();
         let res = to_canonical_list(&res);
         println!("{}", res);
         assert_eq!("mxkh,gkcqxs,bvh,sp,rgc,krjn,bpbdlmg,tdbcfb", res);
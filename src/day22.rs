@@ -1,35 +1,84 @@
+use crate::budget::{Budget, Interrupted};
+use crate::error::{parse_number, ParseError};
+use crate::input::read_day_lines;
+use crate::solution::{lines, Solution};
+use crate::trace::{Event, Trace};
 use std::collections::{HashSet, VecDeque};
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
 use std::iter::FromIterator;
-use std::str::FromStr;
 
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day22.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap().to_string())
+pub fn read_file() -> impl Iterator<Item = String> {
+    read_day_lines(Day22::DAY)
 }
 
-fn parse_decks(iter: impl Iterator<Item = String>) -> (VecDeque<u64>, VecDeque<u64>) {
-    let mut deck1 = VecDeque::new();
-    let mut deck2 = VecDeque::new();
+pub fn parse_decks(
+    iter: impl Iterator<Item = String>,
+) -> Result<(VecDeque<u64>, VecDeque<u64>), ParseError> {
+    let mut decks = vec![VecDeque::new(), VecDeque::new()];
 
-    let mut switched = false;
-    for line in iter.filter(|line| !line.starts_with("Player")) {
-        if line.is_empty() {
-            switched = true;
-        } else if switched {
-            deck2.push_back(u64::from_str(&line).unwrap());
+    let mut player = 0;
+    let mut expect_header = true;
+    for (i, line) in iter.enumerate() {
+        let line_no = i + 1;
+        if expect_header {
+            player += 1;
+            if player > 2 || line != format!("Player {}:", player) {
+                return Err(ParseError::new(
+                    Day22::DAY,
+                    line_no,
+                    1,
+                    format!("Expected 'Player {}:'", player.min(2)),
+                ));
+            }
+            expect_header = false;
+        } else if line.is_empty() {
+            expect_header = true;
         } else {
-            deck1.push_back(u64::from_str(&line).unwrap())
+            decks[player - 1].push_back(parse_number(Day22::DAY, line_no, &line, &line)?);
         }
     }
 
-    (deck1, deck2)
+    let deck2 = decks.pop().unwrap();
+    let deck1 = decks.pop().unwrap();
+    Ok((deck1, deck2))
+}
+
+/// Plays until one player has all the cards. Returns `None` if the decks come back to those of an earlier round, since
+/// then the game never ends.
+pub fn combat(deck1: &mut VecDeque<u64>, deck2: &mut VecDeque<u64>) -> Option<(usize, u64)> {
+    combat_with(deck1, deck2, |_, _| {})
+}
+
+/// Same as `combat`, calling `observe` with the decks before the first round and after every round.
+pub fn combat_with(
+    deck1: &mut VecDeque<u64>,
+    deck2: &mut VecDeque<u64>,
+    mut observe: impl FnMut(&VecDeque<u64>, &VecDeque<u64>),
+) -> Option<(usize, u64)> {
+    play_combat(deck1, deck2, &mut observe, &mut Budget::unlimited())
+        .expect("An unlimited budget never runs out")
+}
+
+/// Same as `combat`, stopping if the budget runs out. Each round is a step.
+pub fn combat_within(
+    deck1: &mut VecDeque<u64>,
+    deck2: &mut VecDeque<u64>,
+    budget: &mut Budget,
+) -> Result<Option<(usize, u64)>, Interrupted> {
+    play_combat(deck1, deck2, &mut |_, _| {}, budget)
 }
 
-fn combat(deck1: &mut VecDeque<u64>, deck2: &mut VecDeque<u64>) -> (usize, u64) {
+fn play_combat(
+    deck1: &mut VecDeque<u64>,
+    deck2: &mut VecDeque<u64>,
+    observe: &mut dyn FnMut(&VecDeque<u64>, &VecDeque<u64>),
+    budget: &mut Budget,
+) -> Result<Option<(usize, u64)>, Interrupted> {
+    let mut states = HashSet::new();
+    observe(deck1, deck2);
     while !deck1.is_empty() && !deck2.is_empty() {
+        if !states.insert((deck1.clone(), deck2.clone())) {
+            return Ok(None);
+        }
         let card1 = deck1.pop_front().unwrap();
         let card2 = deck2.pop_front().unwrap();
         if card1 > card2 {
@@ -39,12 +88,15 @@ fn combat(deck1: &mut VecDeque<u64>, deck2: &mut VecDeque<u64>) -> (usize, u64)
             deck2.push_back(card2);
             deck2.push_back(card1);
         }
+        observe(deck1, deck2);
+        budget.tick(None)?;
     }
 
-    calc_result(&deck1, &deck2)
+    Ok(calc_result(deck1, deck2))
 }
 
-fn calc_result(deck1: &VecDeque<u64>, deck2: &VecDeque<u64>) -> (usize, u64) {
+/// Returns the winner and their score, or `None` if the score does not fit in a `u64`.
+pub fn calc_result(deck1: &VecDeque<u64>, deck2: &VecDeque<u64>) -> Option<(usize, u64)> {
     let (winner, winning_deck) = if deck1.is_empty() {
         (2, &deck2)
     } else {
@@ -54,30 +106,125 @@ fn calc_result(deck1: &VecDeque<u64>, deck2: &VecDeque<u64>) -> (usize, u64) {
     let winning_score = winning_deck
         .iter()
         .zip((1..winning_deck.len() + 1).rev())
-        .map(|(card, pos)| *card * pos as u64)
-        .sum();
+        .try_fold(0u64, |score, (card, pos)| {
+            score.checked_add(card.checked_mul(pos as u64)?)
+        })?;
+
+    Some((winner, winning_score))
+}
+
+/// Returns `None` if the winner's score does not fit in a `u64`.
+pub fn recursive_combat(
+    deck1: &mut VecDeque<u64>,
+    deck2: &mut VecDeque<u64>,
+) -> Option<(usize, u64)> {
+    recursive_combat_with(deck1, deck2, |_, _| {})
+}
+
+/// Same as `recursive_combat`, calling `observe` with the decks before the first round and after every round of this
+/// game. The rounds of sub-games are not observed.
+pub fn recursive_combat_with(
+    deck1: &mut VecDeque<u64>,
+    deck2: &mut VecDeque<u64>,
+    mut observe: impl FnMut(&VecDeque<u64>, &VecDeque<u64>),
+) -> Option<(usize, u64)> {
+    let mut budget = Budget::unlimited();
+    play_recursive_combat(
+        deck1,
+        deck2,
+        &mut observe,
+        &mut budget,
+        &mut Trace::off(),
+        &mut 0,
+    )
+    .expect("An unlimited budget never runs out");
+    calc_result(deck1, deck2)
+}
+
+/// Same as `recursive_combat`, recording a `game` event with the decks at the start of each game, numbered in the
+/// order they start, a `sub_game` event for the round of the game that started it, a `repeat` event when a game ends
+/// because a round repeated, and a `winner` event when a game ends.
+pub fn recursive_combat_traced(
+    deck1: &mut VecDeque<u64>,
+    deck2: &mut VecDeque<u64>,
+    trace: &mut Trace,
+) -> Option<(usize, u64)> {
+    let mut budget = Budget::unlimited();
+    play_recursive_combat(deck1, deck2, &mut |_, _| {}, &mut budget, trace, &mut 0)
+        .expect("An unlimited budget never runs out");
+    calc_result(deck1, deck2)
+}
 
-    (winner, winning_score)
+/// Same as `recursive_combat`, stopping if the budget runs out. Each round of the game or any of its sub-games is a
+/// step.
+pub fn recursive_combat_within(
+    deck1: &mut VecDeque<u64>,
+    deck2: &mut VecDeque<u64>,
+    budget: &mut Budget,
+) -> Result<Option<(usize, u64)>, Interrupted> {
+    play_recursive_combat(
+        deck1,
+        deck2,
+        &mut |_, _| {},
+        budget,
+        &mut Trace::off(),
+        &mut 0,
+    )?;
+    Ok(calc_result(deck1, deck2))
 }
 
-fn recursive_combat(deck1: &mut VecDeque<u64>, deck2: &mut VecDeque<u64>) -> (usize, u64) {
+/// Plays a game and returns its winner. The score is left to the caller, since only the outer game needs it.
+fn play_recursive_combat(
+    deck1: &mut VecDeque<u64>,
+    deck2: &mut VecDeque<u64>,
+    observe: &mut dyn FnMut(&VecDeque<u64>, &VecDeque<u64>),
+    budget: &mut Budget,
+    trace: &mut Trace,
+    games: &mut usize,
+) -> Result<usize, Interrupted> {
+    *games += 1;
+    let game = *games;
+    trace.emit(|| {
+        Event::new("game")
+            .with("game", game)
+            .with("deck1", &*deck1)
+            .with("deck2", &*deck2)
+    });
     let mut states = HashSet::new();
+    let mut rounds = 0;
+    observe(deck1, deck2);
 
     while !deck1.is_empty() && !deck2.is_empty() {
         let state = (deck1.clone(), deck2.clone());
+        rounds += 1;
         if states.contains(&state) {
+            trace.emit(|| {
+                Event::new("repeat")
+                    .with("game", game)
+                    .with("round", rounds)
+            });
             deck2.clear();
         } else {
             let card1 = deck1.pop_front().unwrap();
             let card2 = deck2.pop_front().unwrap();
 
             let winner = if card1 <= (deck1.len() as u64) && card2 <= (deck2.len() as u64) {
-                let mut sub_deck1 =
-                    VecDeque::from_iter(deck1.iter().take(card1 as usize).map(|v| *v));
-                let mut sub_deck2 =
-                    VecDeque::from_iter(deck2.iter().take(card2 as usize).map(|v| *v));
-                let (sub_game_winner, _) = recursive_combat(&mut sub_deck1, &mut sub_deck2);
-                sub_game_winner
+                let mut sub_deck1 = VecDeque::from_iter(deck1.iter().take(card1 as usize).copied());
+                let mut sub_deck2 = VecDeque::from_iter(deck2.iter().take(card2 as usize).copied());
+                trace.emit(|| {
+                    Event::new("sub_game")
+                        .with("game", game)
+                        .with("round", rounds)
+                        .with("sub_game", *games + 1)
+                });
+                play_recursive_combat(
+                    &mut sub_deck1,
+                    &mut sub_deck2,
+                    &mut |_, _| {},
+                    budget,
+                    trace,
+                    games,
+                )?
             } else if card1 > card2 {
                 1
             } else {
@@ -94,69 +241,211 @@ fn recursive_combat(deck1: &mut VecDeque<u64>, deck2: &mut VecDeque<u64>) -> (us
 
             states.insert(state);
         }
+        observe(deck1, deck2);
+        budget.tick(None)?;
     }
 
-    calc_result(&deck1, &deck2)
+    let winner = if deck1.is_empty() { 2 } else { 1 };
+    trace.emit(|| {
+        Event::new("winner")
+            .with("game", game)
+            .with("player", winner)
+            .with("rounds", rounds)
+            .with("score", calc_result(deck1, deck2).map(|(_, score)| score))
+    });
+    Ok(winner)
 }
 
+pub struct Day22;
+
+impl Solution for Day22 {
+    const DAY: u8 = 22;
+    type Input = (VecDeque<u64>, VecDeque<u64>);
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_decks(lines(input))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        let (mut deck1, mut deck2) = input.clone();
+        let (_, res) = combat(&mut deck1, &mut deck2)?;
+        Some(res.to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        let (mut deck1, mut deck2) = input.clone();
+        let (_, res) = recursive_combat(&mut deck1, &mut deck2)?;
+        Some(res.to_string())
+    }
+
+    fn solve_within(
+        &self,
+        part: u8,
+        input: &Self::Input,
+        budget: &mut Budget,
+    ) -> Result<Option<String>, Interrupted> {
+        match part {
+            1 => {
+                let (mut deck1, mut deck2) = input.clone();
+                combat_within(&mut deck1, &mut deck2, budget)
+                    .map(|res| res.map(|(_, res)| res.to_string()))
+            }
+            2 => {
+                let (mut deck1, mut deck2) = input.clone();
+                recursive_combat_within(&mut deck1, &mut deck2, budget)
+                    .map(|res| res.map(|(_, res)| res.to_string()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn explain(&self, part: u8, input: &Self::Input, trace: &mut Trace) -> Option<String> {
+        match part {
+            1 => self.part1(input),
+            2 => {
+                let (mut deck1, mut deck2) = input.clone();
+                let (_, res) = recursive_combat_traced(&mut deck1, &mut deck2, trace)?;
+                Some(res.to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
 mod tests {
-    use crate::day22::{combat, parse_decks, read_file, recursive_combat};
-
-    const EXAMPLE: &str = "
-Player 1:
-9
-2
-6
-3
-1
-
-Player 2:
-5
-8
-4
-7
-10
-";
+    use crate::budget::{Budget, Interrupted};
+    use crate::day22::{
+        calc_result, combat, combat_within, parse_decks, read_file, recursive_combat,
+        recursive_combat_traced, recursive_combat_within, Day22,
+    };
+    use crate::generate::{self, Rng};
+    use crate::input::read_fixture;
+    use crate::solution::{lines, Solution};
+    use crate::trace::{Event, Trace};
+    use itertools::Itertools;
+    use proptest::prelude::*;
+    use serde_json::Value;
+    use std::collections::VecDeque;
+    use std::time::Duration;
+
+    fn decks(seed: u64, size: usize) -> (VecDeque<u64>, VecDeque<u64>) {
+        let input = generate::day22(&mut Rng::new(seed), size);
+        parse_decks(lines(&input)).unwrap()
+    }
+
+    fn all_cards(deck1: &VecDeque<u64>, deck2: &VecDeque<u64>) -> Vec<u64> {
+        deck1
+            .iter()
+            .chain(deck2.iter())
+            .copied()
+            .sorted()
+            .collect_vec()
+    }
 
     #[test]
     fn test_part1_example() {
-        let iter = EXAMPLE[1..].lines().map(|line| line.to_string());
-        let (mut deck1, mut deck2) = parse_decks(iter);
-        let (_, res) = combat(&mut deck1, &mut deck2);
-        assert_eq!(306, res);
+        let (mut deck1, mut deck2) = parse_decks(lines(&read_fixture("day22.txt"))).unwrap();
+        let res = combat(&mut deck1, &mut deck2);
+        assert_eq!(Some((2, 306)), res);
     }
 
     #[test]
     fn test_part1() {
         let iter = read_file();
-        let (mut deck1, mut deck2) = parse_decks(iter);
-        let (_, res) = combat(&mut deck1, &mut deck2);
+        let (mut deck1, mut deck2) = parse_decks(iter).unwrap();
+        let (_, res) = combat(&mut deck1, &mut deck2).unwrap();
         // println!("{}", res);
         assert_eq!(30138, res);
     }
 
+    #[test]
+    fn test_part1_repeat() {
+        // Without the repeat rule, the second example goes round in circles.
+        let (deck1, deck2) = parse_decks(lines(&read_fixture("day22-2.txt"))).unwrap();
+        let res = combat(&mut deck1.clone(), &mut deck2.clone());
+        assert_eq!(res, None);
+        assert_eq!(Day22.part1(&(deck1.clone(), deck2.clone())), None);
+        let mut budget = Budget::unlimited();
+        let res = combat_within(&mut deck1.clone(), &mut deck2.clone(), &mut budget);
+        assert_eq!(res, Ok(None));
+        assert!(budget.done() > 0);
+
+        let (mut deck1, mut deck2) = parse_decks(read_file()).unwrap();
+        let res = combat_within(&mut deck1, &mut deck2, &mut Budget::unlimited());
+        assert_eq!(res.unwrap().map(|(_, score)| score), Some(30138));
+    }
+
+    proptest! {
+        #[test]
+        fn combat_conserves_cards(seed in any::<u64>(), size in 1..20usize) {
+            let (mut deck1, mut deck2) = decks(seed, size);
+            let cards = all_cards(&deck1, &deck2);
+            let res = combat(&mut deck1, &mut deck2);
+            prop_assert_eq!(all_cards(&deck1, &deck2), cards);
+            // A game that repeats stops with both players still holding cards.
+            if let Some((winner, score)) = res {
+                prop_assert!(deck1.is_empty() || deck2.is_empty());
+                prop_assert_eq!(winner, if deck2.is_empty() { 1 } else { 2 });
+                prop_assert_eq!(Some((winner, score)), calc_result(&deck1, &deck2));
+            }
+        }
+
+        #[test]
+        fn recursive_combat_conserves_cards(seed in any::<u64>(), size in 1..12usize) {
+            let (mut deck1, mut deck2) = decks(seed, size);
+            let cards = all_cards(&deck1, &deck2);
+            // Only a repeated round in the outer game loses cards; sub-games that repeat hand theirs back.
+            let mut repeated = false;
+            let (winner, _) = recursive_combat_traced(
+                &mut deck1,
+                &mut deck2,
+                &mut Trace::to(|event| {
+                    if event.step == "repeat" && event.get("game").and_then(Value::as_u64) == Some(1) {
+                        repeated = true;
+                    }
+                }),
+            ).unwrap();
+            prop_assert!(deck1.is_empty() || deck2.is_empty());
+            let remaining = all_cards(&deck1, &deck2);
+            if repeated {
+                // The repeated round ends the game for player 1 and discards player 2's cards.
+                prop_assert_eq!(winner, 1);
+                prop_assert!(remaining.iter().all(|card| cards.contains(card)));
+                prop_assert!(remaining.iter().tuple_windows().all(|(a, b)| a < b));
+            } else {
+                prop_assert_eq!(remaining, cards);
+            }
+        }
+    }
+
+    #[test]
+    fn test_score_overflow() {
+        let decks = Day22
+            .parse("Player 1:\n18446744073709551615\n1\n\nPlayer 2:\n2\n")
+            .unwrap();
+        assert_eq!(Day22.part1(&decks), None);
+        assert_eq!(Day22.part2(&decks), None);
+        let decks = Day22
+            .parse("Player 1:\n18446744073709551615\n\nPlayer 2:\n")
+            .unwrap();
+        assert_eq!(
+            Day22.part1(&decks),
+            Some("18446744073709551615".to_string())
+        );
+    }
+
     #[test]
     fn test_part2_example1() {
-        let iter = EXAMPLE[1..].lines().map(|line| line.to_string());
-        let (mut deck1, mut deck2) = parse_decks(iter);
-        let (_, res) = recursive_combat(&mut deck1, &mut deck2);
+        let (mut deck1, mut deck2) = parse_decks(lines(&read_fixture("day22.txt"))).unwrap();
+        let (_, res) = recursive_combat(&mut deck1, &mut deck2).unwrap();
         assert_eq!(291, res);
     }
 
     #[test]
     fn test_part2_example2() {
-        let example = "Player 1:
-43
-19
-
-Player 2:
-2
-29
-14
-";
-        let iter = example.lines().map(|line| line.to_string());
-        let (mut deck1, mut deck2) = parse_decks(iter);
-        let (winner, _) = recursive_combat(&mut deck1, &mut deck2);
+        let (mut deck1, mut deck2) = parse_decks(lines(&read_fixture("day22-2.txt"))).unwrap();
+        let (winner, _) = recursive_combat(&mut deck1, &mut deck2).unwrap();
         assert_eq!(1, winner);
         // assert_eq!(291, res);
     }
@@ -164,9 +453,67 @@ Player 2:
     #[test]
     fn test_part2() {
         let iter = read_file();
-        let (mut deck1, mut deck2) = parse_decks(iter);
-        let (_, res) = recursive_combat(&mut deck1, &mut deck2);
+        let (mut deck1, mut deck2) = parse_decks(iter).unwrap();
+        let (_, res) = recursive_combat(&mut deck1, &mut deck2).unwrap();
         // println!("{}", res);
         assert_eq!(31587, res);
     }
+
+    #[test]
+    fn test_budget() {
+        let (mut deck1, mut deck2) = parse_decks(read_file()).unwrap();
+        let mut budget = Budget::time_limit(Duration::ZERO);
+        let res = recursive_combat_within(&mut deck1, &mut deck2, &mut budget);
+        assert!(matches!(res, Err(Interrupted::TimedOut(_))));
+
+        let (mut deck1, mut deck2) = decks(3, 10);
+        let mut budget = Budget::time_limit(Duration::from_secs(60));
+        let res = recursive_combat_within(&mut deck1.clone(), &mut deck2.clone(), &mut budget);
+        assert_eq!(res, Ok(recursive_combat(&mut deck1, &mut deck2)));
+    }
+
+    #[test]
+    fn test_trace() {
+        let (mut deck1, mut deck2) = parse_decks(lines(&read_fixture("day22.txt"))).unwrap();
+        let mut events = Vec::new();
+        let res = recursive_combat_traced(
+            &mut deck1,
+            &mut deck2,
+            &mut Trace::to(|event| events.push(event)),
+        );
+        assert_eq!(res, Some((2, 291)));
+        let games = events.iter().filter(|event| event.step == "game").count();
+        assert_eq!(games, 5);
+        let sub_game = events
+            .iter()
+            .position(|event| event.step == "sub_game")
+            .unwrap();
+        assert_eq!(
+            events[sub_game].to_string(),
+            "sub_game game=1 round=9 sub_game=2"
+        );
+        assert_eq!(
+            events[sub_game + 1].to_string(),
+            "game game=2 deck1=[9,8,5,2] deck2=[10,1,7]"
+        );
+        assert_eq!(
+            events.last(),
+            Some(
+                &Event::new("winner")
+                    .with("game", 1)
+                    .with("player", 2)
+                    .with("rounds", 17)
+                    .with("score", 291)
+            )
+        );
+
+        let (mut deck1, mut deck2) = parse_decks(lines(&read_fixture("day22-2.txt"))).unwrap();
+        let mut events = Vec::new();
+        recursive_combat_traced(
+            &mut deck1,
+            &mut deck2,
+            &mut Trace::to(|event| events.push(event)),
+        );
+        assert!(events.iter().any(|event| event.step == "repeat"));
+    }
 }
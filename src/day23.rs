@@ -1,18 +1,55 @@
+use crate::budget::{Budget, Interrupted};
+use crate::error::ParseError;
+use crate::input::read_day;
+use crate::solution::Solution;
 use itertools::Itertools;
 use std::collections::VecDeque;
 use std::iter::FromIterator;
 
-fn parse_input(input: &String) -> Vec<usize> {
-    input
-        .chars()
-        .map(|c| c.to_digit(10).unwrap() as usize)
-        .collect_vec()
+pub fn read_file() -> Result<Vec<usize>, ParseError> {
+    parse_input(read_day(Day23::DAY).trim())
 }
 
-/// Part 1
+/// Parses the cup labels, which must be the numbers from 1 to the number of cups, each used once, on a single line.
+pub fn parse_input(input: &str) -> Result<Vec<usize>, ParseError> {
+    if input.contains('\n') {
+        return Err(ParseError::new(
+            Day23::DAY,
+            2,
+            1,
+            "Expected a single line of cup labels",
+        ));
+    }
+    let mut cups = Vec::new();
+    for (i, c) in input.chars().enumerate() {
+        let error = |message: String| ParseError::new(Day23::DAY, 1, i + 1, message);
+        let label = c
+            .to_digit(10)
+            .ok_or_else(|| error(format!("Unexpected character: '{}'", c)))?
+            as usize;
+        if label == 0 || label > input.len() {
+            return Err(error(format!("Expected a label from 1 to {}", input.len())));
+        }
+        if cups.contains(&label) {
+            return Err(error(format!("Duplicate label: {}", label)));
+        }
+        cups.push(label);
+    }
+    if cups.len() < 4 {
+        return Err(ParseError::new(
+            Day23::DAY,
+            1,
+            1,
+            "Expected at least 4 cups",
+        ));
+    }
+    Ok(cups)
+}
+
+// Part 1
 
-fn play_cups(input: &Vec<usize>, moves: usize) -> String {
-    let mut cups = Vec::from_iter(input.iter().map(|d| *d));
+pub fn play_cups(input: &[usize], moves: usize) -> String {
+    let mut cups = Vec::from_iter(input.iter().copied());
     let mut current_index = 0;
     let mut q = VecDeque::with_capacity(3);
 
@@ -29,7 +66,7 @@ fn play_cups(input: &Vec<usize>, moves: usize) -> String {
         let mut dest_value = current_value - 1;
         while !cups.contains(&dest_value) {
             if dest_value == 0 {
-                dest_value = input.len() as usize;
+                dest_value = input.len();
             } else {
                 dest_value -= 1
             }
@@ -57,8 +94,18 @@ fn play_cups(input: &Vec<usize>, moves: usize) -> String {
     res
 }
 
-/// Part 2
-fn play_cups_faster(input: &Vec<usize>, moves: usize) -> usize {
+// Part 2
+pub fn play_cups_faster(input: &[usize], moves: usize) -> usize {
+    play_cups_faster_within(input, moves, &mut Budget::unlimited())
+        .expect("An unlimited budget never runs out")
+}
+
+/// Same as `play_cups_faster`, stopping if the budget runs out. Each move is a step.
+pub fn play_cups_faster_within(
+    input: &[usize],
+    moves: usize,
+    budget: &mut Budget,
+) -> Result<usize, Interrupted> {
     // For each cup, the value of the next cup in the circular list.
     // The value is zero if the cup as been removed from the list.
     let mut next = vec![0; input.len() + 1];
@@ -98,26 +145,77 @@ fn play_cups_faster(input: &Vec<usize>, moves: usize) -> usize {
         }
 
         current_cup = next[current_cup];
+        budget.tick(Some(moves as u64))?;
     }
 
     let v1 = next[1];
     let v2 = next[v1];
-    v1 * v2
+    Ok(v1 * v2)
 }
 
-fn add_cups(cups: &mut Vec<usize>, n: usize) {
-    for i in (cups.len() as usize)..n {
+pub fn add_cups(cups: &mut Vec<usize>, n: usize) {
+    for i in cups.len()..n {
         cups.push(i + 1);
     }
 }
 
+pub struct Day23;
+
+impl Solution for Day23 {
+    const DAY: u8 = 23;
+    type Input = Vec<usize>;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_input(input.trim())
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        Some(play_cups(input, 100))
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        let mut cups = input.clone();
+        add_cups(&mut cups, 1000000);
+        Some(play_cups_faster(&cups, 10000000).to_string())
+    }
+
+    fn solve_within(
+        &self,
+        part: u8,
+        input: &Self::Input,
+        budget: &mut Budget,
+    ) -> Result<Option<String>, Interrupted> {
+        match part {
+            1 => Ok(self.part1(input)),
+            2 => {
+                let mut cups = input.clone();
+                add_cups(&mut cups, 1000000);
+                play_cups_faster_within(&cups, 10000000, budget).map(|res| Some(res.to_string()))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::day23::{add_cups, parse_input, play_cups, play_cups_faster};
+    use crate::budget::{Budget, CancelToken, Interrupted};
+    use crate::day23::{
+        add_cups, parse_input, play_cups, play_cups_faster, play_cups_faster_within, read_file,
+        Day23,
+    };
+    use crate::error::ParseError;
+    use crate::solution::Solution;
+    use itertools::Itertools;
+    use proptest::prelude::*;
+
+    fn cups() -> impl Strategy<Value = Vec<usize>> {
+        (4..=9usize).prop_flat_map(|n| Just((1..=n).collect_vec()).prop_shuffle())
+    }
 
     #[test]
     fn test_part1_example() {
-        let input = parse_input(&"389125467".to_string());
+        let input = parse_input("389125467").unwrap();
         let res = play_cups(&input, 10);
         assert_eq!(res, "92658374");
 
@@ -127,15 +225,28 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        let input = parse_input(&"685974213".to_string());
+        let input = read_file().unwrap();
         let res = play_cups(&input, 100);
         println!("{}", res);
         assert_eq!(res, "82635947");
     }
 
+    proptest! {
+        #[test]
+        fn implementations_agree(cups in cups(), moves in 0..300usize) {
+            let labels = play_cups(&cups, moves);
+            let mut sorted = labels.chars().sorted().collect::<String>();
+            sorted.insert(0, '1');
+            prop_assert_eq!(sorted, (1..=cups.len()).join(""));
+
+            let digits = labels.chars().map(|c| c.to_digit(10).unwrap() as usize).collect_vec();
+            prop_assert_eq!(digits[0] * digits[1], play_cups_faster(&cups, moves));
+        }
+    }
+
     #[test]
     fn test_part2_example1() {
-        let input = parse_input(&"389125467".to_string());
+        let input = parse_input("389125467").unwrap();
         let res = play_cups_faster(&input, 10);
         assert_eq!(res, 18);
 
@@ -145,7 +256,7 @@ mod tests {
 
     #[test]
     fn test_part2_example2() {
-        let mut input = parse_input(&"389125467".to_string());
+        let mut input = parse_input("389125467").unwrap();
         add_cups(&mut input, 1000000);
         let res = play_cups_faster(&input, 10000000);
         assert_eq!(res, 149245887792);
@@ -153,10 +264,52 @@ mod tests {
 
     #[test]
     fn test_part2() {
-        let mut input = parse_input(&"685974213".to_string());
+        let mut input = read_file().unwrap();
         add_cups(&mut input, 1000000);
         let res = play_cups_faster(&input, 10000000);
         println!("{}", res);
         assert_eq!(res, 157047826689);
     }
+
+    #[test]
+    fn test_cancel() {
+        let token = CancelToken::new();
+        let mut budget = Budget::unlimited()
+            .with_token(token.clone())
+            .with_progress(|progress| {
+                if progress.done >= 3 * progress.total.unwrap() / 10 {
+                    token.cancel()
+                }
+            });
+        let mut cups = vec![3, 8, 9, 1, 2, 5, 4, 6, 7];
+        add_cups(&mut cups, 1000000);
+        let res = play_cups_faster_within(&cups, 1000000, &mut budget);
+        match res {
+            Err(Interrupted::Cancelled(progress)) => {
+                assert!(progress.done >= 300000 && progress.done < 400000)
+            }
+            res => panic!("Expected the game to be cancelled, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Day23.parse("1\n2\n").unwrap_err(),
+            ParseError::new(23, 2, 1, "Expected a single line of cup labels")
+        );
+        assert_eq!(
+            parse_input("38912x467").unwrap_err(),
+            ParseError::new(23, 1, 6, "Unexpected character: 'x'")
+        );
+        assert_eq!(
+            parse_input("389125469").unwrap_err(),
+            ParseError::new(23, 1, 9, "Duplicate label: 9")
+        );
+        assert_eq!(
+            parse_input("123").unwrap_err(),
+            ParseError::new(23, 1, 1, "Expected at least 4 cups")
+        );
+        assert!(Day23.parse("389125467\n").is_ok());
+    }
 }
@@ -1,180 +1,224 @@
+use crate::automaton::{life, Automaton, Hex};
+use crate::error::ParseError;
+use crate::grid::Grid;
+use crate::input::read_day_lines;
+use crate::solution::{lines, Solution};
+use itertools::Itertools;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
 
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day24.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap().to_string())
+pub fn read_file() -> impl Iterator<Item = String> {
+    read_day_lines(Day24::DAY)
 }
 
-type Coord = (i64, i64);
+pub type Coord = (i64, i64);
 
-fn flip_tiles(iter: impl Iterator<Item = String>) -> HashMap<Coord, u64> {
+pub fn parse_coords(iter: impl Iterator<Item = String>) -> Result<Vec<Coord>, ParseError> {
+    iter.enumerate()
+        .map(|(i, line)| coord_for_line(&line).map_err(|e| e.on_line(i + 1)))
+        .collect()
+}
+
+pub fn flip_tiles(coords: &[Coord]) -> HashMap<Coord, u64> {
     let mut tiles: HashMap<Coord, u64> = HashMap::new();
-    for line in iter {
-        let coord = coord_for_line(line);
-        if let Some(v) = tiles.get(&coord) {
+    for coord in coords {
+        if let Some(v) = tiles.get(coord) {
             let v = *v;
-            tiles.insert(coord, (v + 1) % 2);
+            tiles.insert(*coord, (v + 1) % 2);
         } else {
-            tiles.insert(coord, 1);
+            tiles.insert(*coord, 1);
         }
     }
     tiles
 }
 
-fn part1(iter: impl Iterator<Item = String>) -> u64 {
-    let tiles = flip_tiles(iter);
+pub fn part1(coords: &[Coord]) -> u64 {
+    let tiles = flip_tiles(coords);
     tiles.values().sum()
 }
 
-fn coord_for_line(line: String) -> Coord {
+pub fn coord_for_line(line: &str) -> Result<Coord, ParseError> {
     let mut ref_x: i64 = 0;
     let mut ref_y: i64 = 0;
-    let mut chars = line.chars();
-    while let Some(c1) = chars.next() {
-        match c1 {
-            'e' => ref_x += 2,
-            'w' => ref_x -= 2,
-            's' => match chars.next().unwrap() {
-                'e' => {
-                    ref_x += 1;
-                    ref_y += 1;
-                }
-                'w' => {
-                    ref_x -= 1;
-                    ref_y += 1;
-                }
-                _ => {}
-            },
-            'n' => match chars.next().unwrap() {
-                'e' => {
-                    ref_x += 1;
-                    ref_y -= 1;
+    let mut chars = line.chars().enumerate();
+    while let Some((i, c1)) = chars.next() {
+        let (dx, dy) = match c1 {
+            'e' => (2, 0),
+            'w' => (-2, 0),
+            's' | 'n' => {
+                let dy = if c1 == 's' { 1 } else { -1 };
+                match chars.next() {
+                    Some((_, 'e')) => (1, dy),
+                    Some((_, 'w')) => (-1, dy),
+                    _ => {
+                        return Err(ParseError::new(
+                            Day24::DAY,
+                            1,
+                            i + 2,
+                            format!("Expected 'e' or 'w' after '{}'", c1),
+                        ))
+                    }
                 }
-                'w' => {
-                    ref_x -= 1;
-                    ref_y -= 1;
-                }
-                _ => {}
-            },
-            _ => {}
-        }
+            }
+            _ => {
+                return Err(ParseError::new(
+                    Day24::DAY,
+                    1,
+                    i + 1,
+                    format!("Unexpected direction: '{}'", c1),
+                ))
+            }
+        };
+        ref_x += dx;
+        ref_y += dy;
     }
-    (ref_x, ref_y)
+    Ok((ref_x, ref_y))
 }
 
-const ADJ_TILES: [Coord; 6] = [(-2, 0), (2, 0), (-1, -1), (-1, 1), (1, 1), (1, -1)];
-
-/// Adds any missing white tiles around a black tile.
-fn expand(tiles: &mut HashMap<Coord, u64>) {
-    let mut changes = HashMap::new();
-    for ((x, y), v) in tiles.iter() {
-        if *v == 1 {
-            for (xd, yd) in ADJ_TILES.iter() {
-                let coord = (*x + *xd, *y + *yd);
-                if !tiles.contains_key(&coord) {
-                    changes.insert(coord, 0);
-                }
-            }
-        }
-    }
-    for (c, v) in changes.iter() {
-        tiles.insert(*c, *v);
+/// Every day, a black tile with zero or more than two black neighbours turns white and a white tile with exactly two
+/// black neighbours turns black.
+pub fn part2(coords: &[Coord], turns: usize) -> u64 {
+    simulate_floor(coords, turns, |_| {})
+}
+
+/// Runs the daily flips for `turns` days and counts the black tiles. `observe` is called with the tiles flipped by
+/// the instructions and after every day.
+pub fn simulate_floor(
+    coords: &[Coord],
+    turns: usize,
+    mut observe: impl FnMut(&Automaton<Hex, bool>),
+) -> u64 {
+    let tiles = flip_tiles(coords)
+        .into_iter()
+        .map(|(coord, v)| (coord, v == 1));
+    let mut automaton = Automaton::new(Hex, tiles);
+    observe(&automaton);
+    for _ in 0..turns {
+        automaton.step(life(&[2], &[1, 2]));
+        observe(&automaton);
     }
+    automaton.count(true) as u64
 }
 
-fn part2(iter: impl Iterator<Item = String>, turns: usize) -> u64 {
-    let mut tiles = flip_tiles(iter);
-    let mut changes = HashMap::new();
+/// The black tiles flipped by the instructions, then the black tiles after each of `turns` days.
+pub fn floor_days(coords: &[Coord], turns: usize) -> Vec<Vec<Coord>> {
+    let mut res = Vec::new();
+    simulate_floor(coords, turns, |automaton| {
+        res.push(automaton.cells().map(|(coord, _)| coord).collect())
+    });
+    res
+}
 
-    for _ in 0..turns {
-        expand(&mut tiles);
-
-        for (&coord, v) in tiles.iter() {
-            let (x, y) = coord;
-            let adj_black: u64 = ADJ_TILES
-                .iter()
-                .map(|&(xd, yd)| tiles.get(&(x + xd, y + yd)).map(|v| *v).unwrap_or(0))
-                .sum();
-
-            if *v == 1 && (adj_black == 0 || adj_black > 2) {
-                changes.insert(coord, 0);
-            } else if *v == 0 && adj_black == 2 {
-                changes.insert(coord, 1);
+/// Draws each floor with the same bounds, where a tile covers the cell at its doubled coordinates and the cell to the
+/// right of it. Every other row is shifted by one cell, like a brick wall, which keeps all six neighbours touching.
+pub fn floor_frames(days: &[Vec<Coord>]) -> Vec<Grid<bool>> {
+    let coords = days.iter().flatten();
+    let (min_x, max_x) = coords
+        .clone()
+        .map(|(x, _)| *x)
+        .minmax()
+        .into_option()
+        .unwrap_or((0, 0));
+    let (min_y, max_y) = coords
+        .map(|(_, y)| *y)
+        .minmax()
+        .into_option()
+        .unwrap_or((0, 0));
+    let height = (max_y - min_y + 1) as usize;
+    let width = (max_x - min_x + 2) as usize;
+    days.iter()
+        .map(|black| {
+            let mut frame = Grid::filled(height, width, false);
+            for (x, y) in black {
+                let (r, c) = ((y - min_y) as usize, (x - min_x) as usize);
+                frame[(r, c)] = true;
+                frame[(r, c + 1)] = true;
             }
-        }
+            frame
+        })
+        .collect()
+}
 
-        for (c, v) in changes.iter() {
-            tiles.insert(*c, *v);
-        }
+pub struct Day24;
+
+impl Solution for Day24 {
+    const DAY: u8 = 24;
+    type Input = Vec<Coord>;
 
-        changes.clear();
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_coords(lines(input))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        Some(part1(input).to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        Some(part2(input, 100).to_string())
     }
-    tiles.values().sum()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day24::{coord_for_line, part1, part2, read_file};
-
-    const EXAMPLE: &str = "sesenwnenenewseeswwswswwnenewsewsw
-neeenesenwnwwswnenewnwwsewnenwsesweswv
-seswneswswsenwwnwse
-nwnwneseeswswnenewneswwnewseswneseene
-swweswneswnenwsewnwneneseenw
-eesenwseswswnenwswnwnwsewwnwsene
-sewnenenenesenwsewnenwwwse
-wenwwweseeeweswwwnwwe
-wsweesenenewnwwnwsenewsenwwsesesenwne
-neeswseenwwswnwswswnw
-nenwswwsewswnenenewsenwsenwnesesenew
-enewnwewneswsewnwswenweswnenwsenwsw
-sweneswneswneneenwnewenewwneswswnese
-swwesenesewenwneswnwwneseswwne
-enesenwswwswneneswsenwnewswseenwsese
-wnwnesenesenenwwnenwsewesewsesesew
-nenewswnwewswnenesenwnesewesw
-eneswnwswnwsenenwnwnwwseeswneewsenese
-neswnwewnwnwseenwseesewsenwsweewe
-wseweeenwnesenwwwswnew
-";
+    use crate::day24::{coord_for_line, parse_coords, part1, part2, read_file};
+    use crate::error::ParseError;
+    use crate::input::read_fixture;
+    use crate::solution::lines;
 
     #[test]
     fn test_part1_example() {
-        assert_eq!((0, 0), coord_for_line("nwwswee".to_string()));
+        assert_eq!(Ok((0, 0)), coord_for_line("nwwswee"));
 
-        let res = part1(EXAMPLE.lines().map(|l| l.to_string()));
+        let coords = parse_coords(lines(&read_fixture("day24.txt"))).unwrap();
+        let res = part1(&coords);
         assert_eq!(res, 10);
     }
 
     #[test]
     fn test_part1() {
-        let res = part1(read_file());
+        let coords = parse_coords(read_file()).unwrap();
+        let res = part1(&coords);
         println!("{}", res);
         assert_eq!(394, res);
     }
 
     #[test]
     fn test_part2_example() {
-        assert_eq!(part2(EXAMPLE.lines().map(|l| l.to_string()), 1), 15);
-        assert_eq!(part2(EXAMPLE.lines().map(|l| l.to_string()), 2), 12);
-        assert_eq!(part2(EXAMPLE.lines().map(|l| l.to_string()), 3), 25);
-        assert_eq!(part2(EXAMPLE.lines().map(|l| l.to_string()), 4), 14);
-        assert_eq!(part2(EXAMPLE.lines().map(|l| l.to_string()), 5), 23);
-        assert_eq!(part2(EXAMPLE.lines().map(|l| l.to_string()), 6), 28);
-        assert_eq!(part2(EXAMPLE.lines().map(|l| l.to_string()), 7), 41);
-        assert_eq!(part2(EXAMPLE.lines().map(|l| l.to_string()), 8), 37);
-        assert_eq!(part2(EXAMPLE.lines().map(|l| l.to_string()), 9), 49);
-        assert_eq!(part2(EXAMPLE.lines().map(|l| l.to_string()), 10), 37);
-        assert_eq!(part2(EXAMPLE.lines().map(|l| l.to_string()), 100), 2208);
+        let coords = parse_coords(lines(&read_fixture("day24.txt"))).unwrap();
+        assert_eq!(part2(&coords, 1), 15);
+        assert_eq!(part2(&coords, 2), 12);
+        assert_eq!(part2(&coords, 3), 25);
+        assert_eq!(part2(&coords, 4), 14);
+        assert_eq!(part2(&coords, 5), 23);
+        assert_eq!(part2(&coords, 6), 28);
+        assert_eq!(part2(&coords, 7), 41);
+        assert_eq!(part2(&coords, 8), 37);
+        assert_eq!(part2(&coords, 9), 49);
+        assert_eq!(part2(&coords, 10), 37);
+        assert_eq!(part2(&coords, 100), 2208);
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(read_file(), 100);
+        let coords = parse_coords(read_file()).unwrap();
+        let res = part2(&coords, 100);
         println!("{}", res);
         assert_eq!(4036, res);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |s: &str| parse_coords(s.lines().map(|l| l.to_string()));
+        assert_eq!(
+            parse("esew\nnwwsx").unwrap_err(),
+            ParseError::new(24, 2, 5, "Expected 'e' or 'w' after 's'")
+        );
+        assert_eq!(
+            parse("esew\nnwwswee\nenv").unwrap_err(),
+            ParseError::new(24, 3, 3, "Expected 'e' or 'w' after 'n'")
+        );
+        assert_eq!(
+            coord_for_line("eeq").unwrap_err(),
+            ParseError::new(24, 1, 3, "Unexpected direction: 'q'")
+        );
+    }
 }
@@ -1,47 +1,126 @@
+use crate::budget::{Budget, Interrupted};
+use crate::error::{parse_number, ParseError};
+use crate::input::read_day_lines;
+use crate::solution::{lines, Solution};
 use itertools::Itertools;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
-use std::str::FromStr;
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day25.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap().to_string())
+
+pub fn read_file() -> impl Iterator<Item = String> {
+    read_day_lines(Day25::DAY)
 }
 
-fn parse_keys(iter: impl Iterator<Item = String>) -> (u64, u64) {
-    iter.map(|line| u64::from_str(&line).unwrap())
+/// The keys are transformed modulo this number, so a key is always below it.
+pub const MODULUS: u64 = 20201227;
+
+pub fn parse_keys(iter: impl Iterator<Item = String>) -> Result<(u64, u64), ParseError> {
+    let keys = iter
+        .enumerate()
+        .map(
+            |(i, line)| match parse_number(Day25::DAY, i + 1, &line, &line)? {
+                key if key >= MODULUS => Err(ParseError::new(
+                    Day25::DAY,
+                    i + 1,
+                    1,
+                    format!("Expected a key below {}", MODULUS),
+                )),
+                key => Ok(key),
+            },
+        )
+        .collect::<Result<Vec<u64>, _>>()?;
+    keys.into_iter()
         .collect_tuple()
-        .unwrap()
+        .ok_or_else(|| ParseError::new(Day25::DAY, 1, 1, "Expected exactly two public keys"))
 }
 
-fn transform(subject_number: u64, loop_size: usize) -> impl Iterator<Item = (u64, usize)> {
+pub fn transform(subject_number: u64, loop_size: usize) -> impl Iterator<Item = (u64, usize)> {
     (1..(loop_size + 1)).scan(1, move |value, i| {
         *value *= subject_number;
-        *value %= 20201227;
+        *value %= MODULUS;
         Some((*value, i))
     })
 }
 
-fn find_encryption_key(door_key: u64, card_key: u64, loops: usize) -> u64 {
-    let (other_key, loop_size) = transform(7, loops)
-        .find_map(|(key, loop_size)| {
-            if key == door_key {
-                Some((card_key, loop_size))
-            } else if key == card_key {
-                Some((door_key, loop_size))
-            } else {
-                None
-            }
-        })
-        .unwrap();
-
-    transform(other_key, loop_size).last().unwrap().0
+/// Returns `None` if neither key has a loop size up to `loops`.
+pub fn find_encryption_key(door_key: u64, card_key: u64, loops: usize) -> Option<u64> {
+    find_encryption_key_within(door_key, card_key, loops, &mut Budget::unlimited())
+        .expect("An unlimited budget never runs out")
+}
+
+/// Same as `find_encryption_key`, stopping if the budget runs out. Each loop of a transform is a step. Returns `None`
+/// if neither key has a loop size up to `loops`.
+pub fn find_encryption_key_within(
+    door_key: u64,
+    card_key: u64,
+    loops: usize,
+    budget: &mut Budget,
+) -> Result<Option<u64>, Interrupted> {
+    let mut found = None;
+    for (key, loop_size) in transform(7, loops) {
+        if key == door_key {
+            found = Some((card_key, loop_size));
+            break;
+        } else if key == card_key {
+            found = Some((door_key, loop_size));
+            break;
+        }
+        budget.tick(None)?;
+    }
+    let (other_key, loop_size) = match found {
+        Some(found) => found,
+        None => return Ok(None),
+    };
+
+    let mut key = None;
+    for (value, _) in transform(other_key, loop_size) {
+        key = Some(value);
+        budget.tick(None)?;
+    }
+    Ok(key)
+}
+
+pub struct Day25;
+
+impl Solution for Day25 {
+    const DAY: u8 = 25;
+    type Input = (u64, u64);
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_keys(lines(input))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        let (door_key, card_key) = *input;
+        find_encryption_key(door_key, card_key, 10000000).map(|key| key.to_string())
+    }
+
+    fn part2(&self, _input: &Self::Input) -> Option<String> {
+        // Day 25 only has one puzzle.
+        None
+    }
+
+    fn solve_within(
+        &self,
+        part: u8,
+        input: &Self::Input,
+        budget: &mut Budget,
+    ) -> Result<Option<String>, Interrupted> {
+        let (door_key, card_key) = *input;
+        match part {
+            1 => find_encryption_key_within(door_key, card_key, 10000000, budget)
+                .map(|key| key.map(|key| key.to_string())),
+            _ => Ok(None),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::day25::{find_encryption_key, parse_keys, read_file, transform};
+    use crate::budget::{Budget, Interrupted};
+    use crate::day25::{
+        find_encryption_key, find_encryption_key_within, parse_keys, read_file, transform, Day25,
+    };
+    use crate::error::ParseError;
+    use crate::solution::{lines, Solution};
+    use std::time::Duration;
 
     #[test]
     fn test_part1_example() {
@@ -49,14 +128,54 @@ mod tests {
         assert_eq!(17807724, transform(7, 11).last().unwrap().0);
         assert_eq!(14897079, transform(5764801, 11).last().unwrap().0);
         assert_eq!(14897079, transform(17807724, 8).last().unwrap().0);
-        assert_eq!(14897079, find_encryption_key(17807724, 5764801, 20));
+        assert_eq!(Some(14897079), find_encryption_key(17807724, 5764801, 20));
     }
 
     #[test]
     fn test_part1() {
-        let (door_key, card_key) = parse_keys(read_file());
+        let (door_key, card_key) = parse_keys(read_file()).unwrap();
         let res = find_encryption_key(door_key, card_key, 10000000);
-        println!("{}", res);
-        assert_eq!(, res);
+        println!("{:?}", res);
+        assert_eq!(Some(9420461), res);
+    }
+
+    #[test]
+    fn test_no_loop_size() {
+        // Transforming 7 never gives 0 and takes more than 10,000,000 loops to give 1 or 2.
+        for keys in [(1, 1), (0, 1), (0, 0), (1, 2)] {
+            assert_eq!(Day25.part1(&keys), None);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_keys(lines("18446744073709551615\n49\n")).unwrap_err(),
+            ParseError::new(25, 1, 1, "Expected a key below 20201227")
+        );
+        assert_eq!(
+            parse_keys(lines("5764801\n20201227\n")).unwrap_err(),
+            ParseError::new(25, 2, 1, "Expected a key below 20201227")
+        );
+        assert_eq!(
+            parse_keys(lines("5764801\n20201226\n")),
+            Ok((5764801, 20201226))
+        );
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut budget = Budget::unlimited();
+        let res = find_encryption_key_within(17807724, 5764801, 20, &mut budget);
+        assert_eq!(res, Ok(Some(14897079)));
+        // The card's loop size of 8 is found first, then the door's key is transformed 8 times.
+        assert_eq!(budget.done(), 7 + 8);
+        let res = find_encryption_key_within(17807724, 5764801, 5, &mut budget);
+        assert_eq!(res, Ok(None));
+
+        // No loop size up to 10,000,000 gives a key of 1, so the search runs until the budget does.
+        let mut budget = Budget::time_limit(Duration::ZERO);
+        let res = find_encryption_key_within(1, 1, 10000000, &mut budget);
+        assert!(matches!(res, Err(Interrupted::TimedOut(_))));
     }
 }
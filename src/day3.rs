@@ -1,73 +1,84 @@
-use itertools::Itertools;
-use std::fs::File;
-use std::io::prelude::*;
-
-fn read_grid() -> Vec<Vec<char>> {
-    let mut file = File::open("input/day3.txt").unwrap();
-    let mut str = String::new();
-    file.read_to_string(&mut str).unwrap();
-    read_grid_from_string(&str)
-}
+use crate::error::ParseError;
+use crate::grid::Grid;
+use crate::input::read_day;
+use crate::solution::Solution;
 
-fn read_grid_from_string(str: &String) -> Vec<Vec<char>> {
-    str.lines()
-        .map(|line| line.chars().collect_vec())
-        .collect_vec()
+pub fn read_grid() -> Result<Grid<bool>, ParseError> {
+    read_grid_from_string(&read_day(Day3::DAY))
 }
 
-// fn print_grid(grid: &Vec<Vec<char>>) {
-//     for line in grid {
-//         println!("{}", String::from_iter(line.iter()));
-//     }
-// }
+/// Parses the map of open squares (.) and trees (#), which are `true` in the grid.
+pub fn read_grid_from_string(str: &str) -> Result<Grid<bool>, ParseError> {
+    Grid::parse(Day3::DAY, str, |c| match c {
+        '.' => Some(false),
+        '#' => Some(true),
+        _ => None,
+    })
+}
 
-fn count_trees_on_path(grid: &Vec<Vec<char>>, x_velocity: usize, y_velocity: usize) -> u64 {
+pub fn count_trees_on_path(grid: &Grid<bool>, x_velocity: usize, y_velocity: usize) -> u64 {
     let (count, _) = grid
-        .iter()
+        .rows()
         .step_by(y_velocity)
         .fold((0, 0), |(count, pos_x), row| {
-            let new_count = count + (if row[pos_x] == '#' { 1 } else { 0 });
+            let new_count = count + (if row[pos_x] { 1 } else { 0 });
             let new_pos_x = (pos_x + x_velocity) % row.len();
             (new_count, new_pos_x)
         });
     count
 }
 
+pub fn count_trees_on_slopes(grid: &Grid<bool>, slopes: &[(usize, usize)]) -> u64 {
+    slopes
+        .iter()
+        .map(|(x_velocity, y_velocity)| count_trees_on_path(grid, *x_velocity, *y_velocity))
+        .product()
+}
+
+pub struct Day3;
+
+impl Solution for Day3 {
+    const DAY: u8 = 3;
+    type Input = Grid<bool>;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        read_grid_from_string(input)
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        Some(count_trees_on_path(input, 3, 1).to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        let slopes = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+        Some(count_trees_on_slopes(input, &slopes).to_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::day3::{count_trees_on_path, read_grid, read_grid_from_string};
-
-    const EXAMPLE_GRID: &str = "
-..##.......
-#...#...#..
-.#....#..#.
-..#.#...#.#
-.#...##..#.
-..#.##.....
-.#.#.#....#
-.#........#
-#.##...#...
-#...##....#
-.#..#...#.#
-";
+    use crate::day3::{
+        count_trees_on_path, count_trees_on_slopes, read_grid, read_grid_from_string,
+    };
+    use crate::input::read_fixture;
 
     #[test]
     fn part1_example() {
-        let grid = read_grid_from_string(&EXAMPLE_GRID[1..].to_string());
+        let grid = read_grid_from_string(&read_fixture("day3.txt")).unwrap();
         let count = count_trees_on_path(&grid, 3, 1);
         println!("{}", count);
     }
 
     #[test]
     fn part1() {
-        let grid = read_grid();
+        let grid = read_grid().unwrap();
         let count = count_trees_on_path(&grid, 3, 1);
         println!("{}", count);
     }
 
     #[test]
     fn part2_example() {
-        let grid = read_grid_from_string(&EXAMPLE_GRID[1..].to_string());
+        let grid = read_grid_from_string(&read_fixture("day3.txt")).unwrap();
         let slopes = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
 
         let res: u64 = slopes.iter().fold(1, |res, (x_velocity, y_velocity)| {
@@ -80,12 +91,10 @@ mod tests {
 
     #[test]
     fn part2() {
-        let grid = read_grid();
+        let grid = read_grid().unwrap();
         let slopes = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
 
-        let res: u64 = slopes.iter().fold(1, |res, (x_velocity, y_velocity)| {
-            res * count_trees_on_path(&grid, *x_velocity, *y_velocity)
-        });
+        let res = count_trees_on_slopes(&grid, &slopes);
         println!("{}", res);
     }
 }
@@ -1,70 +1,85 @@
+use crate::error::ParseError;
+use crate::input::read_day;
+use crate::solution::Solution;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::prelude::*;
 use std::str::FromStr;
 
 const EYE_COLORS: [&str; 7] = ["amb", "blu", "brn", "grn", "gry", "hzl", "oth"];
 
-fn read_passports() -> Vec<Passport> {
-    let mut file = File::open("input/day4.txt").unwrap();
-    let mut str = String::new();
-    file.read_to_string(&mut str).unwrap();
-    read_passports_from_string(&str)
+pub fn read_passports() -> Result<Vec<Passport>, ParseError> {
+    read_passports_from_string(&read_day(Day4::DAY))
 }
 
-fn read_passports_from_string(str: &String) -> Vec<Passport> {
-    let vec = vec![Passport::new()];
-    str.lines()
-        .flat_map(|line| line.split(" ").into_iter())
-        .fold(vec, |mut acc, input| {
-            if input.is_empty() {
-                acc.push(Passport::new())
-            } else if let Some((key, value)) = input.split(":").collect_tuple() {
-                if let Some(passport) = acc.last_mut() {
-                    passport.fields.insert(key.to_string(), value.to_string());
-                }
+pub fn read_passports_from_string(str: &str) -> Result<Vec<Passport>, ParseError> {
+    let mut res = vec![Passport::new()];
+    for (i, line) in str.lines().enumerate() {
+        if line.is_empty() {
+            res.push(Passport::new());
+            continue;
+        }
+
+        for input in line.split(' ').filter(|input| !input.is_empty()) {
+            let (key, value) = input.split(':').collect_tuple().ok_or_else(|| {
+                ParseError::at(
+                    Day4::DAY,
+                    i + 1,
+                    line,
+                    input,
+                    "Expected a field of the form key:value",
+                )
+            })?;
+            if let Some(passport) = res.last_mut() {
+                passport.fields.insert(key.to_string(), value.to_string());
             }
-            acc
-        })
+        }
+    }
+    Ok(res)
 }
 
-#[derive(Debug)]
-struct Passport {
-    fields: HashMap<String, String>,
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Passport {
+    pub fields: HashMap<String, String>,
+}
+
+impl Default for Passport {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Passport {
-    fn new() -> Passport {
+    pub fn new() -> Passport {
         Passport {
             fields: HashMap::new(),
         }
     }
 
-    fn is_valid(&self) -> bool {
+    pub fn is_valid(&self) -> bool {
         self.is_valid_number_of_fields() && self.all_fields_valid()
     }
 
-    fn is_valid_number_of_fields(&self) -> bool {
+    pub fn is_valid_number_of_fields(&self) -> bool {
         let num_fields = self.fields.len();
         num_fields == 8 || (num_fields == 7 && !self.fields.contains_key("cid"))
     }
 
-    fn all_fields_valid(&self) -> bool {
+    pub fn all_fields_valid(&self) -> bool {
         self.fields
             .iter()
             .all(|(field, value)| Passport::is_valid_field(field, value))
     }
 
-    fn is_valid_field(field: &String, value: &String) -> bool {
-        match field.as_str() {
+    pub fn is_valid_field(field: &str, value: &str) -> bool {
+        match field {
             "byr" => is_valid_number(value, 1920, 2002),
             "iyr" => is_valid_number(value, 2010, 2020),
             "eyr" => is_valid_number(value, 2020, 2030),
             "hgt" if value.ends_with("cm") => is_valid_number(&value[..value.len() - 2], 150, 193),
             "hgt" if value.ends_with("in") => is_valid_number(&value[..value.len() - 2], 59, 76),
             "hcl" => value.starts_with("#") && is_valid_hex_number(&value[1..value.len()]),
-            "ecl" => EYE_COLORS.binary_search(&value.as_str()).is_ok(),
+            "ecl" => EYE_COLORS.binary_search(&value).is_ok(),
             "pid" => value.len() == 9 && value.chars().all(|c| c.is_ascii_digit()),
             "cid" => true,
             _ => false,
@@ -72,38 +87,47 @@ impl Passport {
     }
 }
 
-fn is_valid_number(value: &str, min: u16, max: u16) -> bool {
+pub fn is_valid_number(value: &str, min: u16, max: u16) -> bool {
     u16::from_str(value)
         .map(|v| v >= min && v <= max)
         .unwrap_or(false)
 }
 
-fn is_valid_hex_number(value: &str) -> bool {
+pub fn is_valid_hex_number(value: &str) -> bool {
     u64::from_str_radix(value, 16).is_ok()
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::day4::{read_passports, read_passports_from_string};
+pub struct Day4;
 
-    const EXAMPLE_PART1: &str = "
-ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
-byr:1937 iyr:2017 cid:147 hgt:183cm
+impl Solution for Day4 {
+    const DAY: u8 = 4;
+    type Input = Vec<Passport>;
 
-iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
-hcl:#cfa07d byr:1929
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        read_passports_from_string(input)
+    }
 
-hcl:#ae17e1 iyr:2013
-eyr:2024
-ecl:brn pid:760753108 byr:1931
-hgt:179cm
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        let count = input
+            .iter()
+            .filter(|p| p.is_valid_number_of_fields())
+            .count();
+        Some(count.to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        Some(input.iter().filter(|p| p.is_valid()).count().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day4::{read_passports, read_passports_from_string};
+    use crate::input::read_fixture;
 
-hcl:#cfa07d eyr:2025 pid:166559648
-iyr:2011 ecl:brn hgt:59in
-";
     #[test]
     fn part1_example() {
-        let passports = read_passports_from_string(&EXAMPLE_PART1[1..].to_string());
+        let passports = read_passports_from_string(&read_fixture("day4.txt")).unwrap();
         let count = passports.iter().filter(|p| (*p).is_valid()).count();
         println!("{}", count);
         assert_eq!(count, 2);
@@ -111,7 +135,7 @@ iyr:2011 ecl:brn hgt:59in
 
     #[test]
     fn part1() {
-        let passports = read_passports();
+        let passports = read_passports().unwrap();
         let count = passports
             .iter()
             .filter(|p| (*p).is_valid_number_of_fields())
@@ -120,48 +144,17 @@ iyr:2011 ecl:brn hgt:59in
         assert_eq!(count, 200);
     }
 
-    const EXAMPLE_PART2_INVALID: &str = "
-eyr:1972 cid:100
-hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926
-
-iyr:2019
-hcl:#602927 eyr:1967 hgt:170cm
-ecl:grn pid:012533040 byr:1946
-
-hcl:dab227 iyr:2012
-ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277
-
-hgt:59cm ecl:zzz
-eyr:2038 hcl:74454a iyr:2023
-pid:3556412378 byr:2007
-";
-
     #[test]
     fn part2_invalid() {
-        let passports = read_passports_from_string(&EXAMPLE_PART2_INVALID[1..].to_string());
+        let passports = read_passports_from_string(&read_fixture("day4-invalid.txt")).unwrap();
         let count = passports.iter().filter(|p| (*p).is_valid()).count();
         println!("{}", count);
         assert_eq!(count, 0);
     }
 
-    const EXAMPLE_PART2_VALID: &str = "
-pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980
-hcl:#623a2f
-
-eyr:2029 ecl:blu cid:129 byr:1989
-iyr:2014 pid:896056539 hcl:#a97842 hgt:165cm
-
-hcl:#888785
-hgt:164cm byr:2001 iyr:2015 cid:88
-pid:545766238 ecl:hzl
-eyr:2022
-
-iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719
-";
-
     #[test]
     fn part2_valid() {
-        let passports = read_passports_from_string(&EXAMPLE_PART2_VALID[1..].to_string());
+        let passports = read_passports_from_string(&read_fixture("day4-valid.txt")).unwrap();
         let count = passports.iter().filter(|p| (*p).is_valid()).count();
         println!("{}", count);
         assert_eq!(count, 4);
@@ -169,7 +162,7 @@ iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719
 
     #[test]
     fn part2() {
-        let passports = read_passports();
+        let passports = read_passports().unwrap();
         let count = passports.iter().filter(|p| (*p).is_valid()).count();
         println!("{}", count);
         assert_eq!(count, 116);
@@ -1,40 +1,65 @@
+use crate::error::ParseError;
+use crate::input::read_day_lines;
+use crate::solution::{lines, Solution};
 use itertools::Itertools;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
-
-///
-///  Initial version - Literally translates algorithm in the problem description.
-///
-
-fn read_file() -> Vec<String> {
-    let file = File::open("input/day5.txt").unwrap();
-    BufReader::new(file)
-        .lines()
-        .map(|line| line.unwrap().to_string())
-        .collect_vec()
+use serde::{Deserialize, Serialize};
+
+//
+//  Initial version - Literally translates algorithm in the problem description.
+//
+
+pub fn read_file() -> Vec<String> {
+    read_day_lines(Day5::DAY).collect_vec()
 }
 
-#[derive(Debug)]
-struct BoardingPass {
-    row: u16,
-    column: u16,
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BoardingPass {
+    pub row: u16,
+    pub column: u16,
 }
 
 impl BoardingPass {
-    fn seat(&self) -> u16 {
+    pub fn seat(&self) -> u16 {
         self.row * 8 + self.column
     }
 }
 
-fn decode_pass(pass: &String) -> BoardingPass {
+pub fn decode_pass(pass: &str) -> BoardingPass {
     let (row_codes, column_codes) = pass.split_at(7);
     let row = locate_position(128, row_codes, 'F');
     let column = locate_position(8, column_codes, 'L');
     BoardingPass { row, column }
 }
 
-fn locate_position(positions: u16, codes: &str, lower_code: char) -> u16 {
+/// Checks that each line is 7 row codes (F or B) followed by 3 column codes (L or R) before decoding it.
+pub fn parse_passes(iter: impl Iterator<Item = String>) -> Result<Vec<BoardingPass>, ParseError> {
+    iter.enumerate()
+        .map(|(i, line)| {
+            let bad_code = line.chars().enumerate().find(|(col, c)| match col {
+                0..=6 => *c != 'F' && *c != 'B',
+                7..=9 => *c != 'L' && *c != 'R',
+                _ => true,
+            });
+            match bad_code {
+                Some((col, c)) => Err(ParseError::new(
+                    Day5::DAY,
+                    i + 1,
+                    col + 1,
+                    format!("Unexpected code: '{}'", c),
+                )),
+                None if line.len() != 10 => Err(ParseError::new(
+                    Day5::DAY,
+                    i + 1,
+                    line.len() + 1,
+                    "Expected 10 codes",
+                )),
+                None => Ok(decode_pass(&line)),
+            }
+        })
+        .collect()
+}
+
+pub fn locate_position(positions: u16, codes: &str, lower_code: char) -> u16 {
     let mut min = 0;
     let mut max = positions;
     let (first_codes, last_code) = codes.split_at(codes.len() - 1);
@@ -49,42 +74,86 @@ fn locate_position(positions: u16, codes: &str, lower_code: char) -> u16 {
         }
     }
 
-    match last_code.chars().nth(0) {
+    match last_code.chars().next() {
         Some(c) if c == lower_code => min,
         _ => max - 1,
     }
 }
 
-///
-/// After the fact version. Maps letters directly to binary digits and lines to binary numbers.
-///
-/// Works entirely on iterators, but I believe the `lines` operator still does some dynamic memory allocation to
-/// create the `String` objects it returns.
-///
-
-fn read_file_v2() -> impl Iterator<Item = u16> {
-    let file = File::open("input/day5.txt").unwrap();
-    BufReader::new(file)
-        .lines()
-        .map(|line| line_to_seat(&mut line.unwrap().chars()))
+//
+// After the fact version. Maps letters directly to binary digits and lines to binary numbers.
+//
+// Works entirely on iterators, but I believe the `lines` operator still does some dynamic memory allocation to
+// create the `String` objects it returns.
+//
+
+pub fn read_file_v2() -> impl Iterator<Item = u16> {
+    read_day_lines(Day5::DAY).map(|line| line_to_seat(&mut line.chars()))
 }
 
-fn line_to_seat(line: &mut impl Iterator<Item = char>) -> u16 {
+pub fn line_to_seat(line: &mut impl Iterator<Item = char>) -> u16 {
     line.map(|c| if c == 'F' || c == 'L' { 0 } else { 1 })
         .fold(0, |acc, bit| (acc << 1) | bit)
 }
 
+/// Finds the missing seat whose neighbors on both sides are taken. Expects `seats` to be sorted.
+pub fn find_missing_seat(seats: &[u16]) -> Option<u16> {
+    seats
+        .iter()
+        .tuple_windows()
+        .find(|(s1, s2)| **s2 - **s1 == 2)
+        .map(|(s1, _)| *s1 + 1)
+}
+
+pub struct Day5;
+
+impl Solution for Day5 {
+    const DAY: u8 = 5;
+    type Input = Vec<BoardingPass>;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_passes(lines(input))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        input.iter().map(|p| p.seat()).max().map(|s| s.to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        let seats = input.iter().map(|p| p.seat()).sorted().collect_vec();
+        find_missing_seat(&seats).map(|s| s.to_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::day5::{decode_pass, line_to_seat, read_file, read_file_v2};
+    use crate::day5::{
+        decode_pass, find_missing_seat, line_to_seat, parse_passes, read_file, read_file_v2,
+    };
+    use crate::error::ParseError;
     use itertools::Itertools;
+    use proptest::prelude::*;
 
     #[test]
     fn part1_example() {
-        assert_eq!(decode_pass(&"FBFBBFFRLR".to_string()).seat(), 357);
-        assert_eq!(decode_pass(&"BFFFBBFRRR".to_string()).seat(), 567);
-        assert_eq!(decode_pass(&"FFFBBBFRRR".to_string()).seat(), 119);
-        assert_eq!(decode_pass(&"BBFFBBFRLL".to_string()).seat(), 820);
+        assert_eq!(decode_pass("FBFBBFFRLR").seat(), 357);
+        assert_eq!(decode_pass("BFFFBBFRRR").seat(), 567);
+        assert_eq!(decode_pass("FFFBBBFRRR").seat(), 119);
+        assert_eq!(decode_pass("BBFFBBFRLL").seat(), 820);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |s: &str| parse_passes(s.lines().map(|l| l.to_string()));
+        assert_eq!(parse("FBFBBFFRLR").unwrap()[0].seat(), 357);
+        assert_eq!(
+            parse("FBFBBFFRLR\nFBFBBFLRLR").unwrap_err(),
+            ParseError::new(5, 2, 7, "Unexpected code: 'L'")
+        );
+        assert_eq!(
+            parse("FBFBBFFRL").unwrap_err(),
+            ParseError::new(5, 1, 10, "Expected 10 codes")
+        );
     }
 
     #[test]
@@ -95,6 +164,15 @@ mod tests {
         assert_eq!(line_to_seat(&mut "BBFFBBFRLL".chars()), 820);
     }
 
+    proptest! {
+        #[test]
+        fn decoders_agree(pass in "[FB]{7}[LR]{3}") {
+            let decoded = decode_pass(&pass);
+            prop_assert!(decoded.row < 128 && decoded.column < 8);
+            prop_assert_eq!(decoded.seat(), line_to_seat(&mut pass.chars()));
+        }
+    }
+
     #[test]
     fn part1() {
         let passes = read_file();
@@ -143,5 +221,6 @@ mod tests {
             .unwrap();
         println!("{}", res);
         assert_eq!(*res, 657);
+        assert_eq!(find_missing_seat(&seats), Some(657));
     }
 }
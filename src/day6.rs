@@ -1,70 +1,85 @@
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day6.txt").unwrap();
-    BufReader::new(file)
-        .lines()
-        .map(|s| s.unwrap().to_string())
-        .chain("\n".lines().map(|l| l.to_string())) // Add an extra new line at the end.
+use crate::error::ParseError;
+use crate::input::read_day_lines;
+use crate::solution::{lines, Solution};
+
+pub fn read_file() -> impl Iterator<Item = String> {
+    read_day_lines(Day6::DAY).chain("\n".lines().map(|l| l.to_string())) // Add an extra new line at the end.
+}
+
+/// Checks that every answer is a lowercase letter, which `sum_answer_counts` relies on.
+pub fn parse_answers(iter: impl Iterator<Item = String>) -> Result<Vec<String>, ParseError> {
+    iter.enumerate()
+        .map(
+            |(i, line)| match line.chars().position(|c| !c.is_ascii_lowercase()) {
+                Some(col) => Err(ParseError::new(
+                    Day6::DAY,
+                    i + 1,
+                    col + 1,
+                    "Expected answers from 'a' to 'z'",
+                )),
+                None => Ok(line),
+            },
+        )
+        .collect()
 }
 
-fn sum_answer_counts(lines: impl Iterator<Item = String>, union: bool) -> u32 {
+pub fn sum_answer_counts(lines: impl Iterator<Item = String>, union: bool) -> u32 {
     let initial_set: u32 = match union {
-        true => u32::min_value(),
-        false => u32::max_value(),
+        true => u32::MIN,
+        false => u32::MAX,
     };
 
-    let (count, _) = lines.fold((0, initial_set), |(count, set), line| {
+    // The set is `None` between groups, so that repeated blank lines do not count as empty groups.
+    let (count, _) = lines.fold((0, None), |(count, set): (u32, Option<u32>), line| {
         if line.is_empty() {
-            (count + set.count_ones(), initial_set)
+            (count + set.map_or(0, u32::count_ones), None)
         } else {
             let mut line_set = 0;
             for c in line.bytes() {
                 line_set |= 1 << (25 - (122 - c)) // Convert letter to bit index. 122 == 'z'
             }
 
+            let set = set.unwrap_or(initial_set);
             let new_set = match union {
                 true => set | line_set,
                 false => set & line_set,
             };
-            (count, new_set)
+            (count, Some(new_set))
         }
     });
 
     count
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::day6::{read_file, sum_answer_counts};
-
-    const EXAMPLE: &str = "
-abc
+pub struct Day6;
 
-a
-b
-c
+impl Solution for Day6 {
+    const DAY: u8 = 6;
+    type Input = Vec<String>;
 
-ab
-ac
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        // Add an extra new line at the end.
+        parse_answers(lines(input).chain(lines("\n")))
+    }
 
-a
-a
-a
-a
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        Some(sum_answer_counts(input.iter().cloned(), true).to_string())
+    }
 
-b
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        Some(sum_answer_counts(input.iter().cloned(), false).to_string())
+    }
+}
 
-";
+#[cfg(test)]
+mod tests {
+    use crate::day6::{read_file, sum_answer_counts};
+    use crate::input::read_fixture;
+    use crate::solution::lines;
 
     #[test]
     fn part1_example() {
-        let res = sum_answer_counts(
-            EXAMPLE[1..].to_string().lines().map(|s| s.to_string()),
-            true,
-        );
+        let res = sum_answer_counts(lines(&read_fixture("day6.txt")), true);
         println!("{}", res);
         assert_eq!(res, 11);
     }
@@ -78,10 +93,7 @@ b
 
     #[test]
     fn part2_example() {
-        let res = sum_answer_counts(
-            EXAMPLE[1..].to_string().lines().map(|s| s.to_string()),
-            false,
-        );
+        let res = sum_answer_counts(lines(&read_fixture("day6.txt")), false);
         println!("{}", res);
         assert_eq!(res, 6);
     }
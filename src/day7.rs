@@ -1,49 +1,66 @@
+use crate::error::{parse_number, ParseError};
+use crate::input::read_day_lines;
+use crate::numeric::{exact_option, Natural, Overflow};
+use crate::solution::{lines, Solution};
 use itertools::Itertools;
 use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
-use std::iter::FromIterator;
-use std::str::FromStr;
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day7.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap().to_string())
+
+pub fn read_file() -> impl Iterator<Item = String> {
+    read_day_lines(Day7::DAY)
 }
 
-fn parse_rules(lines: impl Iterator<Item = String>) -> HashMap<String, Vec<(String, u16)>> {
-    HashMap::from_iter(lines.filter(|line| !line.is_empty()).map(|line| {
-        if let Some((bag, contents)) = line.split(" bags contain ").collect_tuple() {
-            if contents == "no other bags." {
-                (bag.to_string(), Vec::new())
-            } else {
-                let parsed_contents = contents
-                    .replace(".", "")
-                    .split(", ")
-                    .map(|phrase| {
-                        let (count_str, inner_bag_phrase) = phrase.split_at(1);
-                        let count = u16::from_str(count_str).unwrap();
-                        let suffix_len = if count > 1 { 5 } else { 4 };
-                        let inner_bag =
-                            inner_bag_phrase[1..inner_bag_phrase.len() - suffix_len].to_string();
-                        (inner_bag, count)
-                    })
-                    .collect_vec();
-                (bag.to_string(), parsed_contents)
-            }
-        } else {
-            panic!("{}", line);
-        }
-    }))
+pub fn parse_rules(
+    lines: impl Iterator<Item = String>,
+) -> Result<HashMap<String, Vec<(String, u16)>>, ParseError> {
+    lines
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| parse_rule(i + 1, &line))
+        .collect()
+}
+
+/// Parses a rule of the form `light red bags contain 1 bright white bag, 2 muted yellow bags.`
+fn parse_rule(line_no: usize, line: &str) -> Result<(String, Vec<(String, u16)>), ParseError> {
+    let error = |part: &str, message: &str| ParseError::at(Day7::DAY, line_no, line, part, message);
+
+    let (bag, contents) = line
+        .split(" bags contain ")
+        .collect_tuple()
+        .ok_or_else(|| error(line, "Expected '<bag> bags contain <contents>.'"))?;
+    let contents = contents
+        .strip_suffix('.')
+        .ok_or_else(|| error(contents, "Expected the contents to end with '.'"))?;
+    if contents == "no other bags" {
+        return Ok((bag.to_string(), Vec::new()));
+    }
+
+    let parsed_contents = contents
+        .split(", ")
+        .map(|phrase| {
+            let (count_str, inner_bag_phrase) = phrase
+                .split_once(' ')
+                .ok_or_else(|| error(phrase, "Expected '<count> <bag> bags'"))?;
+            let count: u16 = parse_number(Day7::DAY, line_no, line, count_str)?;
+            let suffix = if count == 1 { " bag" } else { " bags" };
+            let inner_bag = inner_bag_phrase.strip_suffix(suffix).ok_or_else(|| {
+                error(
+                    inner_bag_phrase,
+                    &format!("Expected the bag to end with '{}'", suffix),
+                )
+            })?;
+            Ok((inner_bag.to_string(), count))
+        })
+        .collect::<Result<Vec<_>, ParseError>>()?;
+    Ok((bag.to_string(), parsed_contents))
 }
 
-fn reverse_rules(rules: &HashMap<String, Vec<(String, u16)>>) -> HashMap<&String, Vec<&String>> {
+pub fn reverse_rules(rules: &HashMap<String, Vec<(String, u16)>>) -> HashMap<&str, Vec<&str>> {
     rules
         .iter()
         .flat_map(|(bag, contents)| {
             contents
                 .iter()
-                .map(move |(inner_bag, _count)| (inner_bag, bag))
+                .map(move |(inner_bag, _count)| (inner_bag.as_str(), bag.as_str()))
         })
         .fold(HashMap::new(), |mut res, (inner_bag, outer_bag)| {
             if let Some(vec) = res.get_mut(inner_bag) {
@@ -55,68 +72,117 @@ fn reverse_rules(rules: &HashMap<String, Vec<(String, u16)>>) -> HashMap<&String
         })
 }
 
-fn collect_outer_bags<'a>(
-    reverse_rules: &'a HashMap<&String, Vec<&String>>,
-    inner_bag: &String,
-    result: &mut HashSet<&'a String>,
+/// Adds every bag that can eventually contain `inner_bag` to `result`.
+pub fn collect_outer_bags<'a>(
+    reverse_rules: &'a HashMap<&str, Vec<&str>>,
+    inner_bag: &str,
+    result: &mut HashSet<&'a str>,
 ) {
     if let Some(outer_bags) = reverse_rules.get(inner_bag) {
         for outer_bag in outer_bags {
-            result.insert(*outer_bag);
-            collect_outer_bags(reverse_rules, outer_bag, result);
+            // A bag already in the result has had its outer bags collected, which also ends any cycle in the rules.
+            if result.insert(*outer_bag) {
+                collect_outer_bags(reverse_rules, outer_bag, result);
+            }
         }
     }
 }
 
-fn count_inner_bags(rules: &HashMap<String, Vec<(String, u16)>>, outer_bag: &String) -> u64 {
-    match rules.get(outer_bag) {
-        None => 0,
-        Some(inner_bags) => inner_bags
-            .iter()
-            .map(|(inner_bag, count)| {
-                let inner_bag_count = count_inner_bags(rules, inner_bag);
-                (*count as u64) * (inner_bag_count + 1)
-            })
-            .sum(),
+/// Returns `None` if `outer_bag` can eventually contain itself, so that it holds no end of bags.
+pub fn count_inner_bags(
+    rules: &HashMap<String, Vec<(String, u16)>>,
+    outer_bag: &str,
+) -> Option<u64> {
+    count_inner_bags_as(rules, outer_bag).expect("The number of bags overflowed")
+}
+
+/// Same as `count_inner_bags`, counting with any `Natural`.
+pub fn count_inner_bags_as<N: Natural>(
+    rules: &HashMap<String, Vec<(String, u16)>>,
+    outer_bag: &str,
+) -> Result<Option<N>, Overflow> {
+    count_inner_bags_from(rules, outer_bag, &mut HashSet::new())
+}
+
+/// Same as `count_inner_bags_as`, where `visiting` holds the bags that contain `outer_bag` on the way down from the
+/// bag the count started from.
+fn count_inner_bags_from<'a, N: Natural>(
+    rules: &'a HashMap<String, Vec<(String, u16)>>,
+    outer_bag: &'a str,
+    visiting: &mut HashSet<&'a str>,
+) -> Result<Option<N>, Overflow> {
+    let inner_bags = match rules.get(outer_bag) {
+        None => return Ok(Some(N::zero())),
+        Some(inner_bags) => inner_bags,
+    };
+    if !visiting.insert(outer_bag) {
+        return Ok(None);
+    }
+
+    let mut total = N::zero();
+    for (inner_bag, count) in inner_bags {
+        let inner_bag_count = match count_inner_bags_from::<N>(rules, inner_bag, visiting)? {
+            Some(inner_bag_count) => inner_bag_count,
+            None => return Ok(None),
+        };
+        total = total.add(&N::from_u64(*count as u64).mul(&inner_bag_count.add(&N::one())?)?)?;
+    }
+    visiting.remove(outer_bag);
+    Ok(Some(total))
+}
+
+pub struct Day7;
+
+impl Solution for Day7 {
+    const DAY: u8 = 7;
+    type Input = HashMap<String, Vec<(String, u16)>>;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_rules(lines(input))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        let reverse_rules = reverse_rules(input);
+        let mut outer_bags = HashSet::new();
+        collect_outer_bags(&reverse_rules, "shiny gold", &mut outer_bags);
+        Some(outer_bags.len().to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        exact_option(count_inner_bags_as(input, "shiny gold"), || {
+            count_inner_bags_as(input, "shiny gold")
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::day7::{
-        collect_outer_bags, count_inner_bags, parse_rules, read_file, reverse_rules,
+        collect_outer_bags, count_inner_bags, count_inner_bags_as, parse_rules, read_file,
+        reverse_rules, Day7,
     };
-    use std::collections::HashSet;
-
-    const EXAMPLE1: &str = "
-light red bags contain 1 bright white bag, 2 muted yellow bags.
-dark orange bags contain 3 bright white bags, 4 muted yellow bags.
-bright white bags contain 1 shiny gold bag.
-muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
-shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.
-dark olive bags contain 3 faded blue bags, 4 dotted black bags.
-vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
-faded blue bags contain no other bags.
-dotted black bags contain no other bags.
-";
+    use crate::input::read_fixture;
+    use crate::numeric::{BigUint, Overflow};
+    use crate::solution::{lines, Solution};
+    use std::collections::{HashMap, HashSet};
 
     #[test]
     fn part1_example() {
-        let mut outer_bags = HashSet::new();
-        let rules = parse_rules(EXAMPLE1[1..].to_string().lines().map(|s| s.to_string()));
+        let rules = parse_rules(lines(&read_fixture("day7.txt"))).unwrap();
 
         let reverse_rules = reverse_rules(&rules);
-        collect_outer_bags(&reverse_rules, &"shiny gold".to_string(), &mut outer_bags);
+        let mut outer_bags = HashSet::new();
+        collect_outer_bags(&reverse_rules, "shiny gold", &mut outer_bags);
         let res = outer_bags.len();
         assert_eq!(res, 4);
     }
 
     #[test]
     fn part1() {
-        let mut outer_bags = HashSet::new();
-        let rules = parse_rules(read_file());
+        let rules = parse_rules(read_file()).unwrap();
         let reverse_rules = reverse_rules(&rules);
-        collect_outer_bags(&reverse_rules, &"shiny gold".to_string(), &mut outer_bags);
+        let mut outer_bags = HashSet::new();
+        collect_outer_bags(&reverse_rules, "shiny gold", &mut outer_bags);
         let res = outer_bags.len();
         println!("{}", res);
         assert_eq!(res, 233);
@@ -124,35 +190,84 @@ dotted black bags contain no other bags.
 
     #[test]
     fn part2_example1() {
-        let rules = parse_rules(EXAMPLE1[1..].to_string().lines().map(|s| s.to_string()));
-        println!("{:?}", rules.get(&"faded blue".to_string()));
+        let rules = parse_rules(lines(&read_fixture("day7.txt"))).unwrap();
+        println!("{:?}", rules.get("faded blue"));
 
-        let res = count_inner_bags(&rules, &"shiny gold".to_string());
-        assert_eq!(res, 32);
+        let res = count_inner_bags(&rules, "shiny gold");
+        assert_eq!(res, Some(32));
     }
 
-    const EXAMPLE2: &str = "
-shiny gold bags contain 2 dark red bags.
-dark red bags contain 2 dark orange bags.
-dark orange bags contain 2 dark yellow bags.
-dark yellow bags contain 2 dark green bags.
-dark green bags contain 2 dark blue bags.
-dark blue bags contain 2 dark violet bags.
-dark violet bags contain no other bags.
-";
-
     #[test]
     fn part2_example2() {
-        let rules = parse_rules(EXAMPLE2[1..].to_string().lines().map(|s| s.to_string()));
-        let res = count_inner_bags(&rules, &"shiny gold".to_string());
-        assert_eq!(res, 126);
+        let rules = parse_rules(lines(&read_fixture("day7-2.txt"))).unwrap();
+        let res = count_inner_bags(&rules, "shiny gold");
+        assert_eq!(res, Some(126));
     }
 
     #[test]
     fn part2() {
-        let rules = parse_rules(read_file());
-        let res = count_inner_bags(&rules, &"shiny gold".to_string());
-        println!("{}", res);
-        assert_eq!(res, 421550);
+        let rules = parse_rules(read_file()).unwrap();
+        let res = count_inner_bags(&rules, "shiny gold");
+        println!("{:?}", res);
+        assert_eq!(res, Some(421550));
+    }
+
+    #[test]
+    fn part2_overflow() {
+        // Each bag holds two of the next, 70 deep, for 2 + 4 + ... + 2^70 bags.
+        let rules = (0..70)
+            .map(|i| (format!("bag {}", i), vec![(format!("bag {}", i + 1), 2)]))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(count_inner_bags_as::<u64>(&rules, "bag 0"), Err(Overflow));
+        let res = count_inner_bags_as::<BigUint>(&rules, "bag 0")
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.to_string(), "2361183241434822606846");
+    }
+
+    #[test]
+    fn cycles() {
+        // A bag that holds itself, directly or through other bags, holds no end of bags, but the bags that can hold it
+        // are still the bags on the cycle and the bags that hold them.
+        for (input, outer_bags) in [
+            ("shiny gold bags contain 1 shiny gold bag.\n", "1"),
+            (
+                "shiny gold bags contain 2 dark red bags.\ndark red bags contain 1 shiny gold bag.\n",
+                "2",
+            ),
+            (
+                "light red bags contain 1 dark red bag.\ndark red bags contain 1 shiny gold bag.\n\
+                 shiny gold bags contain 2 dark red bags.\n",
+                "3",
+            ),
+        ] {
+            let rules = parse_rules(lines(input)).unwrap();
+            assert_eq!(count_inner_bags(&rules, "shiny gold"), None);
+            assert_eq!(Day7.part1(&rules), Some(outer_bags.to_string()));
+            assert_eq!(Day7.part2(&rules), None);
+        }
+
+        // A cycle among the outer bags alone leaves the inner bags countable.
+        let input = "light red bags contain 1 dark red bag.
+dark red bags contain 1 light red bag, 1 shiny gold bag.
+shiny gold bags contain 2 faded blue bags.
+faded blue bags contain no other bags.
+";
+        let rules = parse_rules(lines(input)).unwrap();
+        assert_eq!(Day7.part1(&rules), Some("2".to_string()));
+        assert_eq!(Day7.part2(&rules), Some("2".to_string()));
+
+        // Bags reached along two paths are not a cycle.
+        let input = "shiny gold bags contain 1 dark red bag, 2 dark blue bags.
+dark red bags contain 3 faded blue bags.
+dark blue bags contain 1 faded blue bag.
+faded blue bags contain no other bags.
+";
+        let rules = parse_rules(lines(input)).unwrap();
+        assert_eq!(count_inner_bags(&rules, "shiny gold"), Some(1 + 3 + 2 + 2));
+        let reverse_rules = reverse_rules(&rules);
+        let mut outer_bags = HashSet::new();
+        collect_outer_bags(&reverse_rules, "faded blue", &mut outer_bags);
+        assert_eq!(outer_bags.len(), 3);
     }
 }
@@ -1,71 +1,147 @@
+use crate::error::{parse_number, ParseError};
+use crate::input::read_day_lines;
+use crate::solution::{lines, Certify, Solution};
+use crate::trace::{Event, Trace};
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
-use std::str::FromStr;
-
-fn read_file() -> impl Iterator<Item = String> {
-    let file = File::open("input/day8.txt").unwrap();
-    BufReader::new(file).lines().map(|s| s.unwrap().to_string())
+use std::convert::TryFrom;
+use std::fmt;
+
+pub fn read_file() -> impl Iterator<Item = String> {
+    read_day_lines(Day8::DAY)
 }
 
-#[derive(Debug)]
-enum Instruction {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Instruction {
     ACC(i32),
     JMP(i32),
     NOP(i32),
 }
 
-fn parse_instructions(iter: impl Iterator<Item = String>) -> Vec<Instruction> {
-    iter.map(|line| {
-        let sign = match &line[4..5] {
-            "+" => 1,
-            _ => -1,
-        };
-        let value = i32::from_str(&line[5..]).unwrap();
-        let argument = sign * value;
-
-        match &line[0..3] {
-            "acc" => Instruction::ACC(argument),
-            "jmp" => Instruction::JMP(argument),
-            "nop" => Instruction::NOP(argument),
-            x => panic!("Unexpected argument: {}", x),
+/// The instruction as it appears in the input, such as `acc +3`.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Instruction::ACC(argument) => write!(f, "acc {:+}", argument),
+            Instruction::JMP(argument) => write!(f, "jmp {:+}", argument),
+            Instruction::NOP(argument) => write!(f, "nop {:+}", argument),
         }
-    })
-    .collect_vec()
+    }
 }
 
-fn run_code(program: &Vec<Instruction>) -> Result<i32, i32> {
+pub fn parse_instructions(
+    iter: impl Iterator<Item = String>,
+) -> Result<Vec<Instruction>, ParseError> {
+    iter.enumerate()
+        .map(|(i, line)| {
+            let line_no = i + 1;
+            let (operation, argument_str) = line.split_once(' ').ok_or_else(|| {
+                ParseError::at(
+                    Day8::DAY,
+                    line_no,
+                    &line,
+                    &line,
+                    "Expected '<operation> <argument>'",
+                )
+            })?;
+            if !argument_str.starts_with('+') && !argument_str.starts_with('-') {
+                return Err(ParseError::at(
+                    Day8::DAY,
+                    line_no,
+                    &line,
+                    argument_str,
+                    "Expected the argument to start with '+' or '-'",
+                ));
+            }
+            let argument = parse_number(Day8::DAY, line_no, &line, argument_str)?;
+
+            match operation {
+                "acc" => Ok(Instruction::ACC(argument)),
+                "jmp" => Ok(Instruction::JMP(argument)),
+                "nop" => Ok(Instruction::NOP(argument)),
+                x => Err(ParseError::at(
+                    Day8::DAY,
+                    line_no,
+                    &line,
+                    operation,
+                    format!("Unexpected operation: {}", x),
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Why a program stopped before running off its end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Halt {
+    /// An instruction was about to run a second time. Holds the accumulator at that point.
+    Loop(i32),
+    /// A jump went before the first instruction, to the index given.
+    JumpedOut(i64),
+}
+
+pub fn run_code(program: &[Instruction]) -> Result<i32, Halt> {
+    run_code_traced(program, &mut Trace::off())
+}
+
+/// Same as `run_code`, recording an `execute` event with the accumulator after each instruction, then a `loop` event
+/// at the first instruction to run twice, a `jump_out` event or a `terminate` event.
+pub fn run_code_traced(program: &[Instruction], trace: &mut Trace) -> Result<i32, Halt> {
     let mut seen = HashSet::new();
-    let mut i = 0;
+    let mut i = 0i64;
     let mut acc = 0;
-    let len = program.len() as i32;
+    let len = program.len() as i64;
     while i < len {
         if seen.contains(&i) {
-            return Result::Err(acc);
+            trace.emit(|| Event::new("loop").with("index", i).with("acc", acc));
+            return Result::Err(Halt::Loop(acc));
         } else {
             seen.insert(i);
         }
 
-        match &program[i as usize] {
+        let index = i;
+        let instruction = match usize::try_from(index).ok().and_then(|i| program.get(i)) {
+            Some(instruction) => instruction,
+            None => {
+                trace.emit(|| Event::new("jump_out").with("index", index));
+                return Result::Err(Halt::JumpedOut(index));
+            }
+        };
+        match instruction {
             Instruction::ACC(argument) => {
                 acc += argument;
                 i += 1;
             }
             Instruction::JMP(argument) => {
-                i += argument;
+                i += i64::from(*argument);
             }
             Instruction::NOP(_) => {
                 i += 1;
             }
         }
+        trace.emit(|| {
+            Event::new("execute")
+                .with("index", index)
+                .with("instruction", instruction.to_string())
+                .with("acc", acc)
+        });
     }
 
+    trace.emit(|| Event::new("terminate").with("acc", acc));
     Result::Ok(acc)
 }
 
-fn break_infinite_loop(program: &mut Vec<Instruction>) -> Option<(usize, i32)> {
+pub fn break_infinite_loop(program: &mut Vec<Instruction>) -> Option<(usize, i32)> {
+    break_infinite_loop_traced(program, &mut Trace::off())
+}
+
+/// Same as `break_infinite_loop`, recording a `swap` event before each attempt followed by the events of running the
+/// changed program.
+pub fn break_infinite_loop_traced(
+    program: &mut Vec<Instruction>,
+    trace: &mut Trace,
+) -> Option<(usize, i32)> {
     let candidates = program
         .iter()
         .enumerate()
@@ -82,11 +158,18 @@ fn break_infinite_loop(program: &mut Vec<Instruction>) -> Option<(usize, i32)> {
             _ => panic!("Unexpected instruction"),
         };
 
+        trace.emit(|| {
+            Event::new("swap")
+                .with("index", index)
+                .with("from", program[*index].to_string())
+                .with("to", replacement.to_string())
+        });
+
         // Replace candidate
         program.push(replacement);
         let old_instruction = program.swap_remove(*index);
 
-        match run_code(&program) {
+        match run_code_traced(program, trace) {
             Ok(acc) => Some((*index, acc)),
             Err(_) => {
                 // Restore candidate.
@@ -98,41 +181,126 @@ fn break_infinite_loop(program: &mut Vec<Instruction>) -> Option<(usize, i32)> {
     })
 }
 
+/// Checks that swapping the `jmp` or `nop` at `index` makes the program run off its end, and returns the accumulator
+/// when it does. A program that finishes visits each instruction at most once, so it loops if it runs for longer.
+pub fn check_repair(program: &[Instruction], index: usize) -> Result<i32, String> {
+    match program.get(index) {
+        Some(Instruction::JMP(_)) | Some(Instruction::NOP(_)) => {}
+        Some(Instruction::ACC(_)) => return Err(format!("Instruction {} is an acc", index)),
+        None => return Err(format!("There is no instruction {}", index)),
+    }
+
+    let (mut i, mut acc) = (0i64, 0);
+    for _ in 0..=program.len() {
+        if i == program.len() as i64 {
+            return Ok(acc);
+        }
+        let instruction = usize::try_from(i)
+            .ok()
+            .and_then(|i| program.get(i))
+            .ok_or_else(|| format!("Jumped out of the program to {}", i))?;
+        match (instruction, i as usize == index) {
+            (Instruction::ACC(argument), _) => {
+                acc += argument;
+                i += 1;
+            }
+            (Instruction::JMP(argument), false) | (Instruction::NOP(argument), true) => {
+                i += i64::from(*argument)
+            }
+            (Instruction::NOP(_), false) | (Instruction::JMP(_), true) => i += 1,
+        }
+    }
+    Err(format!("Swapping instruction {} still loops", index))
+}
+
+pub struct Day8;
+
+impl Solution for Day8 {
+    const DAY: u8 = 8;
+    type Input = Vec<Instruction>;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_instructions(lines(input))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        match run_code(input) {
+            Err(Halt::Loop(acc)) => Some(acc.to_string()),
+            _ => None,
+        }
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        let mut program = input.clone();
+        break_infinite_loop(&mut program).map(|(_, acc)| acc.to_string())
+    }
+
+    fn explain(&self, part: u8, input: &Self::Input, trace: &mut Trace) -> Option<String> {
+        match part {
+            1 => match run_code_traced(input, trace) {
+                Err(Halt::Loop(acc)) => Some(acc.to_string()),
+                _ => None,
+            },
+            2 => break_infinite_loop_traced(&mut input.clone(), trace)
+                .map(|(_, acc)| acc.to_string()),
+            _ => None,
+        }
+    }
+}
+
+impl Certify for Day8 {
+    /// The index of the instruction to swap in part 2.
+    type Certificate = usize;
+
+    fn certify(&self, part: u8, input: &Self::Input) -> Option<Self::Certificate> {
+        match part {
+            2 => break_infinite_loop(&mut input.clone()).map(|(index, _)| index),
+            _ => None,
+        }
+    }
+
+    fn check(
+        &self,
+        _part: u8,
+        input: &Self::Input,
+        certificate: &Self::Certificate,
+    ) -> Result<String, String> {
+        check_repair(input, *certificate).map(|acc| acc.to_string())
+    }
+
+    fn describe(&self, certificate: &Self::Certificate) -> String {
+        format!("Swap instruction {}", certificate)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::day8::{break_infinite_loop, parse_instructions, read_file, run_code};
-
-    const EXAMPLE1: &str = "
-nop +0
-acc +1
-jmp +4
-acc +3
-jmp -3
-acc -99
-acc +1
-jmp -4
-acc +6
-";
+    use crate::day8::{
+        break_infinite_loop, break_infinite_loop_traced, check_repair, parse_instructions,
+        read_file, run_code, run_code_traced, Day8, Halt,
+    };
+    use crate::error::ParseError;
+    use crate::input::read_fixture;
+    use crate::solution::{lines, Solution};
+    use crate::trace::{Event, Trace};
 
     #[test]
     fn part1_example() {
-        let instructions =
-            parse_instructions(EXAMPLE1[1..].to_string().lines().map(|s| s.to_string()));
+        let instructions = parse_instructions(lines(&read_fixture("day8.txt"))).unwrap();
         let res = run_code(&instructions);
-        assert_eq!(res, Err(5));
+        assert_eq!(res, Err(Halt::Loop(5)));
     }
 
     #[test]
     fn part1() {
-        let instructions = parse_instructions(read_file());
+        let instructions = parse_instructions(read_file()).unwrap();
         let res = run_code(&instructions);
-        assert_eq!(res, Err(1331));
+        assert_eq!(res, Err(Halt::Loop(1331)));
     }
 
     #[test]
     fn part2_example() {
-        let mut instructions =
-            parse_instructions(EXAMPLE1[1..].to_string().lines().map(|s| s.to_string()));
+        let mut instructions = parse_instructions(lines(&read_fixture("day8.txt"))).unwrap();
         let (index, acc) = break_infinite_loop(&mut instructions).unwrap();
         println!("{}", index);
         assert_eq!(acc, 8);
@@ -140,10 +308,104 @@ acc +6
 
     #[test]
     fn part2() {
-        let mut instructions = parse_instructions(read_file());
+        let mut instructions = parse_instructions(read_file()).unwrap();
         let (index, acc) = break_infinite_loop(&mut instructions).unwrap();
         println!("{}", acc);
         println!("{}", index);
         assert_eq!(acc, 1121);
     }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |s: &str| parse_instructions(s.lines().map(|l| l.to_string()));
+        assert_eq!(
+            parse("nop +0\nhcf +1").unwrap_err(),
+            ParseError::new(8, 2, 1, "Unexpected operation: hcf")
+        );
+        assert_eq!(
+            parse("acc +1\njmp 4").unwrap_err(),
+            ParseError::new(8, 2, 5, "Expected the argument to start with '+' or '-'")
+        );
+        assert_eq!(
+            parse("acc +one").unwrap_err(),
+            ParseError::new(8, 1, 5, "Invalid number: '+one'")
+        );
+        assert_eq!(parse("nop").unwrap_err().line, 1);
+    }
+
+    #[test]
+    fn test_check_repair() {
+        let instructions = parse_instructions(lines(&read_fixture("day8.txt"))).unwrap();
+        assert_eq!(check_repair(&instructions, 7), Ok(8));
+        assert_eq!(
+            check_repair(&instructions, 0),
+            Err("Swapping instruction 0 still loops".to_string())
+        );
+        assert_eq!(
+            check_repair(&instructions, 1),
+            Err("Instruction 1 is an acc".to_string())
+        );
+        assert_eq!(
+            check_repair(&instructions, 9),
+            Err("There is no instruction 9".to_string())
+        );
+        let jumps_out = parse_instructions("jmp +5\nnop -4\n".lines().map(|s| s.to_string()));
+        assert_eq!(
+            check_repair(&jumps_out.unwrap(), 1),
+            Err("Jumped out of the program to 5".to_string())
+        );
+    }
+
+    #[test]
+    fn test_jump_out() {
+        let program = parse_instructions(lines("nop +0\njmp -5\nacc +1\n")).unwrap();
+        assert_eq!(run_code(&program), Err(Halt::JumpedOut(-4)));
+        assert_eq!(Day8.part1(&program), None);
+        let mut events = Vec::new();
+        let res = run_code_traced(&program, &mut Trace::to(|event| events.push(event)));
+        assert_eq!(res, Err(Halt::JumpedOut(-4)));
+        assert_eq!(
+            events.last(),
+            Some(&Event::new("jump_out").with("index", -4))
+        );
+
+        // Swapping the jump for a `nop` is the only repair.
+        let mut program = parse_instructions(lines("jmp -5\n")).unwrap();
+        assert_eq!(break_infinite_loop(&mut program), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_trace() {
+        let instructions = parse_instructions(lines(&read_fixture("day8.txt"))).unwrap();
+        let mut events = Vec::new();
+        let res = run_code_traced(&instructions, &mut Trace::to(|event| events.push(event)));
+        assert_eq!(res, Err(Halt::Loop(5)));
+        let executed = events
+            .iter()
+            .filter(|event| event.step == "execute")
+            .map(|event| event.get("index").unwrap().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(executed, vec![0, 1, 2, 6, 7, 3, 4]);
+        assert_eq!(
+            events[events.len() - 2].to_string(),
+            "execute index=4 instruction=jmp -3 acc=5"
+        );
+        assert_eq!(
+            events.last(),
+            Some(&Event::new("loop").with("index", 1).with("acc", 5))
+        );
+
+        let mut events = Vec::new();
+        let mut program = instructions.clone();
+        let res =
+            break_infinite_loop_traced(&mut program, &mut Trace::to(|event| events.push(event)));
+        assert_eq!(res, Some((7, 8)));
+        let swaps = events
+            .iter()
+            .filter(|event| event.step == "swap")
+            .map(|event| event.get("index").unwrap().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(swaps, vec![0, 2, 4, 7]);
+        assert_eq!(events.last(), Some(&Event::new("terminate").with("acc", 8)));
+    }
 }
@@ -1,19 +1,23 @@
-use std::fs::File;
-use std::io::prelude::*;
-use std::io::BufReader;
-use std::str::FromStr;
-use std::vec::Vec;
-
-fn read_file() -> impl Iterator<Item = u64> {
-    let file = File::open("input/day9.txt").unwrap();
-    BufReader::new(file)
-        .lines()
-        .map(|s| u64::from_str(s.unwrap().as_str()).unwrap())
+use crate::error::{parse_number, ParseError};
+use crate::input::read_day_lines;
+use crate::solution::{lines, Certify, Solution};
+use itertools::{Itertools, MinMaxResult};
+use std::collections::HashSet;
+use std::ops::Range;
+
+pub fn read_file() -> Result<Vec<u64>, ParseError> {
+    parse_numbers(read_day_lines(Day9::DAY))
+}
+
+pub fn parse_numbers(iter: impl Iterator<Item = String>) -> Result<Vec<u64>, ParseError> {
+    iter.enumerate()
+        .map(|(i, line)| parse_number(Day9::DAY, i + 1, &line, &line))
+        .collect()
 }
 
 /// An invalid entry is one for which there does not exist a pair of values that sum to it in the preceding `preamble`
 /// entries.
-fn find_first_invalid(seq: &Vec<u64>, preamble: usize) -> Option<u64> {
+pub fn find_first_invalid(seq: &[u64], preamble: usize) -> Option<u64> {
     (preamble..seq.len()).find_map(|index| {
         let value = seq[index];
         if is_sum_of_pair(value, &seq[index - preamble..index]) {
@@ -25,58 +29,174 @@ fn find_first_invalid(seq: &Vec<u64>, preamble: usize) -> Option<u64> {
 }
 
 /// Returns true if there are a pair of values in `candidates` that sum to `value.
-fn is_sum_of_pair(value: u64, candidates: &[u64]) -> bool {
+pub fn is_sum_of_pair(value: u64, candidates: &[u64]) -> bool {
     // Simple n^2 algorithm works just fine for inputs of this length: n == 25
     candidates
         .iter()
         .filter(|v| **v < value)
         .map(|v| value - v)
         .enumerate()
-        .any(|(i, expected)| candidates[i + 1..].iter().any(|v2| *v2 == expected))
+        .any(|(i, expected)| candidates[i + 1..].contains(&expected))
 }
 
 /// Returns the min and max values in any contiguous subsequence of `candidates` that sums to `target`.
-fn find_summing_subsequence(target: u64, candidates: &Vec<u64>) -> Option<(u64, u64)> {
+pub fn find_summing_subsequence(target: u64, candidates: &[u64]) -> Option<(u64, u64)> {
+    match candidates[find_summing_range(target, candidates)?]
+        .iter()
+        .minmax()
+    {
+        MinMaxResult::NoElements => None,
+        MinMaxResult::OneElement(v) => Some((*v, *v)),
+        MinMaxResult::MinMax(min, max) => Some((*min, *max)),
+    }
+}
+
+/// Returns the first range of `candidates` that sums to `target`.
+pub fn find_summing_range(target: u64, candidates: &[u64]) -> Option<Range<usize>> {
     // Simple n^2 algorithm works just fine for inputs of this length.
     for i in 0..candidates.len() {
         let mut sum = 0;
         let mut j = i;
-        let mut min = u64::max_value();
-        let mut max = u64::min_value();
         while sum < target && j < candidates.len() {
-            let candidate = candidates[j];
-            sum += candidate;
-            min = candidate.min(min);
-            max = candidate.max(max);
+            // A sum past u64::MAX is past the target too.
+            match sum.checked_add(candidates[j]) {
+                Some(next) => sum = next,
+                None => break,
+            }
             j += 1;
         }
 
         if sum == target {
-            return Some((min, max));
+            return Some(i..j);
         }
     }
 
     None
 }
 
+/// Checks that the entry at `invalid` is the first that is not the sum of two different values in the `preamble`
+/// entries before it, and that `range` holds at least two entries that add up to it. Returns the sum of the smallest and
+/// largest entries in the range.
+pub fn check_weakness(
+    seq: &[u64],
+    preamble: usize,
+    invalid: usize,
+    range: &Range<usize>,
+) -> Result<u64, String> {
+    if invalid < preamble || invalid >= seq.len() {
+        return Err(format!("Entry {} is not after the preamble", invalid));
+    }
+    let is_sum = |index: usize| {
+        let window: HashSet<u64> = seq[index - preamble..index].iter().copied().collect();
+        window
+            .iter()
+            .any(|v| seq[index] > *v && seq[index] - v != *v && window.contains(&(seq[index] - v)))
+    };
+    if let Some(index) = (preamble..invalid).find(|index| !is_sum(*index)) {
+        return Err(format!(
+            "Entry {} is invalid before entry {}",
+            index, invalid
+        ));
+    }
+    if is_sum(invalid) {
+        return Err(format!("Entry {} is a sum of two entries", invalid));
+    }
+
+    if range.end > seq.len() || range.len() < 2 {
+        return Err(format!(
+            "{:?} is not a range of at least two entries",
+            range
+        ));
+    }
+    match seq[range.clone()]
+        .iter()
+        .try_fold(0u64, |sum, v| sum.checked_add(*v))
+    {
+        Some(sum) if sum == seq[invalid] => {}
+        Some(sum) => {
+            return Err(format!(
+                "{:?} adds up to {}, not {}",
+                range, sum, seq[invalid]
+            ))
+        }
+        None => return Err(format!("{:?} adds up to more than {}", range, u64::MAX)),
+    }
+    let min = seq[range.clone()].iter().min().unwrap();
+    let max = seq[range.clone()].iter().max().unwrap();
+    Ok(min + max)
+}
+
+pub struct Day9;
+
+impl Solution for Day9 {
+    const DAY: u8 = 9;
+    type Input = Vec<u64>;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError> {
+        parse_numbers(lines(input))
+    }
+
+    fn part1(&self, input: &Self::Input) -> Option<String> {
+        find_first_invalid(input, 25).map(|v| v.to_string())
+    }
+
+    fn part2(&self, input: &Self::Input) -> Option<String> {
+        let target = find_first_invalid(input, 25)?;
+        find_summing_subsequence(target, input).map(|(min, max)| (min + max).to_string())
+    }
+}
+
+impl Certify for Day9 {
+    /// The index of the first invalid entry and the range of entries that adds up to it, for part 2.
+    type Certificate = (usize, Range<usize>);
+
+    fn certify(&self, part: u8, input: &Self::Input) -> Option<Self::Certificate> {
+        if part != 2 {
+            return None;
+        }
+        let target = find_first_invalid(input, 25)?;
+        let invalid = input.iter().skip(25).position(|v| *v == target)? + 25;
+        Some((invalid, find_summing_range(target, input)?))
+    }
+
+    fn check(
+        &self,
+        _part: u8,
+        input: &Self::Input,
+        (invalid, range): &Self::Certificate,
+    ) -> Result<String, String> {
+        check_weakness(input, 25, *invalid, range).map(|weakness| weakness.to_string())
+    }
+
+    fn describe(&self, (invalid, range): &Self::Certificate) -> String {
+        format!(
+            "Entries {} to {} add up to entry {}",
+            range.start,
+            range.end - 1,
+            invalid
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::day9::{find_first_invalid, find_summing_subsequence, read_file};
-    use itertools::Itertools;
+    use crate::day9::{
+        check_weakness, find_first_invalid, find_summing_range, find_summing_subsequence,
+        parse_numbers, read_file,
+    };
+    use crate::input::read_fixture;
+    use crate::solution::lines;
 
     #[test]
     fn part1_example() {
-        let vec = vec![
-            35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309,
-            576,
-        ];
+        let vec = parse_numbers(lines(&read_fixture("day9.txt"))).unwrap();
         let res = find_first_invalid(&vec, 5);
         assert_eq!(res, Some(127));
     }
 
     #[test]
     fn part1() {
-        let vec = read_file().collect_vec();
+        let vec = read_file().unwrap();
         let res = find_first_invalid(&vec, 25);
         println!("{}", res.unwrap());
         assert_eq!(res, Some(177777905));
@@ -84,17 +204,14 @@ mod tests {
 
     #[test]
     fn part2_example() {
-        let vec = vec![
-            35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309,
-            576,
-        ];
+        let vec = parse_numbers(lines(&read_fixture("day9.txt"))).unwrap();
         let res = find_summing_subsequence(127, &vec);
         assert_eq!(res, Some((15, 47)));
     }
 
     #[test]
     fn part2() {
-        let vec = read_file().collect_vec();
+        let vec = read_file().unwrap();
         if let Some((min, max)) = find_summing_subsequence(177777905, &vec) {
             println!("{} {} {}", min, max, min + max);
             assert_eq!(23463012, min + max);
@@ -102,4 +219,38 @@ mod tests {
             panic!("Answer not found");
         }
     }
+
+    #[test]
+    fn test_check_weakness() {
+        let vec = parse_numbers(lines(&read_fixture("day9.txt"))).unwrap();
+        assert_eq!(find_summing_range(127, &vec), Some(2..6));
+        assert_eq!(check_weakness(&vec, 5, 14, &(2..6)), Ok(62));
+        assert_eq!(
+            check_weakness(&vec, 5, 14, &(2..5)),
+            Err("2..5 adds up to 87, not 127".to_string())
+        );
+        assert!(check_weakness(&vec, 5, 14, &(14..15)).is_err());
+        assert_eq!(
+            check_weakness(&vec, 5, 13, &(2..6)),
+            Err("Entry 13 is a sum of two entries".to_string())
+        );
+        assert!(check_weakness(&vec, 5, 3, &(2..6)).is_err());
+
+        // Sums that would not fit in a u64 add up to more than any entry.
+        let max = u64::MAX;
+        assert_eq!(
+            find_summing_range(max - 1, &[max - 2, 5, 2, max - 3]),
+            Some(2..4)
+        );
+        assert_eq!(
+            check_weakness(&[max - 1, 2, 5], 2, 2, &(0..2)),
+            Err(format!("0..2 adds up to more than {}", max))
+        );
+
+        // 25 is invalid with a preamble of 3, before 127.
+        assert_eq!(
+            check_weakness(&vec, 3, 14, &(2..6)),
+            Err("Entry 3 is invalid before entry 14".to_string())
+        );
+    }
 }
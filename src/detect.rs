@@ -0,0 +1,200 @@
+//! Works out which day an input belongs to. Every day's parser is tried, and the days whose parser accepts the input
+//! are ranked by how well it fits the day's format: `Tile NNNN:` headers, `mask = ` lines, hex directions and so on.
+use crate::day1::find_pair_with_sum;
+use crate::day9::is_sum_of_pair;
+use crate::registry;
+use std::collections::HashSet;
+use std::fmt;
+
+/// A day whose parser accepts the input, with how confident the detector is that the input belongs to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub day: u8,
+    /// From 0 to 100.
+    pub confidence: u8,
+    /// The feature of the input the confidence is based on.
+    pub reason: &'static str,
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Day {:>2}: {:>3}% {}",
+            self.day, self.confidence, self.reason
+        )
+    }
+}
+
+/// Returns the days whose parser accepts the input, most plausible first, or none if the input is blank. Days with
+/// the same confidence are in order.
+pub fn detect(input: &str) -> Vec<Candidate> {
+    let lines = input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    // Several parsers accept an empty input, but it tells nothing about the day.
+    if lines.is_empty() {
+        return Vec::new();
+    }
+    let mut candidates = registry::solutions()
+        .iter()
+        .filter(|solution| solution.parse(input).is_ok())
+        .map(|solution| {
+            let (confidence, reason) = score(solution.day(), input, &lines);
+            Candidate {
+                day: solution.day(),
+                confidence,
+                reason,
+            }
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|candidate| (u8::MAX - candidate.confidence, candidate.day));
+    candidates
+}
+
+/// How well an input that the day's parser accepts fits the day's format. Days with distinctive formats, such as
+/// day 2's `1-3 a: abcde` lines, only need their parser to accept the input. Lists of numbers and grids, which several
+/// days' parsers accept, are told apart by their contents.
+fn score(day: u8, input: &str, lines: &[&str]) -> (u8, &'static str) {
+    let numbers = lines
+        .iter()
+        .map(|line| line.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()
+        .unwrap_or_default();
+    let is_grid_of = |cells: &str| {
+        let width = lines.first().map_or(0, |line| line.len());
+        lines
+            .iter()
+            .all(|line| line.len() == width && line.chars().all(|c| cells.contains(c)))
+    };
+    match day {
+        1 if numbers.iter().all(|n| *n < 2020) => {
+            let entries = numbers.iter().map(|n| *n as u32).collect::<HashSet<_>>();
+            match find_pair_with_sum(&entries, 2020) {
+                Some(_) => (90, "Two entries add up to 2020"),
+                None => (40, "A list of numbers below 2020"),
+            }
+        }
+        2 => (95, "Password policies like `1-3 a: abcde`"),
+        3 if is_grid_of(".#") && lines.len() > lines[0].len() => {
+            (80, "A tall map of open squares and trees")
+        }
+        4 if lines
+            .iter()
+            .flat_map(|line| line.split_whitespace())
+            .all(|field| {
+                field.split_once(':').is_some_and(|(key, _)| {
+                    key.len() == 3 && key.chars().all(|c| c.is_ascii_lowercase())
+                })
+            }) =>
+        {
+            (95, "Passport fields like `byr:1937`")
+        }
+        5 => (95, "Boarding passes like `FBFBBFFRLR`"),
+        6 if lines
+            .iter()
+            .all(|line| line.chars().all(|c| c.is_ascii_lowercase())) =>
+        {
+            (60, "Groups of answers in lowercase letters")
+        }
+        7 => (
+            95,
+            "Rules like `bright white bags contain 1 shiny gold bag`",
+        ),
+        8 => (95, "Instructions like `acc +1`"),
+        9 if numbers.len() > 25 => {
+            let valid = (25..numbers.len())
+                .filter(|i| is_sum_of_pair(numbers[*i], &numbers[i - 25..*i]))
+                .count();
+            if valid * 10 >= (numbers.len() - 25) * 9 {
+                (95, "Numbers are sums of two of the 25 before them")
+            } else {
+                (30, "A long list of numbers")
+            }
+        }
+        10 if {
+            let mut adapters = numbers.clone();
+            adapters.sort_unstable();
+            adapters.first().is_some_and(|first| *first <= 3)
+                && adapters
+                    .windows(2)
+                    .all(|pair| pair[0] < pair[1] && pair[1] - pair[0] <= 3)
+        } =>
+        {
+            (90, "Adapters with joltage gaps of at most 3")
+        }
+        11 if input.contains('L') => (95, "A seat layout with `L` seats"),
+        12 => (95, "Navigation instructions like `F10`"),
+        13 if lines.len() == 2 && lines[1].contains(',') => {
+            (95, "A timestamp and bus numbers like `7,13,x,x,59`")
+        }
+        14 => (95, "`mask = ` and `mem[8] = ` lines"),
+        15 if input.contains(',') => (90, "A single line of starting numbers"),
+        16 => (95, "Ticket rules and `your ticket:`"),
+        17 if is_grid_of(".#") && lines.len() <= 16 && lines[0].len() <= 16 => {
+            (80, "A small slice of cubes")
+        }
+        18 if input.contains('+') || input.contains('*') => (90, "Expressions with `+` and `*`"),
+        19 => (95, "Numbered rules and messages"),
+        20 => (95, "`Tile NNNN:` headers"),
+        21 if input.contains("(contains ") => (95, "Ingredient lists with `(contains ...)`"),
+        22 => (95, "Decks for `Player 1:` and `Player 2:`"),
+        23 => (95, "A single line of cup labels"),
+        24 => (95, "Hex directions like `nwwswee`"),
+        25 if numbers.iter().all(|n| *n < 20201227) => (80, "Two public keys below 20201227"),
+        _ => (10, "Parses, but does not look like the day's input"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::detect::{detect, Candidate};
+    use crate::input::read_day;
+
+    #[test]
+    fn test_detect_real_inputs() {
+        for day in 1..=25 {
+            let candidates = detect(&read_day(day));
+            assert_eq!(candidates[0].day, day, "{:?}", candidates);
+            if let Some(runner_up) = candidates.get(1) {
+                assert!(
+                    runner_up.confidence < candidates[0].confidence,
+                    "{:?}",
+                    candidates
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_detect_examples() {
+        let candidates = detect("1721\n979\n366\n299\n675\n1456\n");
+        assert_eq!(
+            candidates[0],
+            Candidate {
+                day: 1,
+                confidence: 90,
+                reason: "Two entries add up to 2020",
+            }
+        );
+        assert_eq!(
+            candidates[0].to_string(),
+            "Day  1:  90% Two entries add up to 2020"
+        );
+        assert!(candidates.iter().any(|candidate| candidate.day == 10));
+
+        let candidates = detect("16\n10\n15\n5\n1\n11\n7\n19\n6\n12\n4\n");
+        assert_eq!(candidates[0].day, 10);
+
+        let candidates = detect("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X\nmem[8] = 11\n");
+        assert_eq!((candidates[0].day, candidates[0].confidence), (14, 95));
+        // Day 21's parser accepts almost any text, but the input has no `(contains ...)`.
+        assert_eq!((candidates[1].day, candidates[1].confidence), (21, 10));
+
+        assert!(detect("\n\n").is_empty());
+        let candidates = detect("Tile 1: not a tile\n");
+        assert!(candidates.iter().all(|candidate| candidate.day != 20));
+    }
+}
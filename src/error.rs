@@ -0,0 +1,100 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A malformed puzzle input. Lines and columns are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u8,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(day: u8, line: usize, column: usize, message: impl Into<String>) -> ParseError {
+        ParseError {
+            day,
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// An error pointing at `part`, which must be a slice of `text`, the text of line `line`.
+    pub fn at(
+        day: u8,
+        line: usize,
+        text: &str,
+        part: &str,
+        message: impl Into<String>,
+    ) -> ParseError {
+        ParseError::new(day, line, column_of(text, part), message)
+    }
+
+    /// Moves the error to another line, for parsers that handle a single line without knowing where it is.
+    pub fn on_line(self, line: usize) -> ParseError {
+        ParseError { line, ..self }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Day {}, line {}, column {}: {}",
+            self.day, self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// The 1-based column at which `part`, a slice of `text`, starts. Falls back to the first column if `part` does not
+/// point into `text`.
+pub fn column_of(text: &str, part: &str) -> usize {
+    let start = text.as_ptr() as usize;
+    let offset = (part.as_ptr() as usize).wrapping_sub(start);
+    if offset <= text.len() && part.len() <= text.len() - offset {
+        text[..offset].chars().count() + 1
+    } else {
+        1
+    }
+}
+
+/// Parses `part`, a slice of `text`, as a number.
+pub fn parse_number<T: FromStr>(
+    day: u8,
+    line: usize,
+    text: &str,
+    part: &str,
+) -> Result<T, ParseError> {
+    T::from_str(part)
+        .map_err(|_| ParseError::at(day, line, text, part, format!("Invalid number: '{}'", part)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{column_of, parse_number, ParseError};
+
+    #[test]
+    fn test_column_of() {
+        let text = "mem[8] = 11";
+        assert_eq!(column_of(text, &text[0..3]), 1);
+        assert_eq!(column_of(text, &text[9..]), 10);
+        assert_eq!(column_of(text, "elsewhere"), 1);
+    }
+
+    #[test]
+    fn test_parse_number() {
+        let text = "acc +1x";
+        assert_eq!(parse_number::<i32>(8, 3, text, &text[5..6]), Ok(1));
+
+        let err = parse_number::<i32>(8, 3, text, &text[5..]).unwrap_err();
+        assert_eq!(err, ParseError::new(8, 3, 6, "Invalid number: '1x'"));
+        assert_eq!(
+            err.to_string(),
+            "Day 8, line 3, column 6: Invalid number: '1x'"
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Downloads puzzle inputs into the input directory. A day that already has an input file is never downloaded again.
+use crate::input::input_dir;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Environment variable holding the value of the `session` cookie of a logged in adventofcode.com account.
+pub const SESSION_VAR: &str = "AOC_SESSION";
+
+/// Environment variable overriding the site the inputs are downloaded from, e.g. with a local stand-in.
+pub const BASE_URL_VAR: &str = "AOC_BASE_URL";
+
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com/2020";
+
+/// Sent with every request, as the site asks automated tools to identify themselves.
+const USER_AGENT: &str = concat!(
+    "aoc-2020/",
+    env!("CARGO_PKG_VERSION"),
+    " (input downloader)"
+);
+
+#[derive(Debug)]
+pub enum FetchError {
+    InvalidDay(u8),
+    /// The input has to be downloaded, but no session token was given.
+    NoSession,
+    /// The request failed, or the server did not answer with the input.
+    Http(String),
+    Io(PathBuf, io::Error),
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FetchError::InvalidDay(day) => write!(f, "Invalid day: {}", day),
+            FetchError::NoSession => write!(
+                f,
+                "Set {} to the session cookie of a logged in account to download inputs",
+                SESSION_VAR
+            ),
+            FetchError::Http(message) => write!(f, "Could not download the input: {}", message),
+            FetchError::Io(path, e) => write!(f, "Could not write {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl Error for FetchError {}
+
+/// Where a fetched input came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fetched {
+    Cached(PathBuf),
+    Downloaded(PathBuf),
+}
+
+/// Downloads `day<N>/input` from `base_url` into `day<N>.txt` files in `dir`.
+#[derive(Debug, Clone)]
+pub struct Fetcher {
+    base_url: String,
+    session: Option<String>,
+    dir: PathBuf,
+}
+
+impl Fetcher {
+    pub fn new(base_url: &str, session: Option<String>, dir: &Path) -> Fetcher {
+        Fetcher {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            session,
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    /// Reads the base URL and session from `AOC_BASE_URL` and `AOC_SESSION`, and caches in the input directory.
+    pub fn from_env() -> Fetcher {
+        let base_url = env::var(BASE_URL_VAR).unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
+        let session = env::var(SESSION_VAR)
+            .ok()
+            .map(|session| session.trim().to_string())
+            .filter(|session| !session.is_empty());
+        Fetcher::new(&base_url, session, &input_dir())
+    }
+
+    pub fn path(&self, day: u8) -> PathBuf {
+        self.dir.join(format!("day{}.txt", day))
+    }
+
+    /// Returns the cached input file, downloading it first if there is none. The download is written to a temporary
+    /// file and renamed into place, so a failed download never leaves a partial input behind.
+    pub fn fetch(&self, day: u8) -> Result<Fetched, FetchError> {
+        if !(1..=25).contains(&day) {
+            return Err(FetchError::InvalidDay(day));
+        }
+        let path = self.path(day);
+        if path.exists() {
+            return Ok(Fetched::Cached(path));
+        }
+
+        let session = self.session.as_ref().ok_or(FetchError::NoSession)?;
+        let url = format!("{}/day/{}/input", self.base_url, day);
+        let input = ureq::get(&url)
+            .header("Cookie", &format!("session={}", session))
+            .header("User-Agent", USER_AGENT)
+            .call()
+            .and_then(|response| response.into_body().read_to_string())
+            .map_err(|e| FetchError::Http(format!("{}: {}", url, e)))?;
+        if input.trim().is_empty() {
+            return Err(FetchError::Http(format!("{}: The input is empty", url)));
+        }
+
+        let io_error = |path: &Path| {
+            let path = path.to_path_buf();
+            move |e| FetchError::Io(path, e)
+        };
+        fs::create_dir_all(&self.dir).map_err(io_error(&self.dir))?;
+        let partial = path.with_extension("txt.part");
+        fs::write(&partial, input).map_err(io_error(&partial))?;
+        fs::rename(&partial, &path).map_err(io_error(&path))?;
+        Ok(Fetched::Downloaded(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fetch::{FetchError, Fetched, Fetcher};
+    use std::env;
+    use std::fs;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    /// The path and cookie of each request the mock server has seen.
+    type Requests = Arc<Mutex<Vec<(String, String)>>>;
+
+    /// A stand-in for the puzzle site on localhost, which answers `/2020/day/3/input` for the session `abc`. Returns
+    /// its base URL.
+    fn mock_server() -> (String, Requests) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/2020", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut head = BufReader::new(&stream)
+                    .lines()
+                    .map(|line| line.unwrap())
+                    .take_while(|line| !line.is_empty());
+                let path = head.next().unwrap().split(' ').nth(1).unwrap().to_string();
+                let cookie = head
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        Some(value.trim().to_string())
+                            .filter(|_| name.eq_ignore_ascii_case("cookie"))
+                    })
+                    .unwrap_or_default();
+                let (status, body) = match (path.as_str(), cookie.as_str()) {
+                    ("/2020/day/3/input", "session=abc") => ("200 OK", "..#\n#..\n"),
+                    ("/2020/day/3/input", _) => {
+                        ("400 Bad Request", "Puzzle inputs differ by user.")
+                    }
+                    _ => ("404 Not Found", "404 Not Found"),
+                };
+                seen.lock().unwrap().push((path, cookie));
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        (base_url, requests)
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("aoc-fetch-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_fetch() {
+        let (base_url, requests) = mock_server();
+        let dir = temp_dir("cache");
+        let fetcher = Fetcher::new(&base_url, Some("abc".to_string()), &dir);
+
+        let path = dir.join("day3.txt");
+        assert_eq!(fetcher.fetch(3).unwrap(), Fetched::Downloaded(path.clone()));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "..#\n#..\n");
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![("/2020/day/3/input".to_string(), "session=abc".to_string())]
+        );
+
+        // Cached inputs are never downloaded again, even without a session.
+        assert_eq!(fetcher.fetch(3).unwrap(), Fetched::Cached(path.clone()));
+        let offline = Fetcher::new(&base_url, None, &dir);
+        assert_eq!(offline.fetch(3).unwrap(), Fetched::Cached(path));
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fetch_errors() {
+        let (base_url, requests) = mock_server();
+        let dir = temp_dir("errors");
+
+        let offline = Fetcher::new(&base_url, None, &dir);
+        assert!(matches!(offline.fetch(3), Err(FetchError::NoSession)));
+        assert!(matches!(offline.fetch(26), Err(FetchError::InvalidDay(26))));
+        assert!(requests.lock().unwrap().is_empty());
+
+        let wrong_session = Fetcher::new(&base_url, Some("xyz".to_string()), &dir);
+        let error = wrong_session.fetch(3).unwrap_err().to_string();
+        assert!(error.contains("/2020/day/3/input"), "{}", error);
+        assert!(error.contains("400"), "{}", error);
+        assert!(matches!(wrong_session.fetch(4), Err(FetchError::Http(_))));
+        assert!(!dir.join("day3.txt").exists());
+        assert!(!dir.join("day4.txt").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
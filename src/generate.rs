@@ -0,0 +1,1063 @@
+//! Random puzzle inputs for stress testing. Every generator takes a seeded `Rng` and a size, and returns an input in
+//! the format of `input/dayN.txt` that the day's parser accepts. Where a part needs the input to have a particular
+//! shape to have an answer, such as a pair summing to 2020 on day 1 or a loop on day 8, that shape is planted.
+//!
+//! The size is the number of lines or records, except for the grid days where it is the side of the grid, day 20
+//! where it is the number of tiles along each side of the image, and day 25 where it is the largest loop size.
+//! Sizes are limited where the puzzle format needs it: day 1 has at most 1011 entries because no other pair may sum to
+//! 2020, day 5 has at most 1000 of the 1024 seats that a boarding pass can name, day 9 stops early once the next number
+//! would not fit in a `u64`, and day 23 has 4 to 9 cups because its labels are single digits.
+use crate::grid::Grid;
+use itertools::Itertools;
+use std::collections::{HashSet, VecDeque};
+use std::convert::TryFrom;
+use std::iter;
+
+/// A SplitMix64 generator, so that a seed gives the same input on every platform.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number from 0 to `n - 1`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "Cannot pick a number below 0");
+        self.next_u64() % n
+    }
+
+    /// A number from `min` to `max`, inclusive.
+    pub fn between(&mut self, min: i64, max: i64) -> i64 {
+        min + self.below((max - min + 1) as u64) as i64
+    }
+
+    /// True with probability `p`.
+    pub fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i as u64 + 1) as usize);
+        }
+    }
+
+    /// `count` different numbers from 0 to `n - 1`, in random order.
+    pub fn sample(&mut self, n: usize, count: usize) -> Vec<usize> {
+        let mut items = (0..n).collect_vec();
+        self.shuffle(&mut items);
+        items.truncate(count);
+        items
+    }
+
+    fn word(&mut self, min: usize, max: usize) -> String {
+        let len = self.between(min as i64, max as i64);
+        (0..len).map(|_| self.letter()).collect()
+    }
+
+    fn letter(&mut self) -> char {
+        (b'a' + self.below(26) as u8) as char
+    }
+}
+
+/// Generates an input for `day` from `seed`, or `None` if there is no generator for the day.
+pub fn generate(day: u8, size: usize, seed: u64) -> Option<String> {
+    let rng = &mut Rng::new(seed);
+    let input = match day {
+        1 => day1(rng, size),
+        2 => day2(rng, size),
+        3 => day3(rng, size),
+        4 => day4(rng, size),
+        5 => day5(rng, size),
+        6 => day6(rng, size),
+        7 => day7(rng, size),
+        8 => day8(rng, size),
+        9 => day9(rng, size),
+        10 => day10(rng, size),
+        11 => day11(rng, size),
+        12 => day12(rng, size),
+        13 => day13(rng, size),
+        14 => day14(rng, size),
+        15 => day15(rng, size),
+        16 => day16(rng, size),
+        17 => day17(rng, size),
+        18 => day18(rng, size),
+        19 => day19(rng, size),
+        20 => day20(rng, size),
+        21 => day21(rng, size),
+        22 => day22(rng, size),
+        23 => day23(rng, size),
+        24 => day24(rng, size),
+        25 => day25(rng, size),
+        _ => return None,
+    };
+    Some(input)
+}
+
+fn grid(rng: &mut Rng, height: usize, width: usize, p: f64, set: char, unset: char) -> String {
+    Grid::from_fn(height, width, |_| rng.chance(p)).render(|cell| if *cell { set } else { unset })
+}
+
+/// Expense report entries with exactly one pair and at least one triple summing to 2020.
+pub fn day1(rng: &mut Rng, size: usize) -> String {
+    let mut entries = HashSet::new();
+    let first = rng.between(1, 1000) as u32;
+    let second = rng.between(1, 1000) as u32;
+    let triple = [first, second, 2020 - first - second];
+    let pair = loop {
+        let n = rng.between(1, 2019) as u32;
+        if n != 1010 && triple.iter().all(|t| *t != n && *t != 2020 - n) {
+            break [n, 2020 - n];
+        }
+    };
+    entries.extend(triple.iter().chain(pair.iter()).copied());
+
+    // Each filler rules out the number that would pair with it, so only about half of 1 to 2019 can be entries.
+    let mut entries = entries.into_iter().collect_vec();
+    let mut fillers = (1..2020).collect_vec();
+    rng.shuffle(&mut fillers);
+    for n in fillers {
+        if entries.len() >= size {
+            break;
+        }
+        if !entries.contains(&n) && !entries.contains(&(2020 - n)) {
+            entries.push(n);
+        }
+    }
+    rng.shuffle(&mut entries);
+    entries.iter().map(|n| format!("{}\n", n)).collect()
+}
+
+/// Password policies and passwords, where about half the letters are the policy's letter.
+pub fn day2(rng: &mut Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| {
+            let min = rng.between(1, 5);
+            let max = rng.between(min, min + 10);
+            let letter = rng.letter();
+            let len = rng.between(1, max + 3);
+            let password: String = (0..len)
+                .map(|_| {
+                    if rng.chance(0.5) {
+                        letter
+                    } else {
+                        rng.letter()
+                    }
+                })
+                .collect();
+            format!("{}-{} {}: {}\n", min, max, letter, password)
+        })
+        .collect()
+}
+
+/// A map `size` rows high and 31 columns wide.
+pub fn day3(rng: &mut Rng, size: usize) -> String {
+    grid(rng, size.max(1), 31, 0.2, '#', '.')
+}
+
+/// Passports with some fields missing and some values out of range, spread over one to three lines each.
+pub fn day4(rng: &mut Rng, size: usize) -> String {
+    const EYE_COLORS: [&str; 7] = ["amb", "blu", "brn", "grn", "gry", "hzl", "oth"];
+    let passports = (0..size.max(1))
+        .map(|_| {
+            let mut fields = Vec::new();
+            for field in ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid", "cid"].iter() {
+                let p = if *field == "cid" { 0.5 } else { 0.9 };
+                if !rng.chance(p) {
+                    continue;
+                }
+                let valid = rng.chance(0.8);
+                let value = match (*field, valid) {
+                    ("byr", true) => rng.between(1920, 2002).to_string(),
+                    ("byr", false) => rng.between(1900, 1919).to_string(),
+                    ("iyr", true) => rng.between(2010, 2020).to_string(),
+                    ("iyr", false) => rng.between(2000, 2009).to_string(),
+                    ("eyr", true) => rng.between(2020, 2030).to_string(),
+                    ("eyr", false) => rng.between(2031, 2040).to_string(),
+                    ("hgt", true) if rng.chance(0.5) => format!("{}cm", rng.between(150, 193)),
+                    ("hgt", true) => format!("{}in", rng.between(59, 76)),
+                    ("hgt", false) => rng.between(50, 200).to_string(),
+                    ("hcl", true) => format!("#{:06x}", rng.below(1 << 24)),
+                    ("hcl", false) => format!("{:06x}", rng.below(1 << 24)),
+                    ("ecl", true) => rng.choose(&EYE_COLORS).to_string(),
+                    ("ecl", false) => rng.word(3, 3),
+                    ("pid", true) => format!("{:09}", rng.below(1_000_000_000)),
+                    ("pid", false) => format!("{:08}", rng.below(100_000_000)),
+                    _ => rng.between(100, 350).to_string(),
+                };
+                fields.push(format!("{}:{}", field, value));
+            }
+            rng.shuffle(&mut fields);
+            let mut passport = String::new();
+            for (i, field) in fields.iter().enumerate() {
+                if i > 0 {
+                    passport.push(if rng.chance(0.3) { '\n' } else { ' ' });
+                }
+                passport.push_str(field);
+            }
+            passport + "\n"
+        })
+        .collect_vec();
+    passports.join("\n")
+}
+
+/// Boarding passes for a block of consecutive seats with one seat missing in the middle.
+pub fn day5(rng: &mut Rng, size: usize) -> String {
+    let count = size.clamp(3, 1000);
+    let start = rng.below((1024 - count) as u64) as usize;
+    let missing = start + rng.between(1, count as i64 - 2) as usize;
+    let mut seats = (start..=start + count)
+        .filter(|seat| *seat != missing)
+        .collect_vec();
+    rng.shuffle(&mut seats);
+    seats
+        .iter()
+        .map(|seat| {
+            let row: String = (3..10)
+                .rev()
+                .map(|bit| if seat & (1 << bit) != 0 { 'B' } else { 'F' })
+                .collect();
+            let column: String = (0..3)
+                .rev()
+                .map(|bit| if seat & (1 << bit) != 0 { 'R' } else { 'L' })
+                .collect();
+            format!("{}{}\n", row, column)
+        })
+        .collect()
+}
+
+/// Groups of one to five people, each answering a random subset of the questions that their group was asked.
+pub fn day6(rng: &mut Rng, size: usize) -> String {
+    let groups = (0..size.max(1))
+        .map(|_| {
+            let count = rng.between(1, 12) as usize;
+            let questions = rng.sample(26, count);
+            let people = rng.between(1, 5);
+            (0..people)
+                .map(|_| {
+                    let count = rng.between(1, questions.len() as i64) as usize;
+                    let mut answers = questions.clone();
+                    rng.shuffle(&mut answers);
+                    answers[..count]
+                        .iter()
+                        .map(|q| (b'a' + *q as u8) as char)
+                        .collect::<String>()
+                        + "\n"
+                })
+                .collect::<String>()
+        })
+        .collect_vec();
+    groups.join("\n")
+}
+
+/// Rules for `size` bags in layers, where bags only contain bags from deeper layers, so there are no cycles and the
+/// number of bags inside one stays small. Shiny gold is in the middle layer and some bags above it contain it.
+pub fn day7(rng: &mut Rng, size: usize) -> String {
+    const ADJECTIVES: [&str; 16] = [
+        "bright", "clear", "dark", "dim", "drab", "dull", "faded", "light", "mirrored", "muted",
+        "pale", "plaid", "posh", "shiny", "striped", "vibrant",
+    ];
+    const COLORS: [&str; 16] = [
+        "aqua", "beige", "black", "blue", "bronze", "coral", "crimson", "gold", "gray", "green",
+        "indigo", "lime", "maroon", "olive", "red", "violet",
+    ];
+    const LAYERS: usize = 6;
+
+    let count = size.max(2).min(ADJECTIVES.len() * COLORS.len());
+    let mut bags = vec!["shiny gold".to_string()];
+    for i in rng.sample(ADJECTIVES.len() * COLORS.len(), count + 1) {
+        let bag = format!(
+            "{} {}",
+            ADJECTIVES[i / COLORS.len()],
+            COLORS[i % COLORS.len()]
+        );
+        if bags.len() < count && bag != bags[0] {
+            bags.push(bag);
+        }
+    }
+    let layers = bags
+        .iter()
+        .enumerate()
+        .map(|(i, _)| {
+            if i == 0 {
+                LAYERS / 2
+            } else {
+                rng.below(LAYERS as u64) as usize
+            }
+        })
+        .collect_vec();
+
+    let mut rules = bags
+        .iter()
+        .enumerate()
+        .map(|(i, bag)| {
+            let deeper = (0..bags.len())
+                .filter(|j| layers[*j] > layers[i])
+                .collect_vec();
+            let mut contents = Vec::new();
+            if !deeper.is_empty() {
+                for _ in 0..rng.between(0, 3) {
+                    contents.push(*rng.choose(&deeper));
+                }
+                if layers[i] < layers[0] && rng.chance(0.3) {
+                    contents.push(0);
+                }
+            }
+            let contents = contents
+                .into_iter()
+                .unique()
+                .map(|j| {
+                    let n = rng.between(1, 5);
+                    format!("{} {} bag{}", n, bags[j], if n == 1 { "" } else { "s" })
+                })
+                .join(", ");
+            if contents.is_empty() {
+                format!("{} bags contain no other bags.\n", bag)
+            } else {
+                format!("{} bags contain {}.\n", bag, contents)
+            }
+        })
+        .collect_vec();
+    rng.shuffle(&mut rules);
+    rules.concat()
+}
+
+/// A program that only jumps forwards, except for one jump backwards on its path that makes it loop. Flipping that
+/// jump to a nop makes it terminate.
+pub fn day8(rng: &mut Rng, size: usize) -> String {
+    let len = size.max(2) as i64;
+    let mut program = (0..len)
+        .map(|i| match rng.below(10) {
+            0..=3 => ("acc", rng.between(-50, 50)),
+            4..=6 => ("nop", rng.between(-i, len - i)),
+            _ => ("jmp", rng.between(1, (len - i).min(5))),
+        })
+        .collect_vec();
+
+    let mut path = Vec::new();
+    let mut i = 0;
+    while i < len {
+        path.push(i);
+        i += match program[i as usize] {
+            ("jmp", offset) => offset,
+            _ => 1,
+        };
+    }
+    let from = rng.below(path.len() as u64) as usize;
+    let to = rng.below(from as u64 + 1) as usize;
+    program[path[from] as usize] = ("jmp", path[to] - path[from]);
+
+    program
+        .iter()
+        .map(|(op, arg)| format!("{} {:+}\n", op, arg))
+        .collect()
+}
+
+/// A preamble of 25 numbers, then numbers that are each the sum of two of the 25 before, except for one invalid
+/// number that is the sum of a contiguous range. Stops early if the next number would not fit in a `u64`, after planting
+/// the invalid number if it is not in yet.
+pub fn day9(rng: &mut Rng, size: usize) -> String {
+    const PREAMBLE: usize = 25;
+    let len = size.max(PREAMBLE + 1);
+    let mut numbers = rng
+        .sample(50, PREAMBLE)
+        .iter()
+        .map(|n| *n as u64 + 1)
+        .collect_vec();
+    let invalid_at = rng.between((PREAMBLE + (len - PREAMBLE) / 2) as i64, len as i64 - 1) as usize;
+
+    while numbers.len() < len {
+        let window = &numbers[numbers.len() - PREAMBLE..];
+        let is_sum = |n: u64| {
+            window
+                .iter()
+                .tuple_combinations()
+                .any(|(a, b)| a != b && a.checked_add(*b) == Some(n))
+        };
+        let (a, b) = loop {
+            let a = *rng.choose(window);
+            let b = *rng.choose(window);
+            if a != b {
+                break (a, b);
+            }
+        };
+        let next = if numbers.len() == invalid_at
+            || (a.checked_add(b).is_none() && numbers.len() < invalid_at)
+        {
+            // Nearly every range sum will do, so try random ranges before going through all of them in order.
+            let count = numbers.len();
+            let prefix_sums = iter::once(0)
+                .chain(numbers.iter().scan(0u128, |sum, n| {
+                    *sum += *n as u128;
+                    Some(*sum)
+                }))
+                .collect_vec();
+            let random_ranges = (0..1000).map(|_| {
+                let i = rng.below(count as u64 - 1) as usize;
+                (i, rng.between(i as i64 + 2, count as i64) as usize)
+            });
+            let all_ranges = (0..count - 1).flat_map(|i| (i + 2..=count).map(move |j| (i, j)));
+            random_ranges
+                .chain(all_ranges)
+                .filter_map(|(i, j)| u64::try_from(prefix_sums[j] - prefix_sums[i]).ok())
+                .find(|n| !is_sum(*n))
+                .expect("Some range sums to a number that is not the sum of two in the window")
+        } else {
+            match a.checked_add(b) {
+                Some(next) => next,
+                None => break,
+            }
+        };
+        numbers.push(next);
+    }
+    numbers.iter().map(|n| format!("{}\n", n)).collect()
+}
+
+/// Adapters that differ by one to three jolts, in runs of ones broken by threes.
+pub fn day10(rng: &mut Rng, size: usize) -> String {
+    let mut adapters: Vec<u64> = Vec::new();
+    let mut joltage = 0;
+    while adapters.len() < size.max(1) {
+        joltage += match rng.below(10) {
+            0..=5 => 1,
+            6 => 2,
+            _ => 3,
+        };
+        adapters.push(joltage);
+    }
+    rng.shuffle(&mut adapters);
+    adapters.iter().map(|n| format!("{}\n", n)).collect()
+}
+
+/// A square waiting room with some floor.
+pub fn day11(rng: &mut Rng, size: usize) -> String {
+    grid(rng, size.max(1), size.max(1), 0.8, 'L', '.')
+}
+
+/// Navigation instructions, with turns in multiples of 90 degrees.
+pub fn day12(rng: &mut Rng, size: usize) -> String {
+    (0..size)
+        .map(|_| match rng.below(7) {
+            0..=3 => format!(
+                "{}{}\n",
+                rng.choose(&['N', 'S', 'E', 'W']),
+                rng.between(1, 100)
+            ),
+            4 => format!("{}{}\n", rng.choose(&['L', 'R']), rng.between(1, 3) * 90),
+            _ => format!("F{}\n", rng.between(1, 100)),
+        })
+        .collect()
+}
+
+/// A timestamp and `size` slots, some of them with buses. The bus numbers are distinct primes, so that the contest has a
+/// solution.
+pub fn day13(rng: &mut Rng, size: usize) -> String {
+    let mut primes = (11..1000u64)
+        .filter(|n| (2..*n).take_while(|d| d * d <= *n).all(|d| n % d != 0))
+        .collect_vec();
+    rng.shuffle(&mut primes);
+    let slots = size.max(1);
+    let buses = primes.into_iter().take(slots).collect_vec();
+
+    let mut schedule = vec!["x".to_string(); slots];
+    for (bus, slot) in buses.iter().zip(rng.sample(slots, buses.len())) {
+        schedule[slot] = bus.to_string();
+    }
+    format!(
+        "{}\n{}\n",
+        rng.between(100_000, 10_000_000),
+        schedule.join(",")
+    )
+}
+
+/// Masks with at most nine floating bits, each followed by one to eight writes.
+pub fn day14(rng: &mut Rng, size: usize) -> String {
+    let mut lines = Vec::new();
+    while lines.len() < size.max(2) {
+        let count = rng.between(0, 9) as usize;
+        let floating = rng.sample(36, count);
+        let mask: String = (0..36)
+            .map(|i| {
+                if floating.contains(&i) {
+                    'X'
+                } else if rng.chance(0.5) {
+                    '1'
+                } else {
+                    '0'
+                }
+            })
+            .collect();
+        lines.push(format!("mask = {}", mask));
+        for _ in 0..rng.between(1, 8) {
+            lines.push(format!(
+                "mem[{}] = {}",
+                rng.below(1 << 16),
+                rng.below(1 << 36)
+            ));
+        }
+    }
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// `size` different starting numbers.
+pub fn day15(rng: &mut Rng, size: usize) -> String {
+    let count = size.max(1);
+    rng.sample(count * 3, count).iter().join(",") + "\n"
+}
+
+/// Twenty fields with nested ranges, so that the values at one index of the tickets fit one more field than those at
+/// the index before, and the fields can only be assigned in one way. About a fifth of the nearby tickets have a value
+/// that is invalid for every field.
+pub fn day16(rng: &mut Rng, size: usize) -> String {
+    const NAMES: [&str; 20] = [
+        "departure location",
+        "departure station",
+        "departure platform",
+        "departure track",
+        "departure date",
+        "departure time",
+        "arrival location",
+        "arrival station",
+        "arrival platform",
+        "arrival track",
+        "class",
+        "duration",
+        "price",
+        "route",
+        "row",
+        "seat",
+        "train",
+        "type",
+        "wagon",
+        "zone",
+    ];
+
+    // Field `f` accepts the values of ranks 0 to `rank[f]`, and the values of rank `r` are 10 * (r + 1) to
+    // 10 * (r + 1) + 9.
+    let mut rank = (0..NAMES.len()).collect_vec();
+    rng.shuffle(&mut rank);
+    let mut columns = (0..NAMES.len()).collect_vec();
+    rng.shuffle(&mut columns);
+    let ticket = |rng: &mut Rng, valid: bool| {
+        let mut values = columns
+            .iter()
+            .map(|r| rng.between(10 * (*r as i64 + 1), 10 * (*r as i64 + 1) + 9))
+            .collect_vec();
+        if !valid {
+            values[rng.below(NAMES.len() as u64) as usize] = rng.between(500, 899);
+        }
+        values.iter().join(",")
+    };
+
+    let mut lines = NAMES
+        .iter()
+        .zip(rank.iter())
+        .map(|(name, r)| {
+            let second = 900 + 10 * *r as i64;
+            format!(
+                "{}: 1-{} or {}-{}",
+                name,
+                10 * (r + 1) + 9,
+                second,
+                second + 5
+            )
+        })
+        .collect_vec();
+    lines.push(String::new());
+    lines.push("your ticket:".to_string());
+    lines.push(ticket(rng, true));
+    lines.push(String::new());
+    lines.push("nearby tickets:".to_string());
+    for i in 0..size.max(1) {
+        let valid = i == 0 || !rng.chance(0.2);
+        lines.push(ticket(rng, valid));
+    }
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// A square initial slice of cubes.
+pub fn day17(rng: &mut Rng, size: usize) -> String {
+    grid(rng, size.max(1), size.max(1), 0.4, '#', '.')
+}
+
+/// Expressions with at most twelve digits each, nested up to three levels deep.
+pub fn day18(rng: &mut Rng, size: usize) -> String {
+    fn expression(rng: &mut Rng, depth: usize, digits: &mut usize) -> String {
+        let terms = rng.between(2, 4);
+        let mut res = String::new();
+        for i in 0..terms {
+            if i > 0 {
+                res.push_str(if rng.chance(0.5) { " + " } else { " * " });
+            }
+            if depth < 3 && *digits >= 4 && rng.chance(0.3) {
+                res.push('(');
+                res.push_str(&expression(rng, depth + 1, digits));
+                res.push(')');
+            } else if *digits > 0 || i == 0 {
+                *digits = digits.saturating_sub(1);
+                res.push_str(&rng.between(1, 9).to_string());
+            } else {
+                res.truncate(res.len() - 3);
+                break;
+            }
+        }
+        res
+    }
+
+    (0..size)
+        .map(|_| expression(rng, 0, &mut 12) + "\n")
+        .collect()
+}
+
+/// A grammar where rules 42 and 31 match strings of eight 'a's and 'b's, with rule 0: 8 11 as in the puzzle, and
+/// messages that are either built from rules 42 and 31 or random.
+pub fn day19(rng: &mut Rng, size: usize) -> String {
+    const DEPTH: usize = 3;
+
+    struct Grammar {
+        rules: Vec<(u64, Vec<Vec<u64>>)>,
+        ids: Vec<u64>,
+    }
+
+    impl Grammar {
+        /// Adds a rule that matches strings of length 2^depth and returns its id.
+        fn build(&mut self, rng: &mut Rng, depth: usize, id: Option<u64>) -> u64 {
+            if depth == 0 {
+                return if rng.chance(0.5) { 1 } else { 2 };
+            }
+            let id = id.unwrap_or_else(|| self.ids.pop().unwrap());
+            let alternatives = (0..2)
+                .map(|_| {
+                    vec![
+                        self.build(rng, depth - 1, None),
+                        self.build(rng, depth - 1, None),
+                    ]
+                })
+                .collect_vec();
+            self.rules.push((id, alternatives));
+            id
+        }
+
+        fn message(&self, rng: &mut Rng, id: u64, res: &mut String) {
+            match id {
+                1 => res.push('a'),
+                2 => res.push('b'),
+                _ => {
+                    let (_, alternatives) = self.rules.iter().find(|(i, _)| *i == id).unwrap();
+                    for sub_rule in rng.choose(alternatives) {
+                        self.message(rng, *sub_rule, res);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut ids = (3..200)
+        .filter(|id| ![8, 11, 31, 42].contains(id))
+        .collect_vec();
+    rng.shuffle(&mut ids);
+    let mut grammar = Grammar {
+        rules: vec![
+            (0, vec![vec![8, 11]]),
+            (8, vec![vec![42]]),
+            (11, vec![vec![42, 31]]),
+        ],
+        ids,
+    };
+    grammar.build(rng, DEPTH, Some(42));
+    grammar.build(rng, DEPTH, Some(31));
+
+    let mut lines = grammar
+        .rules
+        .iter()
+        .map(|(id, alternatives)| {
+            let alternatives = alternatives
+                .iter()
+                .map(|seq| seq.iter().join(" "))
+                .join(" | ");
+            format!("{}: {}", id, alternatives)
+        })
+        .collect_vec();
+    lines.push("1: \"a\"".to_string());
+    lines.push("2: \"b\"".to_string());
+    rng.shuffle(&mut lines);
+    lines.push(String::new());
+
+    for _ in 0..size {
+        let mut message = String::new();
+        if rng.chance(0.5) {
+            let thirty_ones = rng.between(1, 3);
+            let forty_twos = rng.between(thirty_ones + 1, thirty_ones + 4);
+            for _ in 0..forty_twos {
+                grammar.message(rng, 42, &mut message);
+            }
+            for _ in 0..thirty_ones {
+                grammar.message(rng, 31, &mut message);
+            }
+        } else {
+            let len = rng.between(2, 6) << DEPTH;
+            message = (0..len).map(|_| *rng.choose(&['a', 'b'])).collect();
+        }
+        lines.push(message);
+    }
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// The sea monster that day 20 looks for.
+const SEA_MONSTER: [&str; 3] = [
+    "                  # ",
+    "#    ##    ##    ###",
+    " #  #  #  #  #  #   ",
+];
+
+/// A random image with sea monsters anywhere in it, cut into `size` by `size` tiles that are then rotated and flipped.
+/// The borders between tiles are picked so that no two edges match unless they are neighbours in the image. Tiles are
+/// 10x10 pixels like the puzzle's, unless the image has more borders than eight pixels between the corners can keep
+/// apart, in which case the tiles grow.
+pub fn day20(rng: &mut Rng, size: usize) -> String {
+    day20_image(rng, size).0
+}
+
+/// Same as `day20`, also returning the image and the number of sea monsters planted in it.
+fn day20_image(rng: &mut Rng, size: usize) -> (String, Grid<bool>, usize) {
+    let dim = size.max(1);
+    // Edges are a corner pixel, the pixels between the corners and another corner pixel. Reversed edges count as the
+    // same edge.
+    let corners = Grid::from_fn(dim + 1, dim + 1, |_| rng.chance(0.5));
+    let between = edge_pixels(&corners);
+    let (side, image_dim) = (between + 2, dim * between);
+
+    let mut image = Grid::from_fn(image_dim, image_dim, |_| rng.chance(0.25));
+    let (height, width) = (SEA_MONSTER.len(), SEA_MONSTER[0].len());
+    let mut monsters: Vec<(usize, usize)> = Vec::new();
+    for _ in 0..dim * 2 {
+        if image_dim < width {
+            break;
+        }
+        let r = rng.below((image_dim - height + 1) as u64) as usize;
+        let c = rng.below((image_dim - width + 1) as u64) as usize;
+        if monsters.iter().all(|(mr, mc)| {
+            r + height <= *mr || *mr + height <= r || c + width <= *mc || *mc + width <= c
+        }) {
+            for (dr, line) in SEA_MONSTER.iter().enumerate() {
+                for (dc, ch) in line.chars().enumerate() {
+                    image[(r + dr, c + dc)] = image[(r + dr, c + dc)] || ch == '#';
+                }
+            }
+            monsters.push((r, c));
+        }
+    }
+
+    let mut used = HashSet::new();
+    let mut edge = |rng: &mut Rng, first: bool, last: bool| loop {
+        let mut pixels = vec![first];
+        pixels.extend((0..between).map(|_| rng.chance(0.5)));
+        pixels.push(last);
+        let reversed = pixels.iter().rev().copied().collect_vec();
+        if pixels != reversed && !used.contains(&pixels) {
+            used.insert(reversed);
+            used.insert(pixels.clone());
+            break pixels;
+        }
+    };
+    let horizontal = Grid::from_fn(dim + 1, dim, |(r, c)| {
+        edge(rng, corners[(r, c)], corners[(r, c + 1)])
+    });
+    let vertical = Grid::from_fn(dim, dim + 1, |(r, c)| {
+        edge(rng, corners[(r, c)], corners[(r + 1, c)])
+    });
+
+    // Tile numbers have four digits while there are enough of them.
+    let tile_nos = rng.sample((dim * dim).max(9000), dim * dim);
+    let tiles = (0..dim)
+        .cartesian_product(0..dim)
+        .zip(tile_nos)
+        .map(|((r, c), tile_no)| {
+            let tile = Grid::from_fn(side, side, |(tr, tc)| match (tr, tc) {
+                (0, _) => horizontal[(r, c)][tc],
+                (_, 0) => vertical[(r, c)][tr],
+                (tr, _) if tr == side - 1 => horizontal[(r + 1, c)][tc],
+                (_, tc) if tc == side - 1 => vertical[(r, c + 1)][tr],
+                _ => image[(r * between + tr - 1, c * between + tc - 1)],
+            });
+            format!(
+                "Tile {}:\n{}",
+                tile_no + 1000,
+                rng.choose(&tile.orientations())
+                    .render(|p| if *p { '#' } else { '.' })
+            )
+        })
+        .collect_vec();
+    (tiles.join("\n"), image, monsters.len())
+}
+
+/// The number of pixels between the corners of each tile edge: eight, or more if eight cannot give every border that
+/// runs between the `corners` its own pattern. A border with equal corner pixels cannot be a palindrome, and neither it
+/// nor its reverse may be used twice. A border with different corner pixels is never a palindrome, and its reverse has
+/// the corners swapped, so those borders share their patterns between both orders of the corners.
+fn edge_pixels(corners: &Grid<bool>) -> usize {
+    let (rows, columns) = (corners.height(), corners.width());
+    let borders = (0..rows)
+        .cartesian_product(0..columns)
+        .flat_map(|(r, c)| {
+            let right = (c + 1 < columns).then_some(((r, c), (r, c + 1)));
+            let down = (r + 1 < rows).then_some(((r, c), (r + 1, c)));
+            right.into_iter().chain(down)
+        })
+        .map(|(a, b)| (corners[a], corners[b]))
+        .collect_vec();
+    let count = |ends: &[(bool, bool)]| borders.iter().filter(|b| ends.contains(b)).count();
+    let (off, on, mixed) = (
+        count(&[(false, false)]),
+        count(&[(true, true)]),
+        count(&[(false, true), (true, false)]),
+    );
+    (8usize..)
+        .find(|&pixels| {
+            let patterns = 1usize << pixels;
+            let unmirrored = (patterns - (1 << pixels.div_ceil(2))) / 2;
+            off <= unmirrored && on <= unmirrored && mixed <= patterns
+        })
+        .unwrap()
+}
+
+/// Foods with random ingredients, where each allergen is in one ingredient, and a food that lists an allergen
+/// contains its ingredient. Foods do not always list all their allergens. Every allergen is also listed by two foods
+/// that share nothing but its ingredient, so that there is exactly one way to assign the allergens.
+pub fn day21(rng: &mut Rng, size: usize) -> String {
+    const ALLERGENS: [&str; 8] = [
+        "dairy", "eggs", "fish", "nuts", "peanuts", "sesame", "soy", "wheat",
+    ];
+    const PINNING_WORDS: usize = 3;
+    let shared = ALLERGENS.len() + 20 + size;
+    let mut ingredients: Vec<String> = Vec::new();
+    while ingredients.len() < shared + 2 * ALLERGENS.len() * PINNING_WORDS {
+        let word = rng.word(3, 7);
+        if !ingredients.contains(&word) {
+            ingredients.push(word);
+        }
+    }
+
+    // Ingredient `a` contains allergen `a`, and ingredients from `shared` on are only used by one food each.
+    let mut foods = (0..2 * ALLERGENS.len())
+        .map(|i| {
+            let start = shared + i * PINNING_WORDS;
+            let mut food = (start..start + PINNING_WORDS).collect_vec();
+            food.push(i / 2);
+            (food, vec![i / 2])
+        })
+        .collect_vec();
+    for _ in 0..size {
+        let allergens = (0..ALLERGENS.len())
+            .filter(|_| rng.chance(0.3))
+            .collect_vec();
+        let mut food = allergens.clone();
+        for _ in 0..rng.between(3, 15) {
+            food.push(rng.below(shared as u64) as usize);
+        }
+        let listed = allergens
+            .into_iter()
+            .filter(|_| rng.chance(0.7))
+            .collect_vec();
+        foods.push((food.into_iter().unique().collect_vec(), listed));
+    }
+    rng.shuffle(&mut foods);
+
+    foods
+        .iter_mut()
+        .map(|(food, listed)| {
+            rng.shuffle(food);
+            let food = food.iter().map(|i| &ingredients[*i]).join(" ");
+            if listed.is_empty() {
+                format!("{}\n", food)
+            } else {
+                let listed = listed.iter().map(|a| ALLERGENS[*a]).join(", ");
+                format!("{} (contains {})\n", food, listed)
+            }
+        })
+        .collect()
+}
+
+/// Two decks of `size` cards each, shuffled until a game of plain Combat ends, as part 1 has no rule that stops a game
+/// whose rounds repeat.
+pub fn day22(rng: &mut Rng, size: usize) -> String {
+    let count = size.max(1);
+    loop {
+        let mut cards = (1..=2 * count as u64).collect_vec();
+        rng.shuffle(&mut cards);
+        let (deck1, deck2) = cards.split_at(count);
+
+        let mut decks = (
+            deck1.iter().copied().collect::<VecDeque<_>>(),
+            deck2.iter().copied().collect::<VecDeque<_>>(),
+        );
+        let mut rounds = 0;
+        while !decks.0.is_empty() && !decks.1.is_empty() && rounds < 100_000 * count {
+            let (card1, card2) = (decks.0.pop_front().unwrap(), decks.1.pop_front().unwrap());
+            if card1 > card2 {
+                decks.0.extend([card1, card2].iter());
+            } else {
+                decks.1.extend([card2, card1].iter());
+            }
+            rounds += 1;
+        }
+        if decks.0.is_empty() || decks.1.is_empty() {
+            return format!(
+                "Player 1:\n{}\n\nPlayer 2:\n{}\n",
+                deck1.iter().join("\n"),
+                deck2.iter().join("\n")
+            );
+        }
+    }
+}
+
+/// Between 4 and 9 cups in random order, as cups are labelled with single digits from 1 up.
+pub fn day23(rng: &mut Rng, size: usize) -> String {
+    let count = size.clamp(4, 9);
+    rng.sample(count, count).iter().map(|cup| cup + 1).join("") + "\n"
+}
+
+/// Paths of 1 to 20 steps.
+pub fn day24(rng: &mut Rng, size: usize) -> String {
+    const DIRECTIONS: [&str; 6] = ["e", "se", "sw", "w", "nw", "ne"];
+    (0..size.max(1))
+        .map(|_| {
+            let len = rng.between(1, 20);
+            (0..len)
+                .map(|_| *rng.choose(&DIRECTIONS))
+                .collect::<String>()
+                + "\n"
+        })
+        .collect()
+}
+
+/// Public keys for loop sizes from 1 to `size`.
+pub fn day25(rng: &mut Rng, size: usize) -> String {
+    let key = |loop_size: i64| (0..loop_size).fold(1u64, |value, _| value * 7 % 20_201_227);
+    let card = key(rng.between(1, size.max(1) as i64));
+    let door = key(rng.between(1, size.max(1) as i64));
+    format!("{}\n{}\n", card, door)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::day20::{arrange_tiles, corner_product, image_dim, parse_tiles, Day20};
+    use crate::generate::{day20_image, generate, Rng};
+    use crate::registry::solutions;
+    use crate::solution::{lines, Solution};
+
+    #[test]
+    fn test_rng() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        assert_eq!(
+            (0..5).map(|_| a.next_u64()).collect::<Vec<_>>(),
+            (0..5).map(|_| b.next_u64()).collect::<Vec<_>>()
+        );
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+        assert!((0..100).all(|_| (3..=5).contains(&a.between(3, 5))));
+
+        let mut sample = a.sample(10, 10);
+        sample.sort_unstable();
+        assert_eq!(sample, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_generate_is_deterministic() {
+        assert_eq!(generate(7, 50, 3), generate(7, 50, 3));
+        assert_ne!(generate(7, 50, 3), generate(7, 50, 4));
+        assert_eq!(generate(26, 10, 0), None);
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let day1 = generate(1, 1100, 0).unwrap();
+        assert_eq!(day1.lines().count(), 1011);
+        assert!(!generate(9, 2000, 7).unwrap().is_empty());
+        let day13 = generate(13, 40, 0).unwrap();
+        let buses = day13.lines().nth(1).unwrap().split(',');
+        assert_eq!(buses.filter(|bus| *bus != "x").count(), 40);
+    }
+
+    #[test]
+    fn test_every_day_parses() {
+        for solution in solutions() {
+            for (size, seed) in [(1, 1), (10, 2), (100, 3)].iter() {
+                let input = generate(solution.day(), *size, *seed).unwrap();
+                if let Err(e) = solution.parse(&input) {
+                    panic!("Day {} size {}: {}\n{}", solution.day(), size, e, input);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_solvers_find_planted_answers() {
+        // Days 15 and 23 always play millions of rounds in part 2, which is too slow for a debug build.
+        for solution in solutions().iter().filter(|s| ![15, 23].contains(&s.day())) {
+            let input = generate(solution.day(), 10, 7).unwrap();
+            for part in 1..=2 {
+                let answer = solution.run(part, &input).unwrap();
+                // Day 25 only has one puzzle.
+                if (solution.day(), part) != (25, 2) {
+                    assert!(answer.is_some(), "Day {} part {}", solution.day(), part);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_day20_corners() {
+        let input = generate(20, 4, 11).unwrap();
+        let tiles = parse_tiles(lines(&input)).unwrap();
+        let corners = [0, 3, 12, 15]
+            .iter()
+            .map(|i| tiles[*i].tile_no)
+            .product::<u64>();
+        let arrangement = arrange_tiles(&tiles, image_dim(&tiles)).unwrap();
+        assert_eq!(corner_product(&arrangement), Some(corners));
+    }
+
+    #[test]
+    fn test_day20_solves() {
+        // Every tile is oriented at random, and monsters can be anywhere, including against the edges of the image.
+        for (size, seed) in [(3, 1), (3, 2), (3, 3), (4, 4), (5, 5), (12, 6)].iter() {
+            let (input, image, monsters) = day20_image(&mut Rng::new(*seed), *size);
+            let tiles = parse_tiles(lines(&input)).unwrap();
+            assert_eq!(tiles[0].tile.width(), 10);
+            let corners = [0, size - 1, size * (size - 1), size * size - 1]
+                .iter()
+                .map(|i| tiles[*i].tile_no)
+                .product::<u64>();
+            let roughness = image.iter().filter(|(_, p)| **p).count() - monsters * 15;
+            assert_eq!(Day20.part1(&tiles), Some(corners.to_string()));
+            assert_eq!(Day20.part2(&tiles), Some(roughness.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_day20_tile_size() {
+        // Ten pixels per edge are not enough to give every border of a 40x40 image its own pattern.
+        let input = generate(20, 40, 1).unwrap();
+        let tiles = parse_tiles(lines(&input)).unwrap();
+        assert_eq!(tiles.len(), 1600);
+        assert!(tiles[0].tile.width() > 10);
+        assert_eq!(generate(20, 1, 1).unwrap().lines().count(), 11);
+    }
+}
@@ -0,0 +1,380 @@
+use crate::error::ParseError;
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+/// A (row, column) position in a grid.
+pub type Pos = (usize, usize);
+
+/// Row and column steps to the four orthogonal neighbours, clockwise from up.
+pub const DIRECTIONS4: [(i64, i64); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+/// Row and column steps to all eight neighbours, clockwise from up.
+pub const DIRECTIONS8: [(i64, i64); 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+
+/// A fixed-size rectangular grid stored row by row. Positions are (row, column) with (0, 0) at the top left.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    height: usize,
+    width: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Builds a grid from its rows, which must all have the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Grid<T> {
+        let height = rows.len();
+        let width = rows.first().map_or(0, |row| row.len());
+        assert!(
+            rows.iter().all(|row| row.len() == width),
+            "All rows of a grid must have the same length"
+        );
+        Grid {
+            height,
+            width,
+            cells: rows.into_iter().flatten().collect(),
+        }
+    }
+
+    pub fn from_fn(height: usize, width: usize, mut f: impl FnMut(Pos) -> T) -> Grid<T> {
+        let cells = (0..height)
+            .flat_map(|r| (0..width).map(move |c| (r, c)))
+            .map(&mut f)
+            .collect();
+        Grid {
+            height,
+            width,
+            cells,
+        }
+    }
+
+    /// Parses one row per line, converting each character with `f`, which returns `None` for characters that are not
+    /// allowed. Errors are reported for `day`.
+    pub fn parse(
+        day: u8,
+        text: &str,
+        mut f: impl FnMut(char) -> Option<T>,
+    ) -> Result<Grid<T>, ParseError> {
+        let mut rows = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let mut row = Vec::new();
+            for (j, ch) in line.chars().enumerate() {
+                match f(ch) {
+                    Some(cell) => row.push(cell),
+                    None => {
+                        return Err(ParseError::new(
+                            day,
+                            i + 1,
+                            j + 1,
+                            format!("Unexpected character: '{}'", ch),
+                        ))
+                    }
+                }
+            }
+            if let Some(first) = rows.first().map(|first: &Vec<T>| first.len()) {
+                if row.len() != first {
+                    return Err(ParseError::new(
+                        day,
+                        i + 1,
+                        row.len().min(first) + 1,
+                        format!("Expected a row of {} cells", first),
+                    ));
+                }
+            } else if row.is_empty() {
+                return Err(ParseError::new(day, i + 1, 1, "Empty row"));
+            }
+            rows.push(row);
+        }
+        if rows.is_empty() {
+            return Err(ParseError::new(day, 1, 1, "Expected at least one row"));
+        }
+        Ok(Grid::from_rows(rows))
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        if pos.0 < self.height && pos.1 < self.width {
+            Some(&self.cells[pos.0 * self.width + pos.1])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        if pos.0 < self.height && pos.1 < self.width {
+            Some(&mut self.cells[pos.0 * self.width + pos.1])
+        } else {
+            None
+        }
+    }
+
+    /// The position one `step` away from `pos`, if it is inside the grid.
+    pub fn step(&self, pos: Pos, step: (i64, i64)) -> Option<Pos> {
+        let r = pos.0 as i64 + step.0;
+        let c = pos.1 as i64 + step.1;
+        if r >= 0 && c >= 0 && (r as usize) < self.height && (c as usize) < self.width {
+            Some((r as usize, c as usize))
+        } else {
+            None
+        }
+    }
+
+    /// All positions, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width;
+        (0..self.height).flat_map(move |r| (0..width).map(move |c| (r, c)))
+    }
+
+    /// All positions with their cells, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// The orthogonal neighbours of `pos` that are inside the grid.
+    pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        DIRECTIONS4
+            .iter()
+            .filter_map(move |step| self.step(pos, *step))
+    }
+
+    /// The orthogonal and diagonal neighbours of `pos` that are inside the grid.
+    pub fn neighbours8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        DIRECTIONS8
+            .iter()
+            .filter_map(move |step| self.step(pos, *step))
+    }
+
+    /// The positions seen looking from `pos` in the direction of `step`, up to the edge of the grid. `pos` itself is
+    /// not included.
+    pub fn ray(&self, pos: Pos, step: (i64, i64)) -> impl Iterator<Item = Pos> + '_ {
+        let mut current = pos;
+        std::iter::from_fn(move || {
+            current = self.step(current, step)?;
+            Some(current)
+        })
+    }
+
+    /// The first cell seen from `pos` in the direction of `step` that `is_visible` accepts. Cells that it rejects are
+    /// looked through.
+    pub fn first_visible(
+        &self,
+        pos: Pos,
+        step: (i64, i64),
+        is_visible: impl Fn(&T) -> bool,
+    ) -> Option<&T> {
+        self.ray(pos, step)
+            .map(|p| &self[p])
+            .find(|cell| is_visible(cell))
+    }
+
+    pub fn count(&self, f: impl Fn(&T) -> bool) -> usize {
+        self.cells.iter().filter(|cell| f(cell)).count()
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            height: self.height,
+            width: self.width,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Draws the grid one row per line, with a character for each cell.
+    pub fn render(&self, f: impl Fn(&T) -> char) -> String {
+        let mut res = String::with_capacity((self.width + 1) * self.height);
+        for row in self.rows() {
+            res.extend(row.iter().map(&f));
+            res.push('\n');
+        }
+        res
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn filled(height: usize, width: usize, value: T) -> Grid<T> {
+        Grid {
+            height,
+            width,
+            cells: vec![value; height * width],
+        }
+    }
+
+    /// Rotates the grid a quarter turn clockwise.
+    pub fn rotate(&self) -> Grid<T> {
+        Grid::from_fn(self.width, self.height, |(r, c)| {
+            self[(self.height - c - 1, r)].clone()
+        })
+    }
+
+    /// Flips the grid upside down.
+    pub fn flip(&self) -> Grid<T> {
+        Grid::from_fn(self.height, self.width, |(r, c)| {
+            self[(self.height - r - 1, c)].clone()
+        })
+    }
+
+    /// The eight combinations of rotations and flips, starting with the grid itself.
+    pub fn orientations(&self) -> Vec<Grid<T>> {
+        let mut res = Vec::with_capacity(8);
+        let mut grid = self.clone();
+        for flip in 0..2 {
+            if flip == 1 {
+                grid = grid.flip();
+            }
+            for _ in 0..4 {
+                let next = grid.rotate();
+                res.push(grid);
+                grid = next;
+            }
+        }
+        res
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("Position {:?} is outside the grid", pos))
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("Position {:?} is outside the grid", pos))
+    }
+}
+
+impl fmt::Display for Grid<char> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.render(|c| *c))
+    }
+}
+
+/// A grid is serialized as a list of rows.
+impl<T: Serialize> Serialize for Grid<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.rows())
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Grid<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Grid<T>, D::Error> {
+        let rows = Vec::<Vec<T>>::deserialize(deserializer)?;
+        let width = rows.first().map_or(0, |row| row.len());
+        if let Some(i) = rows.iter().position(|row| row.len() != width) {
+            return Err(de::Error::custom(format!(
+                "Row {} has {} cells, expected {}",
+                i + 1,
+                rows[i].len(),
+                width
+            )));
+        }
+        Ok(Grid::from_rows(rows))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ParseError;
+    use crate::grid::{Grid, DIRECTIONS8};
+    use itertools::Itertools;
+
+    fn parse(text: &str) -> Result<Grid<char>, ParseError> {
+        Grid::parse(
+            0,
+            text,
+            |c| if c == '#' || c == '.' { Some(c) } else { None },
+        )
+    }
+
+    #[test]
+    fn test_parse() {
+        let grid = parse("#..\n.#.\n").unwrap();
+        assert_eq!((grid.height(), grid.width()), (2, 3));
+        assert_eq!(grid[(1, 1)], '#');
+        assert_eq!(grid.get((2, 0)), None);
+        assert_eq!(grid.to_string(), "#..\n.#.\n");
+
+        assert_eq!(
+            parse("#..\n.x.").unwrap_err(),
+            ParseError::new(0, 2, 2, "Unexpected character: 'x'")
+        );
+        assert_eq!(
+            parse("#..\n.#").unwrap_err(),
+            ParseError::new(0, 2, 3, "Expected a row of 3 cells")
+        );
+        assert_eq!(parse("").unwrap_err().message, "Expected at least one row");
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = Grid::filled(3, 3, 0);
+        assert_eq!(grid.neighbours4((0, 0)).collect_vec(), vec![(0, 1), (1, 0)]);
+        assert_eq!(grid.neighbours8((0, 0)).count(), 3);
+        assert_eq!(grid.neighbours8((1, 1)).count(), 8);
+        assert_eq!(grid.step((0, 2), (0, 1)), None);
+    }
+
+    #[test]
+    fn test_ray() {
+        let grid = parse("#...\n....\n..#.\n....").unwrap();
+        assert_eq!(
+            grid.ray((0, 0), (1, 1)).collect_vec(),
+            vec![(1, 1), (2, 2), (3, 3)]
+        );
+        let seen = DIRECTIONS8
+            .iter()
+            .filter_map(|step| grid.first_visible((3, 3), *step, |c| *c == '#'))
+            .count();
+        assert_eq!(seen, 1);
+    }
+
+    #[test]
+    fn test_rotate_and_flip() {
+        let grid = parse("#..\n.#.").unwrap();
+        assert_eq!(grid.rotate().to_string(), ".#\n#.\n..\n");
+        assert_eq!(grid.flip().to_string(), ".#.\n#..\n");
+        assert_eq!(grid.rotate().rotate().rotate().rotate(), grid);
+
+        let orientations = grid.orientations();
+        assert_eq!(orientations.len(), 8);
+        assert_eq!(orientations.iter().unique().count(), 8);
+    }
+
+    #[test]
+    fn test_json() {
+        let grid = parse("#..\n.#.").unwrap();
+        let json = serde_json::to_string(&grid).unwrap();
+        assert_eq!(json, r##"[["#",".","."],[".","#","."]]"##);
+        assert_eq!(serde_json::from_str::<Grid<char>>(&json).unwrap(), grid);
+
+        let error = serde_json::from_str::<Grid<char>>(r##"[["#","."],["."]]"##).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Row 2 has 1 cells, expected 2"));
+    }
+}
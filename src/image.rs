@@ -0,0 +1,255 @@
+//! Draws the state of the grid puzzles as PPM or SVG images: the seating of day 11, the cube slices of day 17, the
+//! assembled image of day 20 with its sea monsters, and the hex floor of day 24. The simulations can also be written
+//! as numbered frames, one per generation.
+use crate::day11::{self, Position};
+use crate::day17;
+use crate::day20;
+use crate::day24;
+use crate::grid::Grid;
+use crate::solution::lines;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const WHITE: Rgb = Rgb(255, 255, 255);
+    pub const DARK_GRAY: Rgb = Rgb(48, 48, 48);
+    pub const GREEN: Rgb = Rgb(64, 192, 64);
+    pub const RED: Rgb = Rgb(220, 48, 48);
+    pub const SEA: Rgb = Rgb(16, 64, 128);
+    pub const WAVE: Rgb = Rgb(96, 160, 224);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A binary PPM (P6) image, with each cell drawn as a square of pixels.
+    Ppm,
+    /// An SVG image with one square per cell.
+    Svg,
+}
+
+impl Format {
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        match extension {
+            "ppm" => Some(Format::Ppm),
+            "svg" => Some(Format::Svg),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Ppm => "ppm",
+            Format::Svg => "svg",
+        }
+    }
+}
+
+/// Encodes the image with every cell drawn as a `scale` by `scale` square.
+pub fn encode(image: &Grid<Rgb>, format: Format, scale: usize) -> Vec<u8> {
+    match format {
+        Format::Ppm => to_ppm(image, scale),
+        Format::Svg => to_svg(image, scale).into_bytes(),
+    }
+}
+
+pub fn to_ppm(image: &Grid<Rgb>, scale: usize) -> Vec<u8> {
+    let (width, height) = (image.width() * scale, image.height() * scale);
+    let mut res = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    res.reserve(width * height * 3);
+    for row in image.rows() {
+        for _ in 0..scale {
+            for Rgb(r, g, b) in row {
+                for _ in 0..scale {
+                    res.extend_from_slice(&[*r, *g, *b]);
+                }
+            }
+        }
+    }
+    res
+}
+
+pub fn to_svg(image: &Grid<Rgb>, scale: usize) -> String {
+    let mut res = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" \
+         shape-rendering=\"crispEdges\">\n",
+        image.width() * scale,
+        image.height() * scale,
+        image.width(),
+        image.height()
+    );
+    for ((r, c), Rgb(red, green, blue)) in image.iter() {
+        res.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"1\" height=\"1\" fill=\"#{:02x}{:02x}{:02x}\"/>\n",
+            c, r, red, green, blue
+        ));
+    }
+    res.push_str("</svg>\n");
+    res
+}
+
+/// Writes the frames to `frame-0000.<ext>`, `frame-0001.<ext>` and so on in `dir`, creating it if needed. Returns the
+/// paths written.
+pub fn write_frames(
+    dir: &Path,
+    frames: &[Grid<Rgb>],
+    format: Format,
+    scale: usize,
+) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            let path = dir.join(format!("frame-{:04}.{}", i, format.extension()));
+            fs::write(&path, encode(frame, format, scale))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Draws the state of a day after each generation, ending with the state that answers `part`. Day 20 has a single
+/// frame, the assembled image with the sea monsters highlighted.
+pub fn frames(day: u8, part: u8, input: &str) -> Result<Vec<Grid<Rgb>>, String> {
+    let frames = match day {
+        11 => {
+            let layout = day11::parse_layout(input).map_err(|e| e.to_string())?;
+            day11::seating_rounds(&layout, part)
+                .iter()
+                .map(|round| {
+                    round.map(|p| match p {
+                        Position::Floor => Rgb::DARK_GRAY,
+                        Position::Empty => Rgb::GREEN,
+                        Position::Occupied => Rgb::RED,
+                    })
+                })
+                .collect()
+        }
+        17 => {
+            let active = day17::parse_slice(input).map_err(|e| e.to_string())?;
+            let frames = if part == 1 {
+                day17::slice_frames(&day17::boot_cycles::<3>(&active, 6))
+            } else {
+                day17::slice_frames(&day17::boot_cycles::<4>(&active, 6))
+            };
+            frames
+                .iter()
+                .map(|frame| {
+                    frame.map(|cube| match cube {
+                        Some(true) => Rgb::WHITE,
+                        Some(false) => Rgb::DARK_GRAY,
+                        None => Rgb::BLACK,
+                    })
+                })
+                .collect()
+        }
+        20 => {
+            let tiles = day20::parse_tiles(lines(input)).map_err(|e| e.to_string())?;
+            let arrangement = day20::arrange_tiles(&tiles, day20::image_dim(&tiles))
+                .ok_or("The tiles cannot be arranged")?;
+            let image =
+                day20::merge_tiles(&arrangement).ok_or("The tiles are too small to merge")?;
+            let (image, monsters) = day20::locate_sea_monsters(&image);
+            let image = Grid::from_fn(image.height(), image.width(), |pos| {
+                match (monsters.contains(&pos), image[pos]) {
+                    (true, _) => Rgb::RED,
+                    (false, '#') => Rgb::WAVE,
+                    _ => Rgb::SEA,
+                }
+            });
+            vec![image]
+        }
+        24 => {
+            let coords = day24::parse_coords(lines(input)).map_err(|e| e.to_string())?;
+            let turns = if part == 1 { 0 } else { 100 };
+            day24::floor_frames(&day24::floor_days(&coords, turns))
+                .iter()
+                .map(|frame| frame.map(|black| if *black { Rgb::BLACK } else { Rgb::WHITE }))
+                .collect()
+        }
+        _ => return Err(format!("Day {} has no images", day)),
+    };
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generate::{self, Rng};
+    use crate::grid::Grid;
+    use crate::image::{frames, to_ppm, to_svg, write_frames, Format, Rgb};
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_encode() {
+        let image = Grid::from_rows(vec![vec![Rgb::BLACK, Rgb(1, 2, 3)]]);
+        let ppm = to_ppm(&image, 2);
+        let header = b"P6\n4 2\n255\n";
+        assert_eq!(&ppm[..header.len()], header);
+        assert_eq!(ppm.len(), header.len() + 4 * 2 * 3);
+        assert_eq!(
+            &ppm[header.len()..header.len() + 12],
+            &[0, 0, 0, 0, 0, 0, 1, 2, 3, 1, 2, 3]
+        );
+
+        let svg = to_svg(&image, 10);
+        assert!(svg.contains("width=\"20\" height=\"10\" viewBox=\"0 0 2 1\""));
+        assert!(svg.contains("<rect x=\"1\" y=\"0\" width=\"1\" height=\"1\" fill=\"#010203\"/>"));
+        assert_eq!(Format::from_extension("svg"), Some(Format::Svg));
+        assert_eq!(Format::from_extension("png"), None);
+    }
+
+    #[test]
+    fn test_seating_frames() {
+        let example =
+            "L.LL.LL.LL\nLLLLLLL.LL\nL.L.L..L..\nLLLL.LL.LL\nL.LL.LL.LL\nL.LLLLL.LL\n..L.L.....\n\
+                       LLLLLLLLLL\nL.LLLLLL.L\nL.LLLLL.LL\n";
+        let frames = frames(11, 1, example).unwrap();
+        assert_eq!(frames.len(), 6);
+        assert_eq!(frames[0].count(|c| *c == Rgb::RED), 0);
+        assert_eq!(frames[5].count(|c| *c == Rgb::RED), 37);
+    }
+
+    #[test]
+    fn test_simulation_frames_line_up() {
+        let cubes = frames(17, 2, ".#.\n..#\n###\n").unwrap();
+        assert_eq!(cubes.len(), 7);
+        assert!(cubes
+            .iter()
+            .all(|f| (f.height(), f.width()) == (cubes[6].height(), cubes[6].width())));
+        assert_eq!(cubes[0].count(|c| *c == Rgb::WHITE), 5);
+        assert_eq!(cubes[6].count(|c| *c == Rgb::WHITE), 848);
+
+        let floor = frames(24, 2, &generate::generate(24, 20, 1).unwrap()).unwrap();
+        assert_eq!(floor.len(), 101);
+        assert!(floor
+            .iter()
+            .all(|f| (f.height(), f.width()) == (floor[0].height(), floor[0].width())));
+        assert!(frames(1, 1, "1721\n").is_err());
+    }
+
+    #[test]
+    fn test_sea_monsters() {
+        let input = generate::day20(&mut Rng::new(5), 4);
+        let image = frames(20, 2, &input).unwrap().remove(0);
+        assert_eq!(image.height(), 32);
+        let monster_cells = image.count(|c| *c == Rgb::RED);
+        assert!(monster_cells > 0);
+        assert_eq!(monster_cells % 15, 0);
+    }
+
+    #[test]
+    fn test_write_frames() {
+        let dir = env::temp_dir().join(format!("aoc-frames-{}", std::process::id()));
+        let frames = vec![Grid::filled(2, 3, Rgb::WHITE); 3];
+        let paths = write_frames(&dir, &frames, Format::Svg, 1).unwrap();
+        assert_eq!(paths.len(), 3);
+        assert!(paths[2].ends_with("frame-0002.svg"));
+        assert!(fs::read_to_string(&paths[0]).unwrap().starts_with("<svg"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,104 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+/// Environment variable naming the directory that holds the `day<N>.txt` inputs.
+pub const INPUT_DIR_VAR: &str = "AOC_INPUT_DIR";
+
+/// Where a puzzle input is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputSource {
+    /// A file at an explicit path.
+    Path(PathBuf),
+    /// Standard input, read to the end.
+    Stdin,
+    /// An input that is already in memory.
+    Text(String),
+    /// The `day<N>.txt` file in the input directory, see `input_dir`.
+    Day(u8),
+}
+
+impl InputSource {
+    pub fn read(&self) -> io::Result<String> {
+        match self {
+            InputSource::Path(path) => fs::read_to_string(path),
+            InputSource::Stdin => {
+                let mut res = String::new();
+                io::stdin().read_to_string(&mut res)?;
+                Ok(res)
+            }
+            InputSource::Text(text) => Ok(text.clone()),
+            InputSource::Day(day) => fs::read_to_string(day_path(*day)),
+        }
+    }
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputSource::Path(path) => write!(f, "{}", path.display()),
+            InputSource::Stdin => write!(f, "stdin"),
+            InputSource::Text(_) => write!(f, "in-memory input"),
+            InputSource::Day(day) => write!(f, "{}", day_path(*day).display()),
+        }
+    }
+}
+
+/// The directory named by `AOC_INPUT_DIR`, or the `input` directory of this crate if the variable is not set.
+pub fn input_dir() -> PathBuf {
+    env::var_os(INPUT_DIR_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("input"))
+}
+
+pub fn day_path(day: u8) -> PathBuf {
+    input_dir().join(format!("day{}.txt", day))
+}
+
+/// Reads the input for `day`, panicking if it cannot be read. Meant for the tests that run on the real inputs.
+pub fn read_day(day: u8) -> String {
+    let source = InputSource::Day(day);
+    source
+        .read()
+        .unwrap_or_else(|e| panic!("Could not read {}: {}", source, e))
+}
+
+/// The directory holding the examples from the puzzle descriptions, with their expected answers in `answers.txt`.
+pub fn fixture_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures")
+}
+
+/// Reads an example from the fixture directory, panicking if it cannot be read. Meant for the tests.
+pub fn read_fixture(name: &str) -> String {
+    let source = InputSource::Path(fixture_dir().join(name));
+    source
+        .read()
+        .unwrap_or_else(|e| panic!("Could not read {}: {}", source, e))
+}
+
+/// Same as `read_day`, split into owned lines.
+pub fn read_day_lines(day: u8) -> impl Iterator<Item = String> {
+    let lines: Vec<String> = read_day(day).lines().map(|line| line.to_string()).collect();
+    lines.into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input::{day_path, read_day, read_day_lines, InputSource};
+
+    #[test]
+    fn test_read() {
+        let text = InputSource::Text("1\n2\n".to_string());
+        assert_eq!(text.read().unwrap(), "1\n2\n");
+
+        let path = InputSource::Path(day_path(1));
+        assert_eq!(path.read().unwrap(), read_day(1));
+        assert_eq!(read_day_lines(1).count(), read_day(1).lines().count());
+
+        let missing = InputSource::Path("no/such/file.txt".into());
+        assert!(missing.read().is_err());
+        assert_eq!(missing.to_string(), "no/such/file.txt");
+    }
+}
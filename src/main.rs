@@ -0,0 +1,551 @@
+use aoc_2020::animate::{self, Command};
+use aoc_2020::budget::{Budget, Progress};
+use aoc_2020::cache::parse_cached;
+use aoc_2020::certificate;
+use aoc_2020::detect;
+use aoc_2020::fetch::{Fetched, Fetcher};
+use aoc_2020::generate::generate;
+use aoc_2020::grid::Grid;
+use aoc_2020::image::{self, Format, Rgb};
+use aoc_2020::input::{input_dir, InputSource};
+use aoc_2020::registry;
+#[cfg(feature = "server")]
+use aoc_2020::server;
+use aoc_2020::solution::DynSolution;
+use aoc_2020::timing;
+use aoc_2020::trace::{self, Trace};
+use aoc_2020::verify::{self, Status};
+use itertools::Itertools;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "Usage: aoc <day> <part> [input | -] [--timeout <seconds>]
+       aoc verify [manifest]
+       aoc certify <day> <part> [input | -]
+       aoc explain <day> <part> [--json <path>] [input | -]
+       aoc dump <day> [input | -]
+       aoc detect <input | ->
+       aoc fetch <day>...
+       aoc generate <day> <size> [seed]
+       aoc all [--threads <n>] [--json <path>]
+       aoc image <day> <part> <output.ppm | output.svg> [input | -]
+       aoc frames <day> <part> <directory> <ppm | svg> [input | -]
+       aoc animate <day> <part> [--delay <ms>] [--paused] [input | -]
+       aoc serve [address]";
+
+/// The number of pixels along each side of a cell in exported images.
+const IMAGE_SCALE: usize = 4;
+
+/// The time each generation is shown for when animating, unless `--delay` is given.
+const ANIMATION_DELAY: Duration = Duration::from_millis(200);
+
+fn main() {
+    let args = env::args().skip(1).collect_vec();
+    match run(&args) {
+        Ok(answer) => println!("{}", answer),
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(1);
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<String, String> {
+    match args {
+        [command, manifest @ ..] if command == "verify" && manifest.len() <= 1 => {
+            run_verify(manifest.first())
+        }
+        [command, day, part, input @ ..] if command == "certify" && input.len() <= 1 => {
+            run_certify(day, part, input.first())
+        }
+        [command, day, part, rest @ ..] if command == "explain" => run_explain(day, part, rest),
+        [command, day, input @ ..] if command == "dump" && input.len() <= 1 => {
+            run_dump(day, input.first())
+        }
+        [command, input] if command == "detect" => run_detect(input),
+        [command, days @ ..] if command == "fetch" && !days.is_empty() => run_fetch(days),
+        [command, rest @ ..] if command == "generate" => run_generate(rest),
+        [command, rest @ ..] if command == "all" => run_all(rest),
+        [command, day, part, output, input @ ..] if command == "image" && input.len() <= 1 => {
+            run_image(day, part, output, input.first())
+        }
+        [command, day, part, dir, format, input @ ..]
+            if command == "frames" && input.len() <= 1 =>
+        {
+            run_frames(day, part, dir, format, input.first())
+        }
+        [command, day, part, rest @ ..] if command == "animate" => run_animate(day, part, rest),
+        [command, address @ ..] if command == "serve" && address.len() <= 1 => {
+            run_serve(address.first())
+        }
+        _ => run_solution(args),
+    }
+}
+
+/// Solves one part, giving up after `--timeout`. The slow parts show their progress when stderr is a terminal.
+fn run_solution(args: &[String]) -> Result<String, String> {
+    let mut positional = Vec::new();
+    let mut timeout = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--timeout" {
+            let seconds = args.next().ok_or_else(|| USAGE.to_string())?;
+            timeout = seconds
+                .parse()
+                .ok()
+                .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                .map(Some)
+                .ok_or_else(|| format!("Invalid timeout: {}\n{}", seconds, USAGE))?;
+        } else {
+            positional.push(arg);
+        }
+    }
+    let (day, part) = match positional.as_slice() {
+        [day, part] | [day, part, _] => (parse_arg(day, "day")?, parse_arg(part, "part")?),
+        _ => return Err(USAGE.to_string()),
+    };
+
+    let solution =
+        registry::solution(day).ok_or_else(|| format!("Invalid day: {}\n{}", day, USAGE))?;
+    if part != 1 && part != 2 {
+        return Err(format!("Invalid part: {}\n{}", part, USAGE));
+    }
+
+    let input = read_input(day, positional.get(2).copied())?;
+    // Only the slow parts count their steps and stop themselves, so the part runs on its own thread and is left
+    // behind if it is still going at the deadline.
+    let (sender, answers) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(solve_part(solution, part, &input, timeout));
+    });
+    let answer = match timeout {
+        Some(timeout) => answers.recv_timeout(timeout).map_err(|e| match e {
+            RecvTimeoutError::Timeout => {
+                clear_progress();
+                format!("Timed out after {} seconds", timeout.as_secs_f64())
+            }
+            RecvTimeoutError::Disconnected => "The solver panicked".to_string(),
+        })?,
+        None => answers
+            .recv()
+            .map_err(|_| "The solver panicked".to_string())?,
+    };
+    answer?.ok_or_else(|| "No solution found".to_string())
+}
+
+/// Parses the input and solves the part, stopping the slow parts once `timeout` has passed.
+fn solve_part(
+    solution: &dyn DynSolution,
+    part: u8,
+    input: &str,
+    timeout: Option<Duration>,
+) -> Result<Option<String>, String> {
+    let parsed = parse_cached(solution, input).map_err(|e| e.to_string())?;
+    let mut budget = match timeout {
+        Some(timeout) => Budget::time_limit(timeout),
+        None => Budget::unlimited(),
+    };
+    let show_progress = io::stderr().is_terminal();
+    if show_progress {
+        budget = budget.with_progress(print_progress);
+    }
+    let answer = solution.solve_within(part, parsed.as_ref(), &mut budget);
+    if budget.done() > 0 {
+        clear_progress();
+    }
+    answer.map_err(|e| e.to_string())
+}
+
+fn clear_progress() {
+    if io::stderr().is_terminal() {
+        eprint!("\r\x1b[K");
+    }
+}
+
+fn print_progress(progress: Progress) {
+    match progress.total {
+        Some(total) => eprint!(
+            "\r{:.1}% ({} of {} steps)",
+            progress.done as f64 * 100.0 / total as f64,
+            progress.done,
+            total
+        ),
+        None => eprint!("\r{} steps", progress.done),
+    }
+}
+
+/// Reads the input named on the command line, where `-` is stdin, or the day's input file if there is none.
+fn read_input(day: u8, arg: Option<&String>) -> Result<String, String> {
+    let source = match arg.map(|arg| arg.as_str()) {
+        Some("-") => InputSource::Stdin,
+        Some(path) => InputSource::Path(PathBuf::from(path)),
+        None => InputSource::Day(day),
+    };
+    source.read().map_err(|e| match source {
+        InputSource::Day(day) if e.kind() == io::ErrorKind::NotFound => format!(
+            "Could not read {}: {}\nRun `aoc fetch {}` to download it",
+            source, e, day
+        ),
+        _ => format!("Could not read {}: {}", source, e),
+    })
+}
+
+fn image_frames(day: &str, part: &str, input: Option<&String>) -> Result<Vec<Grid<Rgb>>, String> {
+    let day = parse_arg(day, "day")?;
+    let part = parse_arg(part, "part")?;
+    if part != 1 && part != 2 {
+        return Err(format!("Invalid part: {}\n{}", part, USAGE));
+    }
+    image::frames(day, part, &read_input(day, input)?)
+}
+
+/// Writes the final state of the day as one image, in the format given by the output's extension.
+fn run_image(
+    day: &str,
+    part: &str,
+    output: &str,
+    input: Option<&String>,
+) -> Result<String, String> {
+    let path = Path::new(output);
+    let format = path
+        .extension()
+        .and_then(|extension| Format::from_extension(&extension.to_string_lossy()))
+        .ok_or_else(|| format!("Expected a .ppm or .svg output: {}\n{}", output, USAGE))?;
+    let frames = image_frames(day, part, input)?;
+    let image = frames.last().ok_or("Nothing to draw")?;
+    fs::write(path, image::encode(image, format, IMAGE_SCALE))
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    Ok(format!("Wrote {}", path.display()))
+}
+
+/// Writes one numbered image per generation of the day's simulation.
+fn run_frames(
+    day: &str,
+    part: &str,
+    dir: &str,
+    format: &str,
+    input: Option<&String>,
+) -> Result<String, String> {
+    let format = Format::from_extension(format)
+        .ok_or_else(|| format!("Invalid format: {}\n{}", format, USAGE))?;
+    let frames = image_frames(day, part, input)?;
+    let paths = image::write_frames(Path::new(dir), &frames, format, IMAGE_SCALE)
+        .map_err(|e| format!("Could not write frames to {}: {}", dir, e))?;
+    Ok(format!("Wrote {} frames to {}", paths.len(), dir))
+}
+
+/// Plays the day's simulation in the terminal. Lines typed while it plays control it: an empty line pauses or
+/// resumes, `n` steps one generation while paused and `q` quits. Reading the input from stdin leaves no way to control
+/// it, so it plays through.
+fn run_animate(day: &str, part: &str, args: &[String]) -> Result<String, String> {
+    let day_number = parse_arg(day, "day")?;
+    let part_number = parse_arg(part, "part")?;
+    if part_number != 1 && part_number != 2 {
+        return Err(format!("Invalid part: {}\n{}", part, USAGE));
+    }
+    let mut delay = ANIMATION_DELAY;
+    let mut paused = false;
+    let mut input = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--delay" => {
+                let millis = args.next().ok_or_else(|| USAGE.to_string())?;
+                delay = Duration::from_millis(
+                    millis
+                        .parse()
+                        .map_err(|_| format!("Invalid delay: {}\n{}", millis, USAGE))?,
+                );
+            }
+            "--paused" => paused = true,
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let frames = animate::frames(day_number, part_number, &read_input(day_number, input)?)?;
+    let (sender, commands) = mpsc::channel();
+    if input.is_none_or(|input| input != "-") {
+        thread::spawn(move || {
+            let lines = io::stdin().lock().lines().map_while(Result::ok);
+            for command in lines.filter_map(|line| Command::parse(&line)) {
+                if sender.send(command).is_err() {
+                    break;
+                }
+            }
+        });
+    } else {
+        drop(sender);
+    }
+    let title = format!("Day {} part {}", day_number, part_number);
+    let shown = animate::play(
+        &title,
+        &frames,
+        delay,
+        paused,
+        &commands,
+        &mut io::stdout().lock(),
+    )
+    .map_err(|e| format!("Could not draw the animation: {}", e))?;
+    Ok(format!(
+        "\nShowed {} of {} generations",
+        shown,
+        frames.len()
+    ))
+}
+
+/// Answers `POST /solve` requests on the address, which defaults to port 8020 on localhost, until killed.
+#[cfg(feature = "server")]
+fn run_serve(address: Option<&String>) -> Result<String, String> {
+    let address = address.map_or(server::DEFAULT_ADDRESS, |address| address.as_str());
+    let service = server::Service::bind(address)?;
+    eprintln!("Listening on http://{}/solve", address);
+    service.run();
+    Ok("Stopped".to_string())
+}
+
+#[cfg(not(feature = "server"))]
+fn run_serve(_address: Option<&String>) -> Result<String, String> {
+    Err(
+        "aoc was built without the server feature: cargo build --release --features server"
+            .to_string(),
+    )
+}
+
+/// Checks every answer in the manifest, which defaults to `answers.txt` in the input directory. Fails if any answer
+/// is wrong.
+fn run_verify(manifest: Option<&String>) -> Result<String, String> {
+    let path = manifest
+        .map(PathBuf::from)
+        .unwrap_or_else(|| input_dir().join("answers.txt"));
+    let text = fs::read_to_string(&path)
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let entries = verify::parse_manifest(&text, path.parent().unwrap_or_else(|| Path::new("")))?;
+
+    let reports = verify::verify(&entries);
+    let mut lines = reports
+        .iter()
+        .map(|report| {
+            let input = report
+                .input
+                .as_ref()
+                .map(|input| input.display().to_string())
+                .unwrap_or_default();
+            format!(
+                "Day {:>2} part {} {:<24} {}",
+                report.day, report.part, input, report.status
+            )
+        })
+        .collect_vec();
+
+    let count = |f: fn(&Status) -> bool| reports.iter().filter(|r| f(&r.status)).count();
+    let failed = count(|s| matches!(s, Status::Fail(_)));
+    lines.push(format!(
+        "{} passed, {} failed, {} missing",
+        count(|s| *s == Status::Pass),
+        failed,
+        count(|s| matches!(s, Status::Missing(_)))
+    ));
+
+    if failed == 0 {
+        Ok(lines.join("\n"))
+    } else {
+        Err(lines.join("\n"))
+    }
+}
+
+/// Solves the part with a certificate and checks it. Fails if the checker rejects the certificate or proves a
+/// different answer from the solver's.
+fn run_certify(day: &str, part: &str, input: Option<&String>) -> Result<String, String> {
+    let day = parse_arg(day, "day")?;
+    let part = parse_arg(part, "part")?;
+    if part != 1 && part != 2 {
+        return Err(format!("Invalid part: {}\n{}", part, USAGE));
+    }
+    let checked = certificate::certify(day, part, &read_input(day, input)?)?
+        .ok_or_else(|| format!("No certificate for day {} part {}", day, part))?;
+    let verdict = match (&checked.checked, &checked.solved) {
+        (Ok(answer), Some(solved)) if answer == solved => format!("Checked {}", answer),
+        (Ok(answer), solved) => format!(
+            "Checked {}, but the solver answered {}",
+            answer,
+            solved.as_deref().unwrap_or("nothing")
+        ),
+        (Err(e), _) => format!("Rejected: {}", e),
+    };
+    let report = format!("{}\n{}", checked.certificate, verdict);
+    if checked.is_valid() {
+        Ok(report)
+    } else {
+        Err(report)
+    }
+}
+
+/// Solves one part, printing each step the solver records on the way, or writing the steps to a JSON file.
+fn run_explain(day: &str, part: &str, args: &[String]) -> Result<String, String> {
+    let day = parse_arg(day, "day")?;
+    let part = parse_arg(part, "part")?;
+    let solution =
+        registry::solution(day).ok_or_else(|| format!("Invalid day: {}\n{}", day, USAGE))?;
+    if part != 1 && part != 2 {
+        return Err(format!("Invalid part: {}\n{}", part, USAGE));
+    }
+    let mut json = None;
+    let mut input = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = Some(PathBuf::from(args.next().ok_or_else(|| USAGE.to_string())?)),
+            _ if input.is_none() => input = Some(arg),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let parsed = solution
+        .parse(&read_input(day, input)?)
+        .map_err(|e| e.to_string())?;
+    let (answer, report) = match json {
+        Some(path) => {
+            let mut events = Vec::new();
+            let answer = solution.explain(
+                part,
+                parsed.as_ref(),
+                &mut Trace::to(|event| events.push(event)),
+            );
+            fs::write(&path, trace::to_json(&events))
+                .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+            (
+                answer,
+                format!("Wrote {} steps to {}\n", events.len(), path.display()),
+            )
+        }
+        None => {
+            let mut steps = 0;
+            let answer = solution.explain(
+                part,
+                parsed.as_ref(),
+                &mut Trace::to(|event| {
+                    println!("{}", event);
+                    steps += 1;
+                }),
+            );
+            let report = match steps {
+                0 => "No steps recorded for this part\n".to_string(),
+                _ => String::new(),
+            };
+            (answer, report)
+        }
+    };
+    let answer = answer.ok_or_else(|| format!("{}No solution found", report))?;
+    Ok(format!("{}{}", report, answer))
+}
+
+/// Prints the parsed form of an input as JSON.
+fn run_dump(day: &str, input: Option<&String>) -> Result<String, String> {
+    let day = parse_arg(day, "day")?;
+    let solution =
+        registry::solution(day).ok_or_else(|| format!("Invalid day: {}\n{}", day, USAGE))?;
+    let parsed = solution
+        .parse(&read_input(day, input)?)
+        .map_err(|e| e.to_string())?;
+    Ok(solution.to_json(parsed.as_ref()))
+}
+
+/// Lists the days the input plausibly belongs to, most plausible first.
+fn run_detect(input: &str) -> Result<String, String> {
+    let source = match input {
+        "-" => InputSource::Stdin,
+        path => InputSource::Path(PathBuf::from(path)),
+    };
+    let text = source
+        .read()
+        .map_err(|e| format!("Could not read {}: {}", source, e))?;
+    let candidates = detect::detect(&text);
+    if candidates.is_empty() {
+        return Err("The input does not look like any day's input".to_string());
+    }
+    Ok(candidates.iter().join("\n"))
+}
+
+/// Times every day on a pool of threads, defaulting to one per CPU, and optionally writes the timings as JSON. Fails
+/// if any part could not be solved.
+fn run_all(args: &[String]) -> Result<String, String> {
+    let mut threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut json = None;
+    for pair in args.chunks(2) {
+        match pair {
+            [flag, value] if flag == "--threads" => {
+                threads = value
+                    .parse()
+                    .map_err(|_| format!("Invalid thread count: {}\n{}", value, USAGE))?
+            }
+            [flag, path] if flag == "--json" => json = Some(PathBuf::from(path)),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let start = Instant::now();
+    let timings = timing::time_all(registry::solutions(), threads);
+    let table = timing::render_table(&timings, start.elapsed());
+    if let Some(path) = json {
+        fs::write(&path, timing::to_json(&timings))
+            .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    }
+
+    if timings.iter().all(|timing| timing.answer.is_ok()) {
+        Ok(table)
+    } else {
+        Err(table)
+    }
+}
+
+/// Downloads the inputs for the days that do not have one yet, using the session token in `AOC_SESSION`.
+fn run_fetch(days: &[String]) -> Result<String, String> {
+    let days = days
+        .iter()
+        .map(|day| parse_arg(day, "day"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let fetcher = Fetcher::from_env();
+    let mut lines = Vec::new();
+    for day in days {
+        match fetcher.fetch(day).map_err(|e| e.to_string())? {
+            Fetched::Cached(path) => lines.push(format!("Already have {}", path.display())),
+            Fetched::Downloaded(path) => lines.push(format!("Downloaded {}", path.display())),
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Prints a random input for the day, which is the same for the same size and seed.
+fn run_generate(args: &[String]) -> Result<String, String> {
+    let (day, size, seed) = match args {
+        [day, size] => (day, size, None),
+        [day, size, seed] => (day, size, Some(seed)),
+        _ => return Err(USAGE.to_string()),
+    };
+    let day = parse_arg(day, "day")?;
+    let size = size
+        .parse()
+        .map_err(|_| format!("Invalid size: {}\n{}", size, USAGE))?;
+    let seed = match seed {
+        Some(seed) => seed
+            .parse()
+            .map_err(|_| format!("Invalid seed: {}\n{}", seed, USAGE))?,
+        None => 0,
+    };
+
+    let input =
+        generate(day, size, seed).ok_or_else(|| format!("Invalid day: {}\n{}", day, USAGE))?;
+    Ok(input.trim_end().to_string())
+}
+
+fn parse_arg(arg: &str, name: &str) -> Result<u8, String> {
+    arg.parse()
+        .map_err(|_| format!("Invalid {}: {}\n{}", name, arg, USAGE))
+}
@@ -0,0 +1,145 @@
+//! Arithmetic for the counts and products that outgrow `u64` on large inputs. The solvers that compute them are generic
+//! over `Natural`: with `u64` they report overflow instead of wrapping or panicking, and with `BigUint` they are exact.
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+pub use num_bigint::BigUint;
+
+/// The result did not fit in the numeric type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Arithmetic overflow")
+    }
+}
+
+impl Error for Overflow {}
+
+/// A non-negative integer with checked arithmetic.
+pub trait Natural: Clone + PartialEq + fmt::Debug + fmt::Display {
+    fn from_u64(n: u64) -> Self;
+
+    fn zero() -> Self {
+        Self::from_u64(0)
+    }
+
+    fn one() -> Self {
+        Self::from_u64(1)
+    }
+
+    fn add(&self, other: &Self) -> Result<Self, Overflow>;
+
+    fn mul(&self, other: &Self) -> Result<Self, Overflow>;
+
+    /// The remainder after dividing by `divisor`, which must not be zero.
+    fn rem_u64(&self, divisor: u64) -> u64;
+
+    /// Adds up the numbers, failing on the first overflow.
+    fn sum(numbers: impl IntoIterator<Item = Result<Self, Overflow>>) -> Result<Self, Overflow> {
+        numbers
+            .into_iter()
+            .try_fold(Self::zero(), |total, n| total.add(&n?))
+    }
+}
+
+impl Natural for u64 {
+    fn from_u64(n: u64) -> u64 {
+        n
+    }
+
+    fn add(&self, other: &u64) -> Result<u64, Overflow> {
+        self.checked_add(*other).ok_or(Overflow)
+    }
+
+    fn mul(&self, other: &u64) -> Result<u64, Overflow> {
+        self.checked_mul(*other).ok_or(Overflow)
+    }
+
+    fn rem_u64(&self, divisor: u64) -> u64 {
+        self % divisor
+    }
+}
+
+impl Natural for BigUint {
+    fn from_u64(n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    fn add(&self, other: &BigUint) -> Result<BigUint, Overflow> {
+        Ok(self + other)
+    }
+
+    fn mul(&self, other: &BigUint) -> Result<BigUint, Overflow> {
+        Ok(self * other)
+    }
+
+    fn rem_u64(&self, divisor: u64) -> u64 {
+        u64::try_from(self % divisor).expect("The remainder is less than the divisor")
+    }
+}
+
+/// Formats a result computed with `u64`, or if that overflowed, the one computed by `big`.
+pub fn exact(
+    checked: Result<u64, Overflow>,
+    big: impl FnOnce() -> Result<BigUint, Overflow>,
+) -> String {
+    match checked {
+        Ok(n) => n.to_string(),
+        Err(Overflow) => big().expect("BigUint never overflows").to_string(),
+    }
+}
+
+/// Same as `exact`, for results that may not exist.
+pub fn exact_option(
+    checked: Result<Option<u64>, Overflow>,
+    big: impl FnOnce() -> Result<Option<BigUint>, Overflow>,
+) -> Option<String> {
+    match checked {
+        Ok(n) => n.map(|n| n.to_string()),
+        Err(Overflow) => big()
+            .expect("BigUint never overflows")
+            .map(|n| n.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::numeric::{exact, exact_option, BigUint, Natural, Overflow};
+
+    #[test]
+    fn test_checked_u64() {
+        assert_eq!(u64::MAX.add(&0), Ok(u64::MAX));
+        assert_eq!(u64::MAX.add(&1), Err(Overflow));
+        assert_eq!((1u64 << 32).mul(&(1 << 31)), Ok(1 << 63));
+        assert_eq!((1u64 << 32).mul(&(1 << 32)), Err(Overflow));
+        assert_eq!(u64::sum(vec![Ok(1), Ok(2), Ok(3)]), Ok(6));
+        assert_eq!(u64::sum(vec![Ok(u64::MAX), Ok(1)]), Err(Overflow));
+        assert_eq!(17u64.rem_u64(5), 2);
+    }
+
+    #[test]
+    fn test_big_uint() {
+        let big = BigUint::from_u64(u64::MAX).add(&BigUint::one()).unwrap();
+        assert_eq!(big.to_string(), "18446744073709551616");
+        let squared = big.mul(&big).unwrap();
+        assert_eq!(
+            squared.to_string(),
+            "340282366920938463463374607431768211456"
+        );
+        assert_eq!(squared.rem_u64(1000), 456);
+        assert_eq!(
+            exact(Err(Overflow), || Ok(squared.clone())),
+            squared.to_string()
+        );
+        assert_eq!(exact(Ok(42), || unreachable!()), "42");
+        assert_eq!(
+            exact_option(Err(Overflow), || Ok(Some(big.clone()))),
+            Some(big.to_string())
+        );
+        assert_eq!(exact_option(Err(Overflow), || Ok(None)), None);
+        assert_eq!(exact_option(Ok(None), || unreachable!()), None);
+    }
+}
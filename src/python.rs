@@ -0,0 +1,520 @@
+//! The `aoc_2020` Python extension module, built with the `python` feature.
+//!
+//! ```python
+//! import aoc_2020
+//! aoc_2020.solve(1, 1, open("input/day1.txt").read())
+//! notes = aoc_2020.parse(16, text)
+//! ```
+use crate::assignment::Assignment;
+use crate::grid::Grid;
+use crate::registry;
+use crate::solution::Solution;
+use crate::{
+    day1, day10, day11, day12, day13, day14, day15, day16, day17, day18, day19, day2, day20, day21,
+    day22, day23, day24, day25, day3, day4, day5, day6, day7, day8, day9,
+};
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList, PySet, PyTuple};
+use pyo3::IntoPyObjectExt;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+create_exception!(
+    aoc_2020,
+    ParseError,
+    PyValueError,
+    "Raised when a day's input cannot be parsed."
+);
+
+fn parse_error(e: crate::error::ParseError) -> PyErr {
+    ParseError::new_err(e.to_string())
+}
+
+fn unknown_day(day: u8) -> PyErr {
+    PyValueError::new_err(format!("No solution for day {}", day))
+}
+
+/// Converts a parsed model into plain Python lists, dicts, sets, tuples, strings and numbers.
+trait ToPython {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>>;
+}
+
+macro_rules! to_python_via_pyo3 {
+    ($($t:ty),*) => {
+        $(
+            impl ToPython for $t {
+                fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+                    self.into_bound_py_any(py)
+                }
+            }
+        )*
+    };
+}
+
+to_python_via_pyo3!(u8, u16, u32, u64, usize, i32, i64, bool, char, String);
+
+impl<T: ToPython> ToPython for [T] {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let items = self
+            .iter()
+            .map(|item| item.to_python(py))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(PyList::new(py, items)?.into_any())
+    }
+}
+
+impl<T: ToPython> ToPython for Vec<T> {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.as_slice().to_python(py)
+    }
+}
+
+impl<T: ToPython> ToPython for VecDeque<T> {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let items = self
+            .iter()
+            .map(|item| item.to_python(py))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(PyList::new(py, items)?.into_any())
+    }
+}
+
+impl<T: ToPython> ToPython for HashSet<T> {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let items = self
+            .iter()
+            .map(|item| item.to_python(py))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(PySet::new(py, items)?.into_any())
+    }
+}
+
+impl<K: ToPython, V: ToPython> ToPython for HashMap<K, V> {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let dict = PyDict::new(py);
+        for (key, value) in self.iter() {
+            dict.set_item(key.to_python(py)?, value.to_python(py)?)?;
+        }
+        Ok(dict.into_any())
+    }
+}
+
+impl<A: ToPython, B: ToPython> ToPython for (A, B) {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        Ok(PyTuple::new(py, [self.0.to_python(py)?, self.1.to_python(py)?])?.into_any())
+    }
+}
+
+/// A grid becomes a list of rows.
+impl<T: ToPython> ToPython for Grid<T> {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let rows = self
+            .rows()
+            .map(|row| row.to_python(py))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(PyList::new(py, rows)?.into_any())
+    }
+}
+
+fn dict<'py>(
+    py: Python<'py>,
+    items: Vec<(&str, Bound<'py, PyAny>)>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let dict = PyDict::new(py);
+    for (key, value) in items {
+        dict.set_item(key, value)?;
+    }
+    Ok(dict.into_any())
+}
+
+impl ToPython for day2::PasswordRecord {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        dict(
+            py,
+            vec![
+                ("min", self.min.to_python(py)?),
+                ("max", self.max.to_python(py)?),
+                ("letter", self.letter.to_python(py)?),
+                ("password", self.password.to_python(py)?),
+            ],
+        )
+    }
+}
+
+impl ToPython for day4::Passport {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.fields.to_python(py)
+    }
+}
+
+impl ToPython for day5::BoardingPass {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        dict(
+            py,
+            vec![
+                ("row", self.row.to_python(py)?),
+                ("column", self.column.to_python(py)?),
+            ],
+        )
+    }
+}
+
+/// Instructions become `(operation, argument)` tuples, as in the input.
+impl ToPython for day8::Instruction {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        match self {
+            day8::Instruction::ACC(arg) => ("acc".to_string(), *arg),
+            day8::Instruction::JMP(arg) => ("jmp".to_string(), *arg),
+            day8::Instruction::NOP(arg) => ("nop".to_string(), *arg),
+        }
+        .to_python(py)
+    }
+}
+
+/// Seats become the characters of the input.
+impl ToPython for day11::Position {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        match self {
+            day11::Position::Floor => '.',
+            day11::Position::Empty => 'L',
+            day11::Position::Occupied => '#',
+        }
+        .to_python(py)
+    }
+}
+
+/// Actions become `(letter, value)` tuples, as in the input.
+impl ToPython for day12::Action {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        use day12::{Action, Direction, Turn};
+        match self {
+            Action::Move(Direction::North, value) => ('N', *value),
+            Action::Move(Direction::South, value) => ('S', *value),
+            Action::Move(Direction::East, value) => ('E', *value),
+            Action::Move(Direction::West, value) => ('W', *value),
+            Action::Turn(Turn::Left, value) => ('L', *value),
+            Action::Turn(Turn::Right, value) => ('R', *value),
+            Action::Forward(value) => ('F', *value),
+        }
+        .to_python(py)
+    }
+}
+
+impl ToPython for day14::Instruction {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        match self {
+            day14::Instruction::Mask {
+                ones,
+                zeroes,
+                x_indexes,
+            } => dict(
+                py,
+                vec![
+                    ("ones", ones.to_python(py)?),
+                    ("zeroes", zeroes.to_python(py)?),
+                    ("x_indexes", x_indexes.to_python(py)?),
+                ],
+            ),
+            day14::Instruction::Mem { address, value } => dict(
+                py,
+                vec![
+                    ("address", address.to_python(py)?),
+                    ("value", value.to_python(py)?),
+                ],
+            ),
+        }
+    }
+}
+
+impl ToPython for day16::Notes {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let fields = self
+            .fields
+            .iter()
+            .map(|(name, min1, max1, min2, max2)| {
+                let ranges = ((*min1, *max1), (*min2, *max2));
+                (name.clone(), ranges).to_python(py)
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        dict(
+            py,
+            vec![
+                ("fields", PyList::new(py, fields)?.into_any()),
+                ("my_ticket", self.my_ticket.to_python(py)?),
+                ("nearby_tickets", self.nearby_tickets.to_python(py)?),
+            ],
+        )
+    }
+}
+
+/// Numbers stay numbers and everything else becomes the character of the input.
+impl ToPython for day18::Token {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        use day18::{Operator, Token};
+        match self {
+            Token::Number(n) => n.to_python(py),
+            Token::Op(Operator::Plus) => '+'.to_python(py),
+            Token::Op(Operator::Mult) => '*'.to_python(py),
+            Token::LeftParen => '('.to_python(py),
+            Token::RightParen => ')'.to_python(py),
+        }
+    }
+}
+
+/// Terminal rules become their character and the others a list of alternative sequences of rule numbers.
+impl ToPython for day19::Rule {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        match self {
+            day19::Rule::Terminal(c) => c.to_python(py),
+            day19::Rule::SubRule(alternatives) => alternatives.to_python(py),
+        }
+    }
+}
+
+/// Tiles become a dict with the tile number and the rows of the tile as strings.
+impl ToPython for day20::Tile {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let rows = self
+            .tile
+            .rows()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>();
+        dict(
+            py,
+            vec![
+                ("tile_no", self.tile_no.to_python(py)?),
+                ("tile", rows.to_python(py)?),
+            ],
+        )
+    }
+}
+
+impl ToPython for day21::Food {
+    fn to_python<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        dict(
+            py,
+            vec![
+                ("ingredients", self.ingredients.to_python(py)?),
+                ("allergens", self.allergens.to_python(py)?),
+            ],
+        )
+    }
+}
+
+/// The days that have a solution.
+#[pyfunction]
+fn days() -> Vec<u32> {
+    // A `Vec<u8>` would become `bytes`.
+    registry::solutions()
+        .iter()
+        .map(|s| u32::from(s.day()))
+        .collect()
+}
+
+/// Parses a day's input and returns the parsed model as native Python objects.
+#[pyfunction]
+fn parse<'py>(py: Python<'py>, day: u8, input: &str) -> PyResult<Bound<'py, PyAny>> {
+    macro_rules! model {
+        ($solution:expr) => {
+            $solution.parse(input).map_err(parse_error)?.to_python(py)
+        };
+    }
+    match day {
+        1 => model!(day1::Day1),
+        2 => model!(day2::Day2),
+        3 => model!(day3::Day3),
+        4 => model!(day4::Day4),
+        5 => model!(day5::Day5),
+        6 => model!(day6::Day6),
+        7 => model!(day7::Day7),
+        8 => model!(day8::Day8),
+        9 => model!(day9::Day9),
+        10 => model!(day10::Day10),
+        11 => model!(day11::Day11),
+        12 => model!(day12::Day12),
+        13 => model!(day13::Day13),
+        14 => model!(day14::Day14),
+        15 => model!(day15::Day15),
+        16 => model!(day16::Day16),
+        17 => model!(day17::Day17),
+        18 => model!(day18::Day18),
+        19 => model!(day19::Day19),
+        20 => model!(day20::Day20),
+        21 => model!(day21::Day21),
+        22 => model!(day22::Day22),
+        23 => model!(day23::Day23),
+        24 => model!(day24::Day24),
+        25 => model!(day25::Day25),
+        _ => Err(unknown_day(day)),
+    }
+}
+
+/// Parses a day's input and solves one part. Returns `None` if the part has no solution.
+#[pyfunction]
+fn solve(py: Python<'_>, day: u8, part: u8, input: &str) -> PyResult<Option<String>> {
+    let solution = registry::solution(day).ok_or_else(|| unknown_day(day))?;
+    if part != 1 && part != 2 {
+        return Err(PyValueError::new_err(format!("No part {}", part)));
+    }
+    py.detach(|| solution.run(part, input)).map_err(parse_error)
+}
+
+/// Works out which field is at each index of the day 16 tickets, as a dict from field name to index.
+#[pyfunction]
+fn assign_fields<'py>(py: Python<'py>, input: &str) -> PyResult<Bound<'py, PyAny>> {
+    let notes = day16::Day16.parse(input).map_err(parse_error)?;
+    match day16::assign_fields(&notes) {
+        Assignment::Unique(fields) => fields
+            .into_iter()
+            .enumerate()
+            .map(|(i, field)| (field.clone(), i))
+            .collect::<HashMap<_, _>>()
+            .to_python(py),
+        Assignment::Ambiguous(_, alternatives) => Err(PyValueError::new_err(format!(
+            "The fields can be assigned in {} ways",
+            alternatives.len() + 1
+        ))),
+        Assignment::Unsatisfiable => Err(PyValueError::new_err("The fields cannot be assigned")),
+    }
+}
+
+/// Arranges the day 20 tiles into a square image, as rows of rotated and flipped tiles.
+#[pyfunction]
+fn arrange_tiles<'py>(py: Python<'py>, input: &str) -> PyResult<Bound<'py, PyAny>> {
+    let tiles = day20::Day20.parse(input).map_err(parse_error)?;
+    day20::arrange_tiles(&tiles, day20::image_dim(&tiles))
+        .ok_or_else(|| PyValueError::new_err("The tiles cannot be arranged"))?
+        .to_python(py)
+}
+
+#[pymodule]
+fn aoc_2020(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("ParseError", m.py().get_type::<ParseError>())?;
+    m.add_function(wrap_pyfunction!(days, m)?)?;
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(solve, m)?)?;
+    m.add_function(wrap_pyfunction!(assign_fields, m)?)?;
+    m.add_function(wrap_pyfunction!(arrange_tiles, m)?)?;
+    Ok(())
+}
+
+#[cfg(all(test, feature = "python"))]
+mod tests {
+    use crate::input::read_fixture;
+    use crate::python::{aoc_2020, ParseError};
+    use pyo3::exceptions::PyValueError;
+    use pyo3::prelude::*;
+    use pyo3::types::{PyDict, PyList, PyString, PyTuple};
+    use std::collections::HashMap;
+
+    /// Calls `f` with the module as Python sees it after `import aoc_2020`.
+    fn with_module(f: impl FnOnce(Python<'_>, &Bound<'_, PyModule>) -> PyResult<()>) {
+        Python::initialize();
+        Python::attach(|py| {
+            let module = PyModule::new(py, "aoc_2020")?;
+            aoc_2020(&module)?;
+            f(py, &module)
+        })
+        .unwrap();
+    }
+
+    fn assert_parse_error(py: Python<'_>, res: PyResult<Bound<'_, PyAny>>) -> PyResult<()> {
+        let err = res.unwrap_err();
+        assert!(err.is_instance_of::<ParseError>(py));
+        assert!(err.is_instance_of::<PyValueError>(py));
+        assert_eq!(
+            err.get_type(py).fully_qualified_name()?.to_string(),
+            "aoc_2020.ParseError"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_solve() {
+        with_module(|py, m| {
+            let solve = m.getattr("solve")?;
+            let answer = solve.call1((8, 2, read_fixture("day8.txt")))?;
+            assert!(answer.is_instance_of::<PyString>());
+            assert_eq!(answer.extract::<String>()?, "8");
+            assert!(solve.call1((1, 1, "1\n2\n"))?.is_none());
+
+            assert_parse_error(py, solve.call1((8, 1, "nop +0\nhalt\n")))?;
+            let err = solve.call1((26, 1, "")).unwrap_err();
+            assert!(
+                err.is_instance_of::<PyValueError>(py) && !err.is_instance_of::<ParseError>(py)
+            );
+            assert!(solve.call1((8, 3, "nop +0\n")).is_err());
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_parse() {
+        with_module(|py, m| {
+            let parse = m.getattr("parse")?;
+            let program = parse.call1((8, read_fixture("day8.txt")))?;
+            assert!(program.is_instance_of::<PyList>());
+            let first = program.get_item(0)?;
+            assert!(first.is_instance_of::<PyTuple>());
+            assert_eq!(first.extract::<(String, i64)>()?, ("nop".to_string(), 0));
+            assert_eq!(program.len()?, 9);
+
+            let notes = parse.call1((16, read_fixture("day16-2.txt")))?;
+            assert!(notes.is_instance_of::<PyDict>());
+            assert_eq!(
+                notes
+                    .get_item("fields")?
+                    .get_item(0)?
+                    .extract::<(String, ((u64, u64), (u64, u64)))>()?,
+                ("class".to_string(), ((0, 1), (4, 19)))
+            );
+            assert_eq!(
+                notes.get_item("my_ticket")?.extract::<Vec<u64>>()?,
+                vec![11, 12, 13]
+            );
+            assert_eq!(notes.get_item("nearby_tickets")?.len()?, 3);
+
+            assert_parse_error(py, parse.call1((16, "class: 0-1 or 4-19\n")))?;
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_assign_fields() {
+        with_module(|py, m| {
+            let assign_fields = m.getattr("assign_fields")?;
+            let fields = assign_fields.call1((read_fixture("day16-2.txt"),))?;
+            assert!(fields.is_instance_of::<PyDict>());
+            let expected = [("row", 0), ("class", 1), ("seat", 2)]
+                .iter()
+                .map(|(name, i)| (name.to_string(), *i))
+                .collect::<HashMap<_, _>>();
+            assert_eq!(fields.extract::<HashMap<String, usize>>()?, expected);
+
+            assert_parse_error(py, assign_fields.call1(("your ticket:\n",)))?;
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_arrange_tiles() {
+        with_module(|py, m| {
+            let arrange_tiles = m.getattr("arrange_tiles")?;
+            let rows = arrange_tiles.call1((read_fixture("day20.txt"),))?;
+            assert!(rows.is_instance_of::<PyList>());
+            assert_eq!(rows.len()?, 3);
+            let mut corners = 1;
+            for (r, c) in [(0, 0), (0, 2), (2, 0), (2, 2)].iter() {
+                let tile = rows.get_item(*r)?.get_item(*c)?;
+                assert!(tile.is_instance_of::<PyDict>());
+                assert_eq!(tile.get_item("tile")?.extract::<Vec<String>>()?.len(), 10);
+                corners *= tile.get_item("tile_no")?.extract::<u64>()?;
+            }
+            assert_eq!(corners, 20899048083289);
+
+            assert_parse_error(py, arrange_tiles.call1(("Tile x:\n",)))?;
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,146 @@
+use crate::solution::DynSolution;
+use crate::*;
+
+static SOLUTIONS: [&dyn DynSolution; 25] = [
+    &day1::Day1,
+    &day2::Day2,
+    &day3::Day3,
+    &day4::Day4,
+    &day5::Day5,
+    &day6::Day6,
+    &day7::Day7,
+    &day8::Day8,
+    &day9::Day9,
+    &day10::Day10,
+    &day11::Day11,
+    &day12::Day12,
+    &day13::Day13,
+    &day14::Day14,
+    &day15::Day15,
+    &day16::Day16,
+    &day17::Day17,
+    &day18::Day18,
+    &day19::Day19,
+    &day20::Day20,
+    &day21::Day21,
+    &day22::Day22,
+    &day23::Day23,
+    &day24::Day24,
+    &day25::Day25,
+];
+
+/// Every day's solution, ordered by day.
+pub fn solutions() -> &'static [&'static dyn DynSolution] {
+    &SOLUTIONS
+}
+
+pub fn solution(day: u8) -> Option<&'static dyn DynSolution> {
+    SOLUTIONS.iter().find(|s| s.day() == day).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input::{read_day, read_fixture};
+    use crate::registry::{solution, solutions};
+    use crate::trace::Trace;
+    use serde_json::Value;
+
+    /// Sorts the elements of every array, as sets and maps serialize in no particular order.
+    fn canonical(value: Value) -> Value {
+        match value {
+            Value::Array(items) => {
+                let mut items = items.into_iter().map(canonical).collect::<Vec<_>>();
+                items.sort_by_key(|item| item.to_string());
+                Value::Array(items)
+            }
+            Value::Object(fields) => Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(key, value)| (key, canonical(value)))
+                    .collect(),
+            ),
+            value => value,
+        }
+    }
+
+    #[test]
+    fn test_days_in_order() {
+        for (i, s) in solutions().iter().enumerate() {
+            assert_eq!(s.day() as usize, i + 1);
+        }
+        assert!(solution(0).is_none());
+        assert!(solution(26).is_none());
+    }
+
+    #[test]
+    fn test_run() {
+        let s = solution(8).unwrap();
+        let example = read_fixture("day8.txt");
+        assert_eq!(s.run(1, &example), Ok(Some("5".to_string())));
+        assert_eq!(s.run(2, &example), Ok(Some("8".to_string())));
+        assert_eq!(s.run(3, &example), Ok(None));
+        assert!(s.run(1, "nop +0\nhcf +1\n").is_err());
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        for s in solutions() {
+            let parsed = s.parse(&read_day(s.day())).unwrap();
+            let loaded = s.parse_bytes(&s.to_bytes(parsed.as_ref())).unwrap();
+            let value = |json: String| canonical(serde_json::from_str(&json).unwrap());
+            assert_eq!(
+                value(s.to_json(loaded.as_ref())),
+                value(s.to_json(parsed.as_ref())),
+                "Day {}",
+                s.day()
+            );
+        }
+        let error = solution(8).unwrap().parse_bytes(&[7]).err().unwrap();
+        assert!(error.starts_with("Invalid day 8 model: "), "{}", error);
+    }
+
+    #[test]
+    fn test_explain() {
+        for (day, part, traced) in [
+            (1, 1, false),
+            (8, 1, true),
+            (8, 2, true),
+            (16, 1, false),
+            (16, 2, true),
+            (20, 1, true),
+            (20, 2, true),
+            (22, 2, true),
+        ] {
+            let s = solution(day).unwrap();
+            let parsed = s.parse(&read_day(day)).unwrap();
+            let mut steps = 0;
+            let answer = s.explain(part, parsed.as_ref(), &mut Trace::to(|_| steps += 1));
+            assert_eq!(
+                answer,
+                s.solve(part, parsed.as_ref()),
+                "Day {} part {}",
+                day,
+                part
+            );
+            assert_eq!(steps > 0, traced, "Day {} part {}", day, part);
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        for s in solutions() {
+            let parsed = s.parse(&read_day(s.day())).unwrap();
+            let json = s.to_json(parsed.as_ref());
+            let reparsed = s.parse_json(&json).unwrap();
+            let value = |json: &str| canonical(serde_json::from_str(json).unwrap());
+            assert_eq!(value(&s.to_json(reparsed.as_ref())), value(&json));
+            assert_eq!(s.solve(1, reparsed.as_ref()), s.solve(1, parsed.as_ref()));
+        }
+
+        let s = solution(8).unwrap();
+        let parsed = s.parse_json(r#"[{"ACC": 3}, {"JMP": -1}]"#).unwrap();
+        assert_eq!(s.solve(1, parsed.as_ref()), Some("3".to_string()));
+        let error = s.parse_json(r#"[{"HCF": 1}]"#).err().unwrap();
+        assert!(error.starts_with("Invalid day 8 model: "), "{}", error);
+    }
+}
@@ -0,0 +1,510 @@
+//! A small HTTP service, built with the `server` feature, that solves puzzles for tools written in other languages.
+//!
+//! ```text
+//! POST /solve
+//! {"day": 1, "part": 2, "input": "1721\n979\n366\n299\n675\n1456\n"}
+//!
+//! {"day": 1, "part": 2, "answer": "241861950", "parse_error": null, "error": null, "timed_out": false,
+//!  "parse_us": 12, "solve_us": 3}
+//! ```
+use crate::budget::Budget;
+use crate::error::ParseError;
+use crate::registry;
+use crate::solution::DynSolution;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::SocketAddr;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// The address `aoc serve` listens on by default, which only accepts connections from this machine.
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:8020";
+
+/// How long the service lets a solver run before giving up on the request, unless `Service::with_time_limit` says
+/// otherwise.
+pub const SOLVE_TIME_LIMIT: Duration = Duration::from_secs(10);
+
+/// How many solvers may run at once, counting those still finishing requests that timed out. Parts that do not spend
+/// a budget cannot be stopped, so this keeps them from piling up.
+pub const MAX_WORKERS: usize = 4;
+
+/// The solvers running now, out of `MAX_WORKERS`.
+static LIVE_WORKERS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SolveRequest {
+    pub day: u8,
+    pub part: u8,
+    /// The raw puzzle input.
+    pub input: String,
+}
+
+/// Where and why the input could not be parsed. Lines and columns are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InputError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl From<ParseError> for InputError {
+    fn from(e: ParseError) -> InputError {
+        InputError {
+            line: e.line,
+            column: e.column,
+            message: e.message,
+        }
+    }
+}
+
+/// The outcome of a request. Times are in microseconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SolveResponse {
+    pub day: u8,
+    pub part: u8,
+    /// The answer, or `None` if the input has no solution or could not be solved.
+    pub answer: Option<String>,
+    pub parse_error: Option<InputError>,
+    /// Set if the parser or solver panicked, or the solver ran out of time.
+    pub error: Option<String>,
+    pub timed_out: bool,
+    pub parse_us: u128,
+    pub solve_us: u128,
+}
+
+/// Why a request was not solved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// There is no such day or part.
+    Invalid(String),
+    /// All the workers are still busy with earlier requests.
+    Busy,
+    /// The worker died without answering.
+    WorkerPanicked,
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolveError::Invalid(message) => write!(f, "{}", message),
+            SolveError::Busy => write!(f, "Too many requests are still being solved"),
+            SolveError::WorkerPanicked => write!(f, "The worker thread panicked"),
+        }
+    }
+}
+
+/// A worker's place among the live workers, given back when the worker finishes.
+struct WorkerSlot(&'static AtomicUsize);
+
+impl WorkerSlot {
+    /// Takes a place if fewer than `max` workers are live.
+    fn take(live: &'static AtomicUsize, max: usize) -> Option<WorkerSlot> {
+        live.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+            if n < max {
+                Some(n + 1)
+            } else {
+                None
+            }
+        })
+        .ok()
+        .map(|_| WorkerSlot(live))
+    }
+}
+
+impl Drop for WorkerSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Parses the input and solves the part with the day's solution from the registry, giving up after `SOLVE_TIME_LIMIT`.
+/// Fails if there is no such day or part, or if `MAX_WORKERS` solvers are still running.
+pub fn solve(request: &SolveRequest) -> Result<SolveResponse, SolveError> {
+    solve_with_time_limit(request, SOLVE_TIME_LIMIT)
+}
+
+/// Same as `solve`, giving up after `limit`. The request is solved on its own thread. The slow parts stop themselves
+/// when the limit passes; any other part still running then is left to finish in the background, and its answer is
+/// thrown away. It keeps its place among the `MAX_WORKERS` until it does.
+pub fn solve_with_time_limit(
+    request: &SolveRequest,
+    limit: Duration,
+) -> Result<SolveResponse, SolveError> {
+    solve_on_worker(request, limit, &LIVE_WORKERS, MAX_WORKERS)
+}
+
+/// Same as `solve_with_time_limit`, counting the workers in `live` and allowing at most `max`.
+fn solve_on_worker(
+    request: &SolveRequest,
+    limit: Duration,
+    live: &'static AtomicUsize,
+    max: usize,
+) -> Result<SolveResponse, SolveError> {
+    find_solution(request)?;
+    let slot = WorkerSlot::take(live, max).ok_or(SolveError::Busy)?;
+    let (sender, responses) = mpsc::channel();
+    let worker_request = request.clone();
+    let start = Instant::now();
+    thread::spawn(move || {
+        let response = solve_within(&worker_request, &mut Budget::time_limit(limit));
+        // The place is free as soon as the solver is done, before anyone hears about it.
+        drop(slot);
+        // Nobody is listening if the deadline has passed.
+        let _ = sender.send(response);
+    });
+    match responses.recv_timeout(limit) {
+        Ok(response) => response,
+        Err(RecvTimeoutError::Timeout) => Ok(SolveResponse {
+            day: request.day,
+            part: request.part,
+            answer: None,
+            parse_error: None,
+            error: Some(format!("Timed out after {} seconds", limit.as_secs_f64())),
+            timed_out: true,
+            parse_us: 0,
+            solve_us: start.elapsed().as_micros(),
+        }),
+        // `solve_within` catches the panics of the parser and solver, so this is a bug in the service itself.
+        Err(RecvTimeoutError::Disconnected) => Err(SolveError::WorkerPanicked),
+    }
+}
+
+/// Same as `solve`, solving on this thread and stopping the solver if the budget runs out. The parts that do not
+/// spend the budget run to the end.
+pub fn solve_within(
+    request: &SolveRequest,
+    budget: &mut Budget,
+) -> Result<SolveResponse, SolveError> {
+    let solution = find_solution(request)?;
+    let mut response = SolveResponse {
+        day: request.day,
+        part: request.part,
+        answer: None,
+        parse_error: None,
+        error: None,
+        timed_out: false,
+        parse_us: 0,
+        solve_us: 0,
+    };
+    let start = Instant::now();
+    let parsed = panic::catch_unwind(AssertUnwindSafe(|| solution.parse(&request.input)));
+    response.parse_us = start.elapsed().as_micros();
+    let parsed = match parsed {
+        Ok(Ok(parsed)) => parsed,
+        Ok(Err(e)) => {
+            response.parse_error = Some(e.into());
+            return Ok(response);
+        }
+        Err(_) => {
+            response.error = Some("Parser panicked".to_string());
+            return Ok(response);
+        }
+    };
+
+    let start = Instant::now();
+    let answer = panic::catch_unwind(AssertUnwindSafe(|| {
+        solution.solve_within(request.part, parsed.as_ref(), budget)
+    }));
+    response.solve_us = start.elapsed().as_micros();
+    match answer {
+        Ok(Ok(answer)) => response.answer = answer,
+        Ok(Err(interrupted)) => {
+            response.error = Some(interrupted.to_string());
+            response.timed_out = true;
+        }
+        Err(_) => response.error = Some("Solver panicked".to_string()),
+    }
+    Ok(response)
+}
+
+/// The solution for the day of the request, if there is one and it has the part.
+fn find_solution(request: &SolveRequest) -> Result<&'static dyn DynSolution, SolveError> {
+    let solution = registry::solution(request.day)
+        .ok_or_else(|| SolveError::Invalid(format!("Invalid day: {}", request.day)))?;
+    if request.part != 1 && request.part != 2 {
+        return Err(SolveError::Invalid(format!(
+            "Invalid part: {}",
+            request.part
+        )));
+    }
+    Ok(solution)
+}
+
+/// Serves `POST /solve` until stopped. Requests are handled one at a time, and each solver gets the time limit, so a
+/// slow request holds up the ones behind it for at most that long.
+pub struct Service {
+    server: Server,
+    time_limit: Duration,
+}
+
+impl Service {
+    pub fn bind(address: &str) -> Result<Service, String> {
+        Server::http(address)
+            .map(|server| Service {
+                server,
+                time_limit: SOLVE_TIME_LIMIT,
+            })
+            .map_err(|e| format!("Could not listen on {}: {}", address, e))
+    }
+
+    /// Gives each solver `limit` instead of `SOLVE_TIME_LIMIT`.
+    pub fn with_time_limit(self, limit: Duration) -> Service {
+        Service {
+            time_limit: limit,
+            ..self
+        }
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// Answers requests until `stop` is called.
+    pub fn run(&self) {
+        for request in self.server.incoming_requests() {
+            // The client may have gone away, and there is no one else to tell.
+            let _ = respond(request, self.time_limit);
+        }
+    }
+
+    /// Makes `run` return once the request in hand, if any, is answered.
+    pub fn stop(&self) {
+        self.server.unblock();
+    }
+}
+
+/// Answers a request. A solver that runs out of time is a 504 response, with the rest of the outcome as usual, and a
+/// request that finds every worker busy is a 503 response.
+fn respond(mut request: Request, time_limit: Duration) -> std::io::Result<()> {
+    let (status, body) = match (request.method(), request.url()) {
+        (Method::Post, "/solve") => {
+            let mut body = String::new();
+            match request.as_reader().read_to_string(&mut body) {
+                Ok(_) => match serde_json::from_str::<SolveRequest>(&body) {
+                    Ok(solve_request) => match solve_with_time_limit(&solve_request, time_limit) {
+                        Ok(response) if response.timed_out => (504, to_json(&response)),
+                        Ok(response) => (200, to_json(&response)),
+                        Err(e @ SolveError::Invalid(_)) => (400, error_json(&e.to_string())),
+                        Err(e @ SolveError::Busy) => (503, error_json(&e.to_string())),
+                        Err(e @ SolveError::WorkerPanicked) => (500, error_json(&e.to_string())),
+                    },
+                    Err(e) => (400, error_json(&format!("Invalid request: {}", e))),
+                },
+                Err(e) => (
+                    400,
+                    error_json(&format!("Could not read the request: {}", e)),
+                ),
+            }
+        }
+        (_, "/solve") => (405, error_json("Expected a POST")),
+        (_, url) => (404, error_json(&format!("Not found: {}", url))),
+    };
+    let content_type =
+        Header::from_bytes("Content-Type", "application/json").expect("The header is valid ASCII");
+    request.respond(
+        Response::from_string(body)
+            .with_status_code(status)
+            .with_header(content_type),
+    )
+}
+
+fn to_json(response: &SolveResponse) -> String {
+    serde_json::to_string(response).expect("Responses are always serializable")
+}
+
+fn error_json(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::budget::Budget;
+    use crate::generate::{self, Rng};
+    use crate::input::read_fixture;
+    use crate::server::{
+        solve, solve_on_worker, solve_with_time_limit, solve_within, Service, SolveError,
+        SolveRequest, SOLVE_TIME_LIMIT,
+    };
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    fn request(day: u8, part: u8, input: &str) -> SolveRequest {
+        SolveRequest {
+            day,
+            part,
+            input: input.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_solve() {
+        let response = solve(&request(1, 2, &read_fixture("day1.txt"))).unwrap();
+        assert_eq!(response.answer, Some("241861950".to_string()));
+        assert_eq!((response.parse_error, response.error), (None, None));
+
+        let response = solve(&request(8, 1, "nop +0\nhcf +1\n")).unwrap();
+        let error = response.parse_error.unwrap();
+        assert_eq!((error.line, error.column), (2, 1));
+        assert_eq!(error.message, "Unexpected operation: hcf");
+        assert_eq!(response.answer, None);
+
+        assert_eq!(
+            solve(&request(26, 1, "")),
+            Err(SolveError::Invalid("Invalid day: 26".to_string()))
+        );
+        assert_eq!(
+            solve(&request(1, 3, "")),
+            Err(SolveError::Invalid("Invalid part: 3".to_string()))
+        );
+    }
+
+    /// Sends a request and returns the status code and body of the response.
+    fn send(address: SocketAddr, method: &str, path: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, body.to_string())
+    }
+
+    #[test]
+    fn test_service() {
+        let service = Arc::new(Service::bind("127.0.0.1:0").unwrap());
+        let address = service.local_addr().unwrap();
+        let running = Arc::clone(&service);
+        let handle = thread::spawn(move || running.run());
+
+        let body = serde_json::json!({"day": 8, "part": 2, "input": read_fixture("day8.txt")});
+        let (status, body) = send(address, "POST", "/solve", &body.to_string());
+        assert_eq!(status, 200);
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["answer"], "8");
+        assert!(json["parse_error"].is_null());
+        assert_eq!(json["timed_out"], false);
+        assert!(json["solve_us"].is_u64());
+
+        let (status, body) = send(
+            address,
+            "POST",
+            "/solve",
+            r#"{"day": 8, "part": 1, "input": "jmp 4"}"#,
+        );
+        assert_eq!(status, 200);
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["parse_error"]["column"], 5);
+
+        let (status, body) = send(
+            address,
+            "POST",
+            "/solve",
+            r#"{"day": 30, "part": 1, "input": ""}"#,
+        );
+        assert_eq!(status, 400);
+        assert!(body.contains("Invalid day: 30"));
+        assert_eq!(send(address, "POST", "/solve", "{").0, 400);
+        assert_eq!(send(address, "GET", "/solve", "").0, 405);
+        assert_eq!(send(address, "GET", "/", "").0, 404);
+
+        service.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_timeout() {
+        // Day 15 part 2 plays 30 million rounds, which takes longer than no time at all.
+        let slow = request(15, 2, &read_fixture("day15.txt"));
+        let response = solve_within(&slow, &mut Budget::time_limit(Duration::ZERO)).unwrap();
+        assert!(response.timed_out);
+        assert_eq!(response.answer, None);
+        assert!(response.error.unwrap().starts_with("Timed out after "));
+
+        let service = Service::bind("127.0.0.1:0")
+            .unwrap()
+            .with_time_limit(Duration::from_millis(500));
+        let service = Arc::new(service);
+        let address = service.local_addr().unwrap();
+        let running = Arc::clone(&service);
+        let handle = thread::spawn(move || running.run());
+
+        let body = serde_json::json!({"day": 15, "part": 2, "input": slow.input});
+        let (status, body) = send(address, "POST", "/solve", &body.to_string());
+        assert_eq!(status, 504);
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["timed_out"], true);
+        assert!(json["answer"].is_null());
+
+        // Parts that finish within the limit are still answered.
+        let body = serde_json::json!({"day": 1, "part": 1, "input": read_fixture("day1.txt")});
+        let (status, body) = send(address, "POST", "/solve", &body.to_string());
+        assert_eq!(status, 200);
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["answer"], "514579");
+
+        service.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_time_limit_without_budget() {
+        // Day 11 does not count its steps, and takes seconds to settle an 80x80 room in a debug build.
+        let room = generate::day11(&mut Rng::new(1), 80);
+        let response = solve_with_time_limit(&request(11, 2, &room), Duration::ZERO).unwrap();
+        assert!(response.timed_out);
+        assert_eq!(response.answer, None);
+        assert_eq!(
+            response.error,
+            Some("Timed out after 0 seconds".to_string())
+        );
+
+        let response = solve_with_time_limit(
+            &request(11, 2, &read_fixture("day11.txt")),
+            SOLVE_TIME_LIMIT,
+        );
+        assert_eq!(response.unwrap().answer, Some("26".to_string()));
+        assert_eq!(
+            solve_with_time_limit(&request(26, 1, ""), Duration::ZERO),
+            Err(SolveError::Invalid("Invalid day: 26".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_busy() {
+        // A worker of its own, so that the other tests' solvers do not count.
+        static WORKERS: AtomicUsize = AtomicUsize::new(0);
+        let room = generate::day11(&mut Rng::new(1), 80);
+        let day1 = request(1, 1, &read_fixture("day1.txt"));
+        let response = solve_on_worker(&request(11, 2, &room), Duration::ZERO, &WORKERS, 1);
+        assert!(response.unwrap().timed_out);
+
+        // The room is still being settled in the background, and takes the only worker until it is.
+        assert_eq!(
+            solve_on_worker(&day1, SOLVE_TIME_LIMIT, &WORKERS, 1),
+            Err(SolveError::Busy)
+        );
+        while WORKERS.load(Ordering::SeqCst) > 0 {
+            thread::sleep(Duration::from_millis(10));
+        }
+        let response = solve_on_worker(&day1, SOLVE_TIME_LIMIT, &WORKERS, 1).unwrap();
+        assert_eq!(response.answer, Some("514579".to_string()));
+        assert_eq!(WORKERS.load(Ordering::SeqCst), 0);
+    }
+}
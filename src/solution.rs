@@ -0,0 +1,175 @@
+use crate::budget::{Budget, Interrupted};
+use crate::error::ParseError;
+use crate::trace::Trace;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::any::{type_name, Any};
+
+/// A day's puzzle: parses the raw input once and solves both parts from the parsed form.
+///
+/// Parsing fails on malformed input. Each part returns `None` if the input has no solution.
+pub trait Solution {
+    const DAY: u8;
+    /// The parsed input, which round-trips through JSON so that it can be inspected and produced outside Rust.
+    type Input: Serialize + DeserializeOwned + 'static;
+    /// The version of `Input`'s layout. Bump it whenever `Input` or a type inside it changes, so that cached entries
+    /// written with the old layout are rebuilt instead of decoded into the wrong values.
+    const MODEL_VERSION: u32 = 1;
+
+    fn parse(&self, input: &str) -> Result<Self::Input, ParseError>;
+    fn part1(&self, input: &Self::Input) -> Option<String>;
+    fn part2(&self, input: &Self::Input) -> Option<String>;
+
+    /// Solves a part, stopping if the budget runs out. Only the slow parts spend the budget; the rest ignore it.
+    fn solve_within(
+        &self,
+        part: u8,
+        input: &Self::Input,
+        _budget: &mut Budget,
+    ) -> Result<Option<String>, Interrupted> {
+        Ok(match part {
+            1 => self.part1(input),
+            2 => self.part2(input),
+            _ => None,
+        })
+    }
+
+    /// Solves a part, recording the steps that lead to the answer. Parts without interesting steps record nothing.
+    fn explain(&self, part: u8, input: &Self::Input, _trace: &mut Trace) -> Option<String> {
+        match part {
+            1 => self.part1(input),
+            2 => self.part2(input),
+            _ => None,
+        }
+    }
+}
+
+/// A solution that can back its answers with a certificate: evidence, such as the entries that add up to 2020, that
+/// a checker validates against the input without trusting the solver.
+pub trait Certify: Solution {
+    type Certificate;
+
+    /// Returns `None` if the part has no solution or does not produce certificates.
+    fn certify(&self, part: u8, input: &Self::Input) -> Option<Self::Certificate>;
+
+    /// Checks the certificate against the input, returning the answer it proves or why it is wrong.
+    fn check(
+        &self,
+        part: u8,
+        input: &Self::Input,
+        certificate: &Self::Certificate,
+    ) -> Result<String, String>;
+
+    /// A short human readable form of the certificate.
+    fn describe(&self, certificate: &Self::Certificate) -> String;
+}
+
+/// Object-safe view of a `Solution` so that every day can be stored in the same registry.
+pub trait DynSolution: Sync {
+    fn day(&self) -> u8;
+    fn parse(&self, input: &str) -> Result<Box<dyn Any>, ParseError>;
+    fn solve(&self, part: u8, input: &dyn Any) -> Option<String>;
+    fn solve_within(
+        &self,
+        part: u8,
+        input: &dyn Any,
+        budget: &mut Budget,
+    ) -> Result<Option<String>, Interrupted>;
+    fn explain(&self, part: u8, input: &dyn Any, trace: &mut Trace) -> Option<String>;
+
+    /// Pretty-printed JSON of a parsed input.
+    fn to_json(&self, input: &dyn Any) -> String;
+    /// Reads a parsed input back from JSON, ready to be solved.
+    fn parse_json(&self, json: &str) -> Result<Box<dyn Any>, String>;
+    /// A compact binary encoding of a parsed input, for caching.
+    fn to_bytes(&self, input: &dyn Any) -> Vec<u8>;
+    /// Reads a parsed input back from `to_bytes`.
+    fn parse_bytes(&self, bytes: &[u8]) -> Result<Box<dyn Any>, String>;
+    /// The name of the parsed input's type.
+    fn model_name(&self) -> &'static str;
+    /// The version of the parsed input's layout.
+    fn model_version(&self) -> u32;
+
+    fn run(&self, part: u8, input: &str) -> Result<Option<String>, ParseError> {
+        Ok(self.solve(part, self.parse(input)?.as_ref()))
+    }
+}
+
+impl<S: Solution + Sync> DynSolution for S {
+    fn day(&self) -> u8 {
+        S::DAY
+    }
+
+    fn parse(&self, input: &str) -> Result<Box<dyn Any>, ParseError> {
+        Ok(Box::new(Solution::parse(self, input)?))
+    }
+
+    fn solve(&self, part: u8, input: &dyn Any) -> Option<String> {
+        let input = input
+            .downcast_ref::<S::Input>()
+            .expect("Input was not parsed by this solution");
+        match part {
+            1 => self.part1(input),
+            2 => self.part2(input),
+            _ => None,
+        }
+    }
+
+    fn solve_within(
+        &self,
+        part: u8,
+        input: &dyn Any,
+        budget: &mut Budget,
+    ) -> Result<Option<String>, Interrupted> {
+        let input = input
+            .downcast_ref::<S::Input>()
+            .expect("Input was not parsed by this solution");
+        Solution::solve_within(self, part, input, budget)
+    }
+
+    fn explain(&self, part: u8, input: &dyn Any, trace: &mut Trace) -> Option<String> {
+        let input = input
+            .downcast_ref::<S::Input>()
+            .expect("Input was not parsed by this solution");
+        Solution::explain(self, part, input, trace)
+    }
+
+    fn to_json(&self, input: &dyn Any) -> String {
+        let input = input
+            .downcast_ref::<S::Input>()
+            .expect("Input was not parsed by this solution");
+        serde_json::to_string_pretty(input).expect("Parsed inputs are always serializable")
+    }
+
+    fn parse_json(&self, json: &str) -> Result<Box<dyn Any>, String> {
+        serde_json::from_str::<S::Input>(json)
+            .map(|input| Box::new(input) as Box<dyn Any>)
+            .map_err(|e| format!("Invalid day {} model: {}", S::DAY, e))
+    }
+
+    fn to_bytes(&self, input: &dyn Any) -> Vec<u8> {
+        let input = input
+            .downcast_ref::<S::Input>()
+            .expect("Input was not parsed by this solution");
+        postcard::to_stdvec(input).expect("Parsed inputs are always serializable")
+    }
+
+    fn parse_bytes(&self, bytes: &[u8]) -> Result<Box<dyn Any>, String> {
+        postcard::from_bytes::<S::Input>(bytes)
+            .map(|input| Box::new(input) as Box<dyn Any>)
+            .map_err(|e| format!("Invalid day {} model: {}", S::DAY, e))
+    }
+
+    fn model_name(&self) -> &'static str {
+        type_name::<S::Input>()
+    }
+
+    fn model_version(&self) -> u32 {
+        S::MODEL_VERSION
+    }
+}
+
+/// Splits the raw input into owned lines, which is what most of the day parsers consume.
+pub fn lines(input: &str) -> impl Iterator<Item = String> + '_ {
+    input.lines().map(|line| line.to_string())
+}
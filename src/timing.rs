@@ -0,0 +1,215 @@
+use crate::cache::parse_cached;
+use crate::input::InputSource;
+use crate::solution::DynSolution;
+use serde::Serialize;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The answer to one part of a day and how long it took. Each part parses its own input, so both steps are timed on
+/// the same thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timing {
+    pub day: u8,
+    pub part: u8,
+    /// The answer, or why there is none: the input could not be read or parsed, or the solver panicked.
+    pub answer: Result<Option<String>, String>,
+    pub parse_time: Duration,
+    pub solve_time: Duration,
+}
+
+/// Reads the day's input from the input directory, then parses it, through the cache if `AOC_CACHE_DIR` is set, and
+/// solves the part.
+pub fn time_part(solution: &dyn DynSolution, part: u8) -> Timing {
+    let day = solution.day();
+    let mut timing = Timing {
+        day,
+        part,
+        answer: Ok(None),
+        parse_time: Duration::default(),
+        solve_time: Duration::default(),
+    };
+    let source = InputSource::Day(day);
+    let input = match source.read() {
+        Ok(input) => input,
+        Err(e) => {
+            timing.answer = Err(format!("Could not read {}: {}", source, e));
+            return timing;
+        }
+    };
+
+    let start = Instant::now();
+    let parsed = panic::catch_unwind(AssertUnwindSafe(|| parse_cached(solution, &input)));
+    timing.parse_time = start.elapsed();
+    let parsed = match parsed {
+        Ok(Ok(parsed)) => parsed,
+        Ok(Err(e)) => {
+            timing.answer = Err(e.to_string());
+            return timing;
+        }
+        Err(_) => {
+            timing.answer = Err("Parser panicked".to_string());
+            return timing;
+        }
+    };
+
+    let start = Instant::now();
+    let answer = panic::catch_unwind(AssertUnwindSafe(|| solution.solve(part, parsed.as_ref())));
+    timing.solve_time = start.elapsed();
+    timing.answer = answer.map_err(|_| "Solver panicked".to_string());
+    timing
+}
+
+/// Times both parts of every solution on a pool of `threads` threads. The results are ordered by day and part.
+pub fn time_all(solutions: &[&dyn DynSolution], threads: usize) -> Vec<Timing> {
+    let jobs: Vec<(&dyn DynSolution, u8)> = solutions
+        .iter()
+        .flat_map(|solution| (1..=2).map(move |part| (*solution, part)))
+        .collect();
+    let next = AtomicUsize::new(0);
+    let timings = Mutex::new(Vec::with_capacity(jobs.len()));
+
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| {
+                while let Some((solution, part)) = jobs.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let timing = time_part(*solution, *part);
+                    timings.lock().unwrap().push(timing);
+                }
+            });
+        }
+    });
+
+    let mut timings = timings.into_inner().unwrap();
+    timings.sort_by_key(|timing| (timing.day, timing.part));
+    timings
+}
+
+/// Formats the timings as a table with a summary line, where `elapsed` is the wall clock time of the whole run.
+pub fn render_table(timings: &[Timing], elapsed: Duration) -> String {
+    let answers: Vec<String> = timings
+        .iter()
+        .map(|timing| match &timing.answer {
+            Ok(Some(answer)) => answer.clone(),
+            Ok(None) => "-".to_string(),
+            Err(e) => format!("ERROR ({})", e),
+        })
+        .collect();
+    let width = answers.iter().map(|a| a.len()).max().unwrap_or(0).max(6);
+
+    let mut lines = vec![format!(
+        "Day Part {:<width$} {:>12} {:>12}",
+        "Answer",
+        "Parse",
+        "Solve",
+        width = width
+    )];
+    for (timing, answer) in timings.iter().zip(answers.iter()) {
+        lines.push(format!(
+            "{:>3} {:>4} {:<width$} {:>9.3} ms {:>9.3} ms",
+            timing.day,
+            timing.part,
+            answer,
+            millis(timing.parse_time),
+            millis(timing.solve_time),
+            width = width
+        ));
+    }
+
+    let total: Duration = timings.iter().map(|t| t.parse_time + t.solve_time).sum();
+    let failed = timings.iter().filter(|t| t.answer.is_err()).count();
+    lines.push(format!(
+        "{} parts, {} failed, {:.3} ms of work in {:.3} ms",
+        timings.len(),
+        failed,
+        millis(total),
+        millis(elapsed)
+    ));
+    lines.join("\n")
+}
+
+/// A row of the JSON report. Times are in microseconds.
+#[derive(Serialize)]
+struct Record<'a> {
+    day: u8,
+    part: u8,
+    answer: Option<&'a str>,
+    error: Option<&'a str>,
+    parse_us: u128,
+    solve_us: u128,
+}
+
+/// Formats the timings as a JSON array with one object per part.
+pub fn to_json(timings: &[Timing]) -> String {
+    let records: Vec<Record> = timings
+        .iter()
+        .map(|timing| Record {
+            day: timing.day,
+            part: timing.part,
+            answer: timing.answer.as_ref().ok().and_then(|a| a.as_deref()),
+            error: timing.answer.as_ref().err().map(|e| e.as_str()),
+            parse_us: timing.parse_time.as_micros(),
+            solve_us: timing.solve_time.as_micros(),
+        })
+        .collect();
+    serde_json::to_string_pretty(&records).expect("Timings are always serializable")
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::registry::solutions;
+    use crate::timing::{render_table, time_all, to_json, Timing};
+    use std::time::Duration;
+
+    #[test]
+    fn test_time_all() {
+        let timings = time_all(&solutions()[..10], 4);
+        assert_eq!(timings.len(), 20);
+        for (i, timing) in timings.iter().enumerate() {
+            assert_eq!(
+                (timing.day, timing.part),
+                (i as u8 / 2 + 1, i as u8 % 2 + 1)
+            );
+        }
+        assert_eq!(timings[0].answer, Ok(Some("970816".to_string())));
+        assert!(timings.iter().all(|t| matches!(t.answer, Ok(Some(_)))));
+    }
+
+    #[test]
+    fn test_reports() {
+        let timings = vec![
+            Timing {
+                day: 25,
+                part: 1,
+                answer: Ok(Some("42".to_string())),
+                parse_time: Duration::from_micros(1500),
+                solve_time: Duration::from_micros(250),
+            },
+            Timing {
+                day: 25,
+                part: 2,
+                answer: Ok(None),
+                parse_time: Duration::from_micros(10),
+                solve_time: Duration::from_micros(0),
+            },
+        ];
+
+        let table = render_table(&timings, Duration::from_millis(2));
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], " 25    1 42         1.500 ms     0.250 ms");
+        assert_eq!(lines[3], "2 parts, 0 failed, 1.760 ms of work in 2.000 ms");
+
+        let json: serde_json::Value = serde_json::from_str(&to_json(&timings)).unwrap();
+        assert_eq!(json[0]["answer"], "42");
+        assert_eq!(json[0]["parse_us"], 1500);
+        assert!(json[1]["answer"].is_null());
+        assert!(json[1]["error"].is_null());
+    }
+}
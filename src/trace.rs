@@ -0,0 +1,161 @@
+//! Step-by-step accounts of how the solvers reach their answers, for finding the step where a wrong answer diverged.
+//! Solvers that support tracing take a `Trace` and describe each step as an `Event`, which is only built if the trace
+//! is recording.
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::Value;
+use std::fmt;
+
+/// One step of a solver: what kind of step it was, such as `place` or `execute`, and named values describing it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub step: &'static str,
+    pub fields: Vec<(&'static str, Value)>,
+}
+
+impl Event {
+    pub fn new(step: &'static str) -> Event {
+        Event {
+            step,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Adds a field, keeping the order in which fields are added.
+    pub fn with(mut self, name: &'static str, value: impl Serialize) -> Event {
+        let value = serde_json::to_value(value).expect("Event fields are always serializable");
+        self.fields.push((name, value));
+        self
+    }
+
+    /// The value of the field, if the event has it.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// `step name=value name=value`, with strings unquoted.
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.step)?;
+        for (name, value) in &self.fields {
+            match value {
+                Value::String(s) => write!(f, " {}={}", name, s)?,
+                value => write!(f, " {}={}", name, value)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An object with the step under `step` followed by the fields.
+impl Serialize for Event {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.fields.len() + 1))?;
+        map.serialize_entry("step", self.step)?;
+        for (name, value) in &self.fields {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+/// Where a solver sends its events, if anywhere.
+pub struct Trace<'a> {
+    sink: Option<Box<dyn FnMut(Event) + 'a>>,
+}
+
+impl<'a> Trace<'a> {
+    /// A trace that records nothing, which costs the solver nothing.
+    pub fn off() -> Trace<'a> {
+        Trace { sink: None }
+    }
+
+    /// A trace that passes every event to `sink`.
+    pub fn to(sink: impl FnMut(Event) + 'a) -> Trace<'a> {
+        Trace {
+            sink: Some(Box::new(sink)),
+        }
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Records the event made by `event`, which is only called if the trace is on.
+    pub fn emit(&mut self, event: impl FnOnce() -> Event) {
+        if let Some(sink) = self.sink.as_mut() {
+            sink(event());
+        }
+    }
+}
+
+/// Formats the events as a JSON array with one event per line, which keeps long traces compact but easy to grep.
+pub fn to_json(events: &[Event]) -> String {
+    let lines = events
+        .iter()
+        .map(|event| serde_json::to_string(event).expect("Events are always serializable"))
+        .collect::<Vec<_>>();
+    if lines.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n{}\n]", lines.join(",\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::trace::{to_json, Event, Trace};
+    use serde_json::json;
+
+    #[test]
+    fn test_event() {
+        let event = Event::new("place")
+            .with("tile", 2311)
+            .with("side", "left")
+            .with("decks", vec![1, 2]);
+        assert_eq!(event.to_string(), "place tile=2311 side=left decks=[1,2]");
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"step":"place","tile":2311,"side":"left","decks":[1,2]}"#
+        );
+        assert_eq!(event.get("tile"), Some(&json!(2311)));
+        assert_eq!(event.get("row"), None);
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut events = Vec::new();
+        let mut trace = Trace::to(|event| events.push(event));
+        assert!(trace.is_on());
+        trace.emit(|| Event::new("first"));
+        trace.emit(|| Event::new("second").with("n", 2));
+        drop(trace);
+        assert_eq!(
+            events,
+            vec![Event::new("first"), Event::new("second").with("n", 2)]
+        );
+
+        let mut trace = Trace::off();
+        assert!(!trace.is_on());
+        trace.emit(|| unreachable!());
+    }
+
+    #[test]
+    fn test_to_json() {
+        let events = vec![
+            Event::new("execute").with("index", 0),
+            Event::new("terminate").with("acc", 8),
+        ];
+        let json = to_json(&events);
+        assert_eq!(
+            json,
+            "[\n{\"step\":\"execute\",\"index\":0},\n{\"step\":\"terminate\",\"acc\":8}\n]"
+        );
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed[1]["acc"], 8);
+        assert_eq!(to_json(&[]), "[]");
+    }
+}
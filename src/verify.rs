@@ -0,0 +1,238 @@
+use crate::input::InputSource;
+use crate::registry;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// An expected answer from the manifest. `answer` is `None` for parts that have no solution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub day: u8,
+    pub part: u8,
+    pub input: PathBuf,
+    pub answer: Option<String>,
+}
+
+/// Parses a manifest with one `<day> <part> <input> <answer>` entry per line, where an answer of `-` means that the
+/// part has no solution. Input paths are relative to `base`. Blank lines and lines starting with `#` are skipped.
+pub fn parse_manifest(text: &str, base: &Path) -> Result<Vec<Entry>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| {
+            let error = |message: &str| format!("Manifest line {}: {}", i + 1, message);
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (day, part, input, answer) = match fields.as_slice() {
+                [day, part, input, answer] => (day, part, input, answer),
+                _ => return Err(error("Expected '<day> <part> <input> <answer>'")),
+            };
+            Ok(Entry {
+                day: day.parse().map_err(|_| error("Invalid day"))?,
+                part: part.parse().map_err(|_| error("Invalid part"))?,
+                input: base.join(input),
+                answer: Some(answer.to_string()).filter(|answer| answer != "-"),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    /// The solver gave a different answer or could not parse the input.
+    Fail(String),
+    /// The input could not be read, or the manifest has no entry for a registered day and part.
+    Missing(String),
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Pass => write!(f, "pass"),
+            Status::Fail(reason) => write!(f, "FAIL ({})", reason),
+            Status::Missing(reason) => write!(f, "missing ({})", reason),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub day: u8,
+    pub part: u8,
+    pub input: Option<PathBuf>,
+    pub status: Status,
+}
+
+pub fn check(entry: &Entry) -> Status {
+    let solution = match registry::solution(entry.day) {
+        Some(solution) if entry.part == 1 || entry.part == 2 => solution,
+        _ => return Status::Fail("No solver for this day and part".to_string()),
+    };
+    let source = InputSource::Path(entry.input.clone());
+    let input = match source.read() {
+        Ok(input) => input,
+        Err(e) => return Status::Missing(format!("Could not read {}: {}", source, e)),
+    };
+    match solution.run(entry.part, &input) {
+        Ok(answer) if answer == entry.answer => Status::Pass,
+        Ok(answer) => Status::Fail(format!(
+            "expected {}, got {}",
+            entry.answer.as_deref().unwrap_or("no solution"),
+            answer.as_deref().unwrap_or("no solution")
+        )),
+        Err(e) => Status::Fail(e.to_string()),
+    }
+}
+
+/// Checks every entry in order, then reports the registered days and parts that have no entry as missing.
+pub fn verify(entries: &[Entry]) -> Vec<Report> {
+    let mut reports = entries
+        .iter()
+        .map(|entry| Report {
+            day: entry.day,
+            part: entry.part,
+            input: Some(entry.input.clone()),
+            status: check(entry),
+        })
+        .collect::<Vec<_>>();
+
+    for solution in registry::solutions() {
+        for part in 1..=2 {
+            let day = solution.day();
+            if !entries.iter().any(|e| e.day == day && e.part == part) {
+                reports.push(Report {
+                    day,
+                    part,
+                    input: None,
+                    status: Status::Missing("No expected answer".to_string()),
+                });
+            }
+        }
+    }
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input::{day_path, fixture_dir, input_dir};
+    use crate::verify::{check, parse_manifest, verify, Entry, Status};
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = "
+# day part input answer
+1 1 day1.txt 970816
+
+25 2 day25.txt -
+";
+        let entries = parse_manifest(manifest, Path::new("input")).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                Entry {
+                    day: 1,
+                    part: 1,
+                    input: Path::new("input/day1.txt").to_path_buf(),
+                    answer: Some("970816".to_string()),
+                },
+                Entry {
+                    day: 25,
+                    part: 2,
+                    input: Path::new("input/day25.txt").to_path_buf(),
+                    answer: None,
+                },
+            ]
+        );
+
+        let err = parse_manifest("1 1 day1.txt\n", Path::new("input")).unwrap_err();
+        assert_eq!(
+            err,
+            "Manifest line 1: Expected '<day> <part> <input> <answer>'"
+        );
+        assert!(parse_manifest("x 1 day1.txt 1\n", Path::new("input")).is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let entry = |part, input: &str, answer: &str| Entry {
+            day: 1,
+            part,
+            input: input_dir().join(input),
+            answer: Some(answer.to_string()),
+        };
+        assert_eq!(check(&entry(1, "day1.txt", "970816")), Status::Pass);
+        assert_eq!(
+            check(&entry(2, "day1.txt", "1")),
+            Status::Fail("expected 1, got 96047280".to_string())
+        );
+        assert!(matches!(
+            check(&entry(1, "no-such-input.txt", "1")),
+            Status::Missing(_)
+        ));
+        assert!(matches!(check(&entry(3, "day1.txt", "1")), Status::Fail(_)));
+    }
+
+    #[test]
+    fn test_verify_reports_missing_entries() {
+        let entries = vec![Entry {
+            day: 1,
+            part: 1,
+            input: day_path(1),
+            answer: Some("970816".to_string()),
+        }];
+        let reports = verify(&entries);
+        assert_eq!(reports.len(), 50);
+        assert_eq!(reports[0].status, Status::Pass);
+        assert_eq!((reports[1].day, reports[1].part), (1, 2));
+        assert!(reports[1..]
+            .iter()
+            .all(|report| matches!(report.status, Status::Missing(_))));
+    }
+
+    /// Runs every example in the fixture directory through its day's solver. Adding an example only takes a file and
+    /// a line in `answers.txt`.
+    #[test]
+    fn test_fixtures() {
+        let dir = fixture_dir();
+        let manifest = fs::read_to_string(dir.join("answers.txt")).unwrap();
+        let entries = parse_manifest(&manifest, &dir).unwrap();
+        let failures = entries
+            .iter()
+            .filter_map(|entry| match check(entry) {
+                Status::Pass => None,
+                status => Some(format!(
+                    "Day {} part {} {}: {}",
+                    entry.day,
+                    entry.part,
+                    entry.input.display(),
+                    status
+                )),
+            })
+            .collect::<Vec<_>>();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+
+        // The day 9 example uses a preamble of 5 where the solver uses the puzzle's 25, and the part 2 examples of day 11
+        // only count what one seat can see, so `day9` and `day11` test them instead.
+        let unlisted = [
+            "answers.txt",
+            "day9.txt",
+            "day11-2.txt",
+            "day11-3.txt",
+            "day11-4.txt",
+        ];
+        for file in fs::read_dir(&dir).unwrap() {
+            let path = file.unwrap().path();
+            if !unlisted
+                .iter()
+                .any(|name| path.file_name().unwrap() == *name)
+            {
+                assert!(
+                    entries.iter().any(|entry| entry.input == path),
+                    "{} has no answers",
+                    path.display()
+                );
+            }
+        }
+    }
+}